@@ -21,7 +21,7 @@ async fn main() -> Result<()> {
         println!("  Name: {}", lat_var.name);
         println!("  Shape: {:?}", lat_var.shape);
         println!("  Dtype: {}", lat_var.dtype);
-        println!("  Compressor: {:?}", lat_var.compressor);
+        println!("  Compressor: {:?}", lat_var.codecs.compressor());
 
         match store.read_coordinate_data(lat_var).await {
             Ok(data) => {
@@ -56,7 +56,7 @@ async fn main() -> Result<()> {
         println!("  Name: {}", lon_var.name);
         println!("  Shape: {:?}", lon_var.shape);
         println!("  Dtype: {}", lon_var.dtype);
-        println!("  Compressor: {:?}", lon_var.compressor);
+        println!("  Compressor: {:?}", lon_var.codecs.compressor());
 
         match store.read_coordinate_data(lon_var).await {
             Ok(data) => {
@@ -91,7 +91,7 @@ async fn main() -> Result<()> {
         println!("  Name: {}", plev_var.name);
         println!("  Shape: {:?}", plev_var.shape);
         println!("  Dtype: {}", plev_var.dtype);
-        println!("  Compressor: {:?}", plev_var.compressor);
+        println!("  Compressor: {:?}", plev_var.codecs.compressor());
 
         match store.read_coordinate_data(plev_var).await {
             Ok(data) => {
@@ -126,7 +126,7 @@ async fn main() -> Result<()> {
         println!("  Name: {}", time_var.name);
         println!("  Shape: {:?}", time_var.shape);
         println!("  Dtype: {}", time_var.dtype);
-        println!("  Compressor: {:?}", time_var.compressor);
+        println!("  Compressor: {:?}", time_var.codecs.compressor());
 
         match store.read_coordinate_data(time_var).await {
             Ok(data) => {
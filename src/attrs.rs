@@ -0,0 +1,402 @@
+//! Attribute editing for the `attrs` subcommand (like `ncatted` for netCDF files)
+
+use crate::diff::{ChangeKind, Difference};
+use crate::fsutil::write_atomically;
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// How the value of `set` and `append` is interpreted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    /// An integer, a finite float, `true`/`false`, or else a string
+    Auto,
+    String,
+    /// Integers, comma separated for a list
+    Int,
+    /// Floats, comma separated for a list
+    Float,
+    /// `true`/`false`, comma separated for a list
+    Bool,
+    /// Any JSON value, e.g. `[0, 100]` or `{"a": 1}`
+    Json,
+}
+
+/// An edit of one attribute
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edit {
+    /// Create or overwrite the attribute
+    Set(Value),
+    Delete,
+    /// Rename the attribute, keeping its value
+    Rename(String),
+    /// Append to a string attribute, or add values to a list (creating the attribute if needed)
+    Append(Value),
+}
+
+/// Split a `VAR:ATTR` target into the variable (or group) and the attribute name; an empty
+/// variable selects the global attributes
+pub fn parse_target(target: &str) -> Result<(&str, &str)> {
+    match target.split_once(':') {
+        Some((node, name)) if !name.is_empty() => Ok((node.trim_matches('/'), name)),
+        _ => Err(anyhow::anyhow!(
+            "Invalid attribute '{}': expected VAR:ATTR (or :ATTR for a global attribute)",
+            target
+        )),
+    }
+}
+
+fn parse_list(text: &str, parse: impl Fn(&str) -> Option<Value>, type_name: &str) -> Result<Value> {
+    let values = text
+        .split(',')
+        .map(|item| {
+            parse(item.trim())
+                .ok_or_else(|| anyhow::anyhow!("Invalid {} value '{}'", type_name, item.trim()))
+        })
+        .collect::<Result<Vec<Value>>>()?;
+    Ok(match <[Value; 1]>::try_from(values) {
+        Ok([value]) => value,
+        Err(values) => Value::Array(values),
+    })
+}
+
+fn parse_int(text: &str) -> Option<Value> {
+    text.parse::<i64>().ok().map(Value::from)
+}
+
+fn parse_float(text: &str) -> Option<Value> {
+    // JSON has no NaN or infinity
+    let value = text.parse::<f64>().ok().filter(|v| v.is_finite())?;
+    serde_json::Number::from_f64(value).map(Value::Number)
+}
+
+fn parse_bool(text: &str) -> Option<Value> {
+    text.parse::<bool>().ok().map(Value::Bool)
+}
+
+/// Parse an attribute value given on the command line
+pub fn parse_value(text: &str, value_type: ValueType) -> Result<Value> {
+    match value_type {
+        ValueType::Auto => Ok(parse_int(text)
+            .or_else(|| parse_float(text))
+            .or_else(|| parse_bool(text))
+            .unwrap_or_else(|| Value::String(text.to_string()))),
+        ValueType::String => Ok(Value::String(text.to_string())),
+        ValueType::Int => parse_list(text, parse_int, "integer"),
+        ValueType::Float => parse_list(text, parse_float, "float"),
+        ValueType::Bool => parse_list(text, parse_bool, "boolean"),
+        ValueType::Json => {
+            serde_json::from_str(text).with_context(|| format!("Invalid JSON value '{}'", text))
+        }
+    }
+}
+
+fn append_value(existing: Value, value: Value) -> Result<Value> {
+    Ok(match (existing, value) {
+        (Value::String(mut text), Value::String(more)) => {
+            text.push_str(&more);
+            Value::String(text)
+        }
+        (Value::String(_), _) => {
+            return Err(anyhow::anyhow!(
+                "Only strings can be appended to a string attribute"
+            ));
+        }
+        (existing, value) => {
+            let mut values = match existing {
+                Value::Array(values) => values,
+                other => vec![other],
+            };
+            match value {
+                Value::Array(more) => values.extend(more),
+                other => values.push(other),
+            }
+            Value::Array(values)
+        }
+    })
+}
+
+/// Apply an edit to the attributes of the node at `label`, returning the resulting differences
+/// (none if the attribute already has the value to set)
+pub fn apply_edit(
+    attributes: &mut Map<String, Value>,
+    label: &str,
+    name: &str,
+    edit: Edit,
+) -> Result<Vec<Difference>> {
+    let difference = |kind, name: &str, old: Option<&Value>, new: Option<&Value>| Difference {
+        kind,
+        path: label.to_string(),
+        item: format!("attribute '{}'", name),
+        old: old.map(Value::to_string),
+        new: new.map(Value::to_string),
+    };
+    let not_found = || anyhow::anyhow!("Attribute '{}' not found on '{}'", name, label);
+
+    let differences = match edit {
+        Edit::Set(value) | Edit::Append(value) if !attributes.contains_key(name) => {
+            let added = difference(ChangeKind::Added, name, None, Some(&value));
+            attributes.insert(name.to_string(), value);
+            vec![added]
+        }
+        Edit::Set(value) if attributes.get(name) == Some(&value) => Vec::new(),
+        Edit::Set(value) => {
+            let old = attributes.insert(name.to_string(), value);
+            vec![difference(
+                ChangeKind::Changed,
+                name,
+                old.as_ref(),
+                attributes.get(name),
+            )]
+        }
+        Edit::Append(value) => {
+            let old = attributes[name].clone();
+            let new = append_value(old.clone(), value)?;
+            attributes.insert(name.to_string(), new);
+            vec![difference(
+                ChangeKind::Changed,
+                name,
+                Some(&old),
+                attributes.get(name),
+            )]
+        }
+        Edit::Delete => {
+            let old = attributes.shift_remove(name).ok_or_else(not_found)?;
+            vec![difference(ChangeKind::Removed, name, Some(&old), None)]
+        }
+        Edit::Rename(new_name) => {
+            if attributes.contains_key(&new_name) {
+                return Err(anyhow::anyhow!(
+                    "Attribute '{}' already exists on '{}'",
+                    new_name,
+                    label
+                ));
+            }
+            let value = attributes.get(name).ok_or_else(not_found)?;
+            let differences = vec![
+                difference(ChangeKind::Removed, name, Some(value), None),
+                difference(ChangeKind::Added, &new_name, None, Some(value)),
+            ];
+            // Keep the attribute where it was
+            *attributes = std::mem::take(attributes)
+                .into_iter()
+                .map(|(key, value)| {
+                    if key == name {
+                        (new_name.clone(), value)
+                    } else {
+                        (key, value)
+                    }
+                })
+                .collect();
+            differences
+        }
+    };
+    Ok(differences)
+}
+
+/// Whether a directory store has a group or array at `node_path`, according to its per-node
+/// metadata files
+pub fn node_exists(root: &Path, node_path: &str, zarr_format: u8) -> bool {
+    let dir = root.join(node_path);
+    if zarr_format == 3 {
+        dir.join("zarr.json").is_file()
+    } else {
+        dir.join(".zarray").is_file() || dir.join(".zgroup").is_file()
+    }
+}
+
+/// Paths of the arrays called `name` among per-node metadata documents, keyed as by
+/// `ZarrStore::scan_metadata_documents`, sorted
+pub fn arrays_named(documents: &BTreeMap<String, Value>, name: &str) -> Vec<String> {
+    documents
+        .iter()
+        .filter_map(|(key, document)| {
+            if let Some(path) = key.strip_suffix(".zarray") {
+                // v2: the node's .zarray
+                Some(path.trim_end_matches('/'))
+            } else if key.ends_with(".zattrs") || key.ends_with(".zgroup") {
+                None
+            } else {
+                // v3: the node path, with the node type in its zarr.json
+                (document["node_type"] == "array").then_some(key.as_str())
+            }
+        })
+        .filter(|path| path.rsplit('/').next() == Some(name))
+        .map(str::to_string)
+        .collect()
+}
+
+/// The metadata document holding the attributes of a node of a directory store: `.zattrs` for
+/// v2, or `zarr.json` (under `attributes`) for v3
+pub struct AttributeDocument {
+    pub file: PathBuf,
+    document: Value,
+    zarr_format: u8,
+}
+
+impl AttributeDocument {
+    pub fn open(root: &Path, node_path: &str, zarr_format: u8) -> Result<Self> {
+        let name = if zarr_format == 3 {
+            "zarr.json"
+        } else {
+            ".zattrs"
+        };
+        let file = root.join(node_path).join(name);
+        let document = match std::fs::read(&file) {
+            Ok(data) => serde_json::from_slice(&data)
+                .with_context(|| format!("Failed to parse '{}'", file.display()))?,
+            // A v2 node without attributes has no .zattrs yet
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && zarr_format == 2 => {
+                Value::Object(Map::new())
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read '{}'", file.display()));
+            }
+        };
+        if !document.is_object() {
+            return Err(anyhow::anyhow!("'{}' is not a JSON object", file.display()));
+        }
+        Ok(Self {
+            file,
+            document,
+            zarr_format,
+        })
+    }
+
+    pub fn attributes_mut(&mut self) -> Result<&mut Map<String, Value>> {
+        let object = self.document.as_object_mut().expect("checked on open");
+        let attributes = if self.zarr_format == 3 {
+            object
+                .entry("attributes")
+                .or_insert_with(|| Value::Object(Map::new()))
+        } else {
+            return Ok(object);
+        };
+        attributes.as_object_mut().ok_or_else(|| {
+            anyhow::anyhow!(
+                "'attributes' in '{}' is not a JSON object",
+                self.file.display()
+            )
+        })
+    }
+
+    /// Replace the file with the edited document
+    pub fn save(&self) -> Result<()> {
+        write_atomically(&self.file, |writer| {
+            serde_json::to_writer_pretty(&mut *writer, &self.document)?;
+            writeln!(writer)?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_values() {
+        assert_eq!(parse_target("tas:units").unwrap(), ("tas", "units"));
+        assert_eq!(parse_target(":title").unwrap(), ("", "title"));
+        assert!(parse_target("tas").is_err());
+
+        assert_eq!(parse_value("3", ValueType::Auto).unwrap(), json!(3));
+        assert_eq!(parse_value("0.5", ValueType::Auto).unwrap(), json!(0.5));
+        assert_eq!(parse_value("true", ValueType::Auto).unwrap(), json!(true));
+        assert_eq!(parse_value("NaN", ValueType::Auto).unwrap(), json!("NaN"));
+        assert_eq!(parse_value("1, 2", ValueType::Auto).unwrap(), json!("1, 2"));
+        assert_eq!(parse_value("3", ValueType::String).unwrap(), json!("3"));
+        assert_eq!(
+            parse_value("0,100", ValueType::Float).unwrap(),
+            json!([0.0, 100.0])
+        );
+        assert_eq!(parse_value("-9999", ValueType::Int).unwrap(), json!(-9999));
+        assert!(parse_value("1.5", ValueType::Int).is_err());
+        assert_eq!(
+            parse_value(r#"{"a": [1]}"#, ValueType::Json).unwrap(),
+            json!({"a": [1]})
+        );
+    }
+
+    #[test]
+    fn test_apply_edits() {
+        let mut attributes = json!({"units": "K", "history": "created", "flags": 1})
+            .as_object()
+            .unwrap()
+            .clone();
+        let edit = |attributes: &mut Map<String, Value>, name, edit| {
+            crate::diff::render_lines(&apply_edit(attributes, "tas", name, edit).unwrap())
+        };
+
+        assert_eq!(
+            edit(&mut attributes, "units", Edit::Set(json!("degC"))),
+            "~ tas: attribute 'units': \"K\" -> \"degC\"\n"
+        );
+        assert_eq!(edit(&mut attributes, "units", Edit::Set(json!("degC"))), "");
+        assert_eq!(
+            edit(&mut attributes, "history", Edit::Append(json!("; edited"))),
+            "~ tas: attribute 'history': \"created\" -> \"created; edited\"\n"
+        );
+        assert_eq!(
+            edit(&mut attributes, "flags", Edit::Append(json!([2, 4]))),
+            "~ tas: attribute 'flags': 1 -> [1,2,4]\n"
+        );
+        assert_eq!(
+            edit(&mut attributes, "units", Edit::Rename("unit".to_string())),
+            "- tas: attribute 'units' = \"degC\"\n+ tas: attribute 'unit' = \"degC\"\n"
+        );
+        assert_eq!(
+            edit(&mut attributes, "comment", Edit::Append(json!("new"))),
+            "+ tas: attribute 'comment' = \"new\"\n"
+        );
+        assert_eq!(
+            edit(&mut attributes, "flags", Edit::Delete),
+            "- tas: attribute 'flags' = [1,2,4]\n"
+        );
+        assert_eq!(
+            attributes.keys().collect::<Vec<_>>(),
+            ["unit", "history", "comment"]
+        );
+
+        assert!(apply_edit(&mut attributes, "tas", "missing", Edit::Delete).is_err());
+        assert!(
+            apply_edit(
+                &mut attributes,
+                "tas",
+                "unit",
+                Edit::Rename("history".into())
+            )
+            .is_err()
+        );
+        assert!(apply_edit(&mut attributes, "tas", "unit", Edit::Append(json!(1))).is_err());
+    }
+
+    #[test]
+    fn test_arrays_named() {
+        let v2: BTreeMap<String, Value> = [
+            (".zgroup", json!({})),
+            ("tas/.zarray", json!({})),
+            ("tas/.zattrs", json!({})),
+            ("g/tas/.zarray", json!({})),
+            ("g/.zgroup", json!({})),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+        assert_eq!(arrays_named(&v2, "tas"), ["g/tas", "tas"]);
+        assert!(arrays_named(&v2, "g").is_empty());
+
+        let v3: BTreeMap<String, Value> = [
+            ("g", json!({"node_type": "group"})),
+            ("g/pr", json!({"node_type": "array"})),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+        assert_eq!(arrays_named(&v3, "pr"), ["g/pr"]);
+        assert!(arrays_named(&v3, "g").is_empty());
+    }
+}
@@ -0,0 +1,835 @@
+//! CDL rendering: where groups, variables and dimensions go in the nested dump, and the strictly
+//! valid CDL document written by `--cdl` for `ncgen`
+
+use crate::metadata::{AttributeValue, Variable, ZarrMetadata};
+use anyhow::{Result, anyhow};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Write};
+
+/// Name of a variable in CDL output (an array at the store root is shown as `root`)
+pub fn variable_label(variable: &Variable) -> &str {
+    if variable.path.is_empty() {
+        "root"
+    } else {
+        &variable.name
+    }
+}
+
+/// The group path (`""` for the root) that the node at `path` belongs to
+fn parent_group(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(parent, _)| parent)
+}
+
+/// Where groups, variables and dimensions are placed in the nested CDL output
+pub struct GroupLayout {
+    /// Child group paths of each group path, including groups that only exist implicitly as the
+    /// parent of an array
+    subgroups: BTreeMap<String, Vec<String>>,
+    /// Dimensions defined in each group: the deepest group enclosing every variable that uses
+    /// the dimension, so it is in scope wherever it is referenced
+    dimensions: BTreeMap<String, Vec<String>>,
+}
+
+impl GroupLayout {
+    pub fn new(metadata: &ZarrMetadata) -> Self {
+        let mut subgroups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let group_paths = metadata
+            .groups
+            .keys()
+            .map(String::as_str)
+            .chain(metadata.variables.keys().map(|path| parent_group(path)));
+        for path in group_paths {
+            // Register the group and all of its ancestors
+            let mut path = path;
+            while !path.is_empty() {
+                let parent = parent_group(path);
+                let children = subgroups.entry(parent.to_string()).or_default();
+                if children.iter().any(|child| child == path) {
+                    break;
+                }
+                children.push(path.to_string());
+                path = parent;
+            }
+        }
+        for children in subgroups.values_mut() {
+            children.sort();
+        }
+
+        let mut dimensions: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (name, info) in &metadata.dimensions {
+            let scope = info
+                .appearances
+                .iter()
+                .map(|(path, _)| parent_group(path))
+                .reduce(common_group)
+                .unwrap_or("");
+            dimensions
+                .entry(scope.to_string())
+                .or_default()
+                .push(name.clone());
+        }
+        for names in dimensions.values_mut() {
+            names.sort();
+        }
+
+        Self {
+            subgroups,
+            dimensions,
+        }
+    }
+
+    pub fn subgroups(&self, path: &str) -> &[String] {
+        self.subgroups.get(path).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn dimensions(&self, path: &str) -> &[String] {
+        self.dimensions.get(path).map_or(&[], Vec::as_slice)
+    }
+
+    /// Variables directly inside the group at `path`, sorted by name
+    pub fn variables<'a>(&self, metadata: &'a ZarrMetadata, path: &str) -> Vec<&'a Variable> {
+        let mut variables: Vec<&Variable> = metadata
+            .variables
+            .values()
+            .filter(|var| parent_group(&var.path) == path)
+            .collect();
+        variables.sort_by(|a, b| a.path.cmp(&b.path));
+        variables
+    }
+}
+
+/// The deepest group path enclosing both group paths `a` and `b`
+fn common_group<'a>(a: &'a str, b: &str) -> &'a str {
+    let mut end = 0;
+    let mut offset = 0;
+    for (part, other) in a.split('/').zip(b.split('/')) {
+        if part != other {
+            break;
+        }
+        offset += part.len();
+        end = offset;
+        offset += 1;
+    }
+    &a[..end]
+}
+
+/// Primitive netCDF types, named as in CDL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CdlType {
+    Byte,
+    UByte,
+    Short,
+    UShort,
+    Int,
+    UInt,
+    Int64,
+    UInt64,
+    Float,
+    Double,
+    String,
+}
+
+impl CdlType {
+    /// The CDL type of a Zarr dtype, or `None` if netCDF has no equivalent (e.g. complex numbers)
+    pub fn from_dtype(dtype: &str) -> Option<Self> {
+        let code = dtype.trim_start_matches(['<', '>', '|', '=']);
+        Some(match code {
+            "i1" => CdlType::Byte,
+            "u1" | "b1" | "?" => CdlType::UByte,
+            "i2" => CdlType::Short,
+            "u2" => CdlType::UShort,
+            "i4" => CdlType::Int,
+            "u4" => CdlType::UInt,
+            "i8" => CdlType::Int64,
+            "u8" => CdlType::UInt64,
+            "f4" => CdlType::Float,
+            "f8" => CdlType::Double,
+            "string" | "O" => CdlType::String,
+            s if s.starts_with('S') || s.starts_with('U') => CdlType::String,
+            _ => return None,
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CdlType::Byte => "byte",
+            CdlType::UByte => "ubyte",
+            CdlType::Short => "short",
+            CdlType::UShort => "ushort",
+            CdlType::Int => "int",
+            CdlType::UInt => "uint",
+            CdlType::Int64 => "int64",
+            CdlType::UInt64 => "uint64",
+            CdlType::Float => "float",
+            CdlType::Double => "double",
+            CdlType::String => "string",
+        }
+    }
+
+    /// `value` as a number of this type, or `None` if the type cannot hold it. Negative integers
+    /// wrap around for unsigned types, as NumPy casts a `-1` fill value of a `uint8` array to 255.
+    pub fn convert(self, value: Number) -> Option<Number> {
+        let (bits, signed) = match self {
+            CdlType::Byte => (8, true),
+            CdlType::UByte => (8, false),
+            CdlType::Short => (16, true),
+            CdlType::UShort => (16, false),
+            CdlType::Int => (32, true),
+            CdlType::UInt => (32, false),
+            CdlType::Int64 => (64, true),
+            CdlType::UInt64 => (64, false),
+            CdlType::Float | CdlType::Double | CdlType::String => return Some(value),
+        };
+        let integer = match value {
+            Number::Integer(i) => i,
+            Number::Float(v) if v.is_finite() && v.fract() == 0.0 => v as i128,
+            Number::Float(_) => return None,
+        };
+        let half = 1i128 << (bits - 1);
+        let integer = if !signed && (-half..0).contains(&integer) {
+            integer + (half << 1)
+        } else {
+            integer
+        };
+        let range = if signed { -half..half } else { 0..half << 1 };
+        range.contains(&integer).then_some(Number::Integer(integer))
+    }
+
+    /// A numeric literal of this type, e.g. `2s`, `3LL`, `1.5f` or `NaN`
+    fn literal(self, value: Number) -> String {
+        let suffix = match self {
+            CdlType::Byte => "b",
+            CdlType::UByte => "UB",
+            CdlType::Short => "s",
+            CdlType::UShort => "US",
+            CdlType::Int => "",
+            CdlType::UInt => "U",
+            CdlType::Int64 => "LL",
+            CdlType::UInt64 => "ULL",
+            CdlType::Float => return float_literal(value.as_f64(), true),
+            CdlType::Double | CdlType::String => return float_literal(value.as_f64(), false),
+        };
+        match value {
+            Number::Integer(i) => format!("{}{}", i, suffix),
+            // Numbers of integer types are integers (see [`Self::convert`])
+            Number::Float(v) => float_literal(v, false),
+        }
+    }
+}
+
+/// A typed attribute number: integral JSON values are kept exact
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Number {
+    /// Wide enough for both `int64` and `uint64` values
+    Integer(i128),
+    Float(f64),
+}
+
+impl Number {
+    pub fn as_f64(self) -> f64 {
+        match self {
+            Number::Integer(i) => i as f64,
+            Number::Float(v) => v,
+        }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Number::Integer(i) => write!(f, "{}", i),
+            Number::Float(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/// A `float` (with an `f` suffix) or `double` literal that ncgen will not read as an integer
+fn float_literal(value: f64, single: bool) -> String {
+    let text = if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+    } else if single {
+        format!("{:?}", value as f32)
+    } else {
+        format!("{:?}", value)
+    };
+    if single { format!("{}f", text) } else { text }
+}
+
+/// Attributes whose type must match the variable type (`_FillValue` is enforced by the netCDF
+/// library, the others by the CF conventions)
+const VARIABLE_TYPED_ATTRIBUTES: &[&str] = &[
+    "_FillValue",
+    "missing_value",
+    "valid_min",
+    "valid_max",
+    "valid_range",
+];
+
+/// Packing attributes, which are floating point even when their JSON value is integral
+const FLOAT_ATTRIBUTES: &[&str] = &["scale_factor", "add_offset"];
+
+/// Zarr attributes that only restate the dimensions of the variable declaration
+pub const HIDDEN_ATTRIBUTES: &[&str] = &["_ARRAY_DIMENSIONS", "dimension_names"];
+
+/// An attribute value in the netCDF data model
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypedAttribute {
+    /// A character string (`char` attribute)
+    Text(String),
+    /// Several strings, which need the netCDF-4 `string` type
+    Strings(Vec<String>),
+    /// One or more numbers of a single type
+    Numbers(CdlType, Vec<Number>),
+}
+
+impl TypedAttribute {
+    /// Type a JSON attribute value; `var_type` is the type of the variable for attributes that
+    /// must share it. Returns `None` for `null`, which netCDF cannot represent, and an error for
+    /// numbers the variable type cannot hold (e.g. a `_FillValue` of 1.5 for an `int` variable).
+    pub fn new(
+        name: &str,
+        value: &AttributeValue,
+        var_type: Option<CdlType>,
+    ) -> Result<Option<Self>> {
+        let typed =
+            var_type.filter(|t| *t != CdlType::String && VARIABLE_TYPED_ATTRIBUTES.contains(&name));
+        let float = FLOAT_ATTRIBUTES.contains(&name);
+        let items: Vec<&AttributeValue> = match value {
+            AttributeValue::Null => return Ok(None),
+            AttributeValue::Array(items) => items.iter().collect(),
+            single => vec![single],
+        };
+
+        if !items.is_empty() && typed.is_none() {
+            let strings: Option<Vec<String>> = items
+                .iter()
+                .map(|item| match item {
+                    AttributeValue::String(s) => Some(s.clone()),
+                    _ => None,
+                })
+                .collect();
+            if let Some(mut strings) = strings {
+                return Ok(Some(if strings.len() == 1 {
+                    TypedAttribute::Text(strings.remove(0))
+                } else {
+                    TypedAttribute::Strings(strings)
+                }));
+            }
+        }
+
+        let numbers: Option<Vec<Number>> = items
+            .iter()
+            .map(|item| match item {
+                AttributeValue::Boolean(b) => Some(Number::Integer(i128::from(*b))),
+                item => attribute_number(item, float),
+            })
+            .collect();
+        if let Some(numbers) = numbers.filter(|n| !n.is_empty()) {
+            let all_booleans = items
+                .iter()
+                .all(|item| matches!(item, AttributeValue::Boolean(_)));
+            if let Some(var_type) = typed {
+                let converted = numbers
+                    .iter()
+                    .map(|n| {
+                        var_type.convert(*n).ok_or_else(|| {
+                            anyhow!("{} is not representable as {}", n, var_type.name())
+                        })
+                    })
+                    .collect::<Result<_>>()?;
+                return Ok(Some(TypedAttribute::Numbers(var_type, converted)));
+            }
+            let cdl_type = if all_booleans {
+                CdlType::Byte
+            } else if numbers.iter().all(fits::<i32>) {
+                CdlType::Int
+            } else if numbers.iter().all(fits::<i64>) {
+                CdlType::Int64
+            } else if numbers.iter().all(fits::<u64>) {
+                CdlType::UInt64
+            } else {
+                CdlType::Double
+            };
+            return Ok(Some(TypedAttribute::Numbers(cdl_type, numbers)));
+        }
+
+        // Objects, empty and mixed arrays are kept as their JSON text
+        Ok(Some(TypedAttribute::Text(sorted_json(value).to_string())))
+    }
+}
+
+/// Whether a number is an integer in the range of `T`
+fn fits<T: TryFrom<i128>>(n: &Number) -> bool {
+    matches!(n, Number::Integer(i) if T::try_from(*i).is_ok())
+}
+
+/// An attribute value as a CDL literal list, with the explicit type it needs (if any)
+struct AttributeLiteral {
+    type_name: Option<&'static str>,
+    values: String,
+}
+
+/// Numeric value of a JSON attribute: integral numbers are integers unless `float` is set
+fn attribute_number(value: &AttributeValue, float: bool) -> Option<Number> {
+    match value {
+        AttributeValue::Integer(i) if float => Some(Number::Float(*i as f64)),
+        AttributeValue::Integer(i) => Some(Number::Integer(i128::from(*i))),
+        AttributeValue::UnsignedInteger(i) if float => Some(Number::Float(*i as f64)),
+        AttributeValue::UnsignedInteger(i) => Some(Number::Integer(i128::from(*i))),
+        AttributeValue::Number(v)
+            if !float && v.fract() == 0.0 && v.abs() < 9.007_199_254_740_992e15 =>
+        {
+            Some(Number::Integer(*v as i128))
+        }
+        AttributeValue::Number(v) => Some(Number::Float(*v)),
+        AttributeValue::String(_) => value.as_f64().map(Number::Float),
+        _ => None,
+    }
+}
+
+/// Render an attribute value as CDL (see [`TypedAttribute::new`])
+fn attribute_literal(
+    name: &str,
+    value: &AttributeValue,
+    var_type: Option<CdlType>,
+) -> Result<Option<AttributeLiteral>> {
+    let Some(typed) = TypedAttribute::new(name, value, var_type)? else {
+        return Ok(None);
+    };
+    let literal = match typed {
+        TypedAttribute::Text(text) => AttributeLiteral {
+            type_name: None,
+            values: quote(&text),
+        },
+        TypedAttribute::Strings(strings) => AttributeLiteral {
+            type_name: Some("string"),
+            values: strings
+                .iter()
+                .map(|s| quote(s))
+                .collect::<Vec<_>>()
+                .join(", "),
+        },
+        TypedAttribute::Numbers(cdl_type, numbers) => AttributeLiteral {
+            type_name: None,
+            values: numbers
+                .iter()
+                .map(|n| cdl_type.literal(*n))
+                .collect::<Vec<_>>()
+                .join(", "),
+        },
+    };
+    Ok(Some(literal))
+}
+
+/// JSON representation of an attribute value with object keys sorted
+pub fn sorted_json(value: &AttributeValue) -> serde_json::Value {
+    match value {
+        AttributeValue::Object(map) => {
+            let sorted: BTreeMap<&String, &AttributeValue> = map.iter().collect();
+            serde_json::Value::Object(
+                sorted
+                    .into_iter()
+                    .map(|(k, v)| (k.clone(), sorted_json(v)))
+                    .collect(),
+            )
+        }
+        AttributeValue::Array(items) => {
+            serde_json::Value::Array(items.iter().map(sorted_json).collect())
+        }
+        other => serde_json::to_value(other).unwrap_or(serde_json::Value::Null),
+    }
+}
+
+/// A double-quoted CDL string with `"`, `\` and control characters escaped
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() && (c as u32) < 0x80 => {
+                let _ = write!(out, "\\{:03o}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// A CDL identifier, with characters that are not allowed in names escaped by `\` like ncdump
+pub fn escape_name(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for (i, c) in name.chars().enumerate() {
+        let allowed = c.is_ascii_alphabetic()
+            || c == '_'
+            || !c.is_ascii()
+            || (i > 0 && (c.is_ascii_digit() || matches!(c, '.' | '@' | '+' | '-')));
+        if !allowed {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    if out.is_empty() {
+        out.push('_');
+    }
+    out
+}
+
+/// Writes a Zarr hierarchy as CDL that `ncgen -k nc4` accepts
+pub struct CdlWriter<'a> {
+    metadata: &'a ZarrMetadata,
+    layout: GroupLayout,
+    /// Values of coordinate variables to write in `data:` sections (`-c`)
+    coordinate_data: Option<&'a BTreeMap<String, anyhow::Result<Vec<f64>>>>,
+    /// Whether to add the special attributes ncgen understands (`-s`)
+    special: bool,
+}
+
+impl<'a> CdlWriter<'a> {
+    pub fn new(
+        metadata: &'a ZarrMetadata,
+        coordinate_data: Option<&'a BTreeMap<String, anyhow::Result<Vec<f64>>>>,
+        special: bool,
+    ) -> Self {
+        Self {
+            metadata,
+            layout: GroupLayout::new(metadata),
+            coordinate_data,
+            special,
+        }
+    }
+
+    /// The whole document, `netcdf name { ... }`
+    pub fn render(&self, dataset_name: &str) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "netcdf {} {{", escape_name(dataset_name));
+        self.write_group(&mut out, "", "");
+        out.push_str("}\n");
+        out
+    }
+
+    fn write_group(&self, out: &mut String, path: &str, indent: &str) {
+        let metadata = self.metadata;
+
+        let dimensions = self.layout.dimensions(path);
+        if !dimensions.is_empty() {
+            let _ = writeln!(out, "{}dimensions:", indent);
+            for name in dimensions {
+                let info = &metadata.dimensions[name];
+                // netCDF only allows zero-length dimensions as unlimited ones
+                if info.is_unlimited || info.max_length == 0 {
+                    let _ = writeln!(
+                        out,
+                        "{}\t{} = UNLIMITED ; // ({} currently)",
+                        indent,
+                        escape_name(name),
+                        info.max_length
+                    );
+                } else {
+                    let _ = writeln!(
+                        out,
+                        "{}\t{} = {} ;",
+                        indent,
+                        escape_name(name),
+                        info.max_length
+                    );
+                }
+            }
+        }
+
+        let variables = self.layout.variables(metadata, path);
+        let attributes = if path.is_empty() {
+            Some(&metadata.global_attributes)
+        } else {
+            metadata.groups.get(path).map(|g| &g.attributes)
+        };
+        let has_attributes =
+            attributes.is_some_and(|a| !a.is_empty()) || (path.is_empty() && self.special);
+        if !variables.is_empty() || has_attributes {
+            let _ = writeln!(out, "{}variables:", indent);
+        }
+
+        for var in &variables {
+            self.write_variable(out, var, indent);
+        }
+
+        if has_attributes {
+            let _ = writeln!(
+                out,
+                "\n{}// {} attributes:",
+                indent,
+                if path.is_empty() { "global" } else { "group" }
+            );
+            if let Some(attributes) = attributes {
+                self.write_attributes(out, "", attributes, None, indent);
+            }
+            if path.is_empty() && self.special {
+                // Groups and the extended types need the netCDF-4 data model
+                let _ = writeln!(out, "{}\t\t:_Format = \"netCDF-4\" ;", indent);
+            }
+        }
+
+        self.write_data(out, &variables, indent);
+
+        for child in self.layout.subgroups(path) {
+            let name = child.rsplit('/').next().unwrap_or(child);
+            let _ = writeln!(out, "\n{}group: {} {{", indent, escape_name(name));
+            self.write_group(out, child, &format!("{}  ", indent));
+            let _ = writeln!(out, "{}  }} // group {}", indent, escape_name(name));
+        }
+    }
+
+    fn write_variable(&self, out: &mut String, var: &Variable, indent: &str) {
+        let name = escape_name(variable_label(var));
+        let dims: Vec<String> = var
+            .dimensions
+            .iter()
+            .map(|d| escape_name(&d.name))
+            .collect();
+        let declaration = if dims.is_empty() {
+            name.clone()
+        } else {
+            format!("{}({})", name, dims.join(", "))
+        };
+
+        let Some(cdl_type) = CdlType::from_dtype(&var.dtype) else {
+            let _ = writeln!(
+                out,
+                "{}\t// {} {} ; (data type '{}' has no netCDF equivalent)",
+                indent, var.dtype, declaration, var.dtype
+            );
+            return;
+        };
+        let _ = writeln!(out, "{}\t{} {} ;", indent, cdl_type.name(), declaration);
+
+        let mut attributes = var.attributes.clone();
+        if let Some(fill_value) = &var.fill_value
+            && !matches!(fill_value, AttributeValue::Null)
+            && cdl_type != CdlType::String
+        {
+            attributes
+                .entry("_FillValue".to_string())
+                .or_insert_with(|| fill_value.clone());
+        }
+        self.write_attributes(out, &name, &attributes, Some(cdl_type), indent);
+
+        if self.special {
+            let mut special = vec![format!("{}:_Storage = \"chunked\"", name)];
+            if !var.chunks.is_empty() {
+                let sizes: Vec<String> = var.chunks.iter().map(u64::to_string).collect();
+                special.push(format!("{}:_ChunkSizes = {}", name, sizes.join(", ")));
+            }
+            if let Some(endian) = var.endianness() {
+                special.push(format!("{}:_Endianness = {}", name, quote(endian)));
+            }
+            for line in special {
+                let _ = writeln!(out, "{}\t\t{} ;", indent, line);
+            }
+        }
+    }
+
+    fn write_attributes(
+        &self,
+        out: &mut String,
+        var_name: &str,
+        attributes: &HashMap<String, AttributeValue>,
+        var_type: Option<CdlType>,
+        indent: &str,
+    ) {
+        let mut sorted: Vec<_> = attributes
+            .iter()
+            .filter(|(key, _)| var_name.is_empty() || !HIDDEN_ATTRIBUTES.contains(&key.as_str()))
+            .collect();
+        sorted.sort_by_key(|(key, _)| key.as_str());
+
+        for (key, value) in sorted {
+            let target = format!("{}:{}", var_name, escape_name(key));
+            match attribute_literal(key, value, var_type) {
+                Ok(Some(AttributeLiteral {
+                    type_name: Some(type_name),
+                    values,
+                })) => {
+                    let _ = writeln!(out, "{}\t\t{} {} = {} ;", indent, type_name, target, values);
+                }
+                Ok(Some(AttributeLiteral {
+                    type_name: None,
+                    values,
+                })) => {
+                    let _ = writeln!(out, "{}\t\t{} = {} ;", indent, target, values);
+                }
+                Ok(None) => {
+                    let _ = writeln!(out, "{}\t\t// {} = null ;", indent, target);
+                }
+                // Left out, as ncgen rejects values the variable type cannot hold
+                Err(e) => {
+                    eprintln!("Warning: leaving out attribute {}: {}", target, e);
+                    let _ = writeln!(
+                        out,
+                        "{}\t\t// {} = {} ; ({})",
+                        indent,
+                        target,
+                        sorted_json(value),
+                        e
+                    );
+                }
+            }
+        }
+    }
+
+    /// The `data:` section with the coordinate values read for `-c`
+    fn write_data(&self, out: &mut String, variables: &[&Variable], indent: &str) {
+        let Some(coordinate_data) = self.coordinate_data else {
+            return;
+        };
+        let values: Vec<(&Variable, &Vec<f64>)> = variables
+            .iter()
+            .filter(|var| CdlType::from_dtype(&var.dtype).is_some_and(|t| t != CdlType::String))
+            .filter_map(|var| match coordinate_data.get(&var.path) {
+                Some(Ok(data)) if !data.is_empty() => Some((*var, data)),
+                _ => None,
+            })
+            .collect();
+        if values.is_empty() {
+            return;
+        }
+
+        let _ = writeln!(out, "{}data:", indent);
+        for (var, data) in values {
+            let missing_values = crate::cf::collect_missing_values_f64(var);
+            let formatted: Vec<String> = data
+                .iter()
+                .map(|&v| {
+                    if crate::cf::is_missing_value(v, &missing_values) {
+                        "_".to_string()
+                    } else if v.is_finite() && v.fract() == 0.0 && v.abs() < 9.2e18 {
+                        format!("{}", v as i64)
+                    } else {
+                        float_literal(v, false)
+                    }
+                })
+                .collect();
+            let _ = writeln!(
+                out,
+                "\n{} {} = {} ;",
+                indent,
+                escape_name(variable_label(var)),
+                formatted.join(", ")
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literal(name: &str, value: AttributeValue, var_type: Option<CdlType>) -> String {
+        let literal = attribute_literal(name, &value, var_type).unwrap().unwrap();
+        match literal.type_name {
+            Some(t) => format!("{} {}", t, literal.values),
+            None => literal.values,
+        }
+    }
+
+    #[test]
+    fn test_attribute_literals() {
+        use AttributeValue::*;
+
+        assert_eq!(literal("units", String("K".into()), None), "\"K\"");
+        assert_eq!(literal("count", Number(3.0), None), "3");
+        assert_eq!(literal("big", Number(5e9), None), "5000000000LL");
+        assert_eq!(literal("scale_factor", Number(1.0), None), "1.0");
+        assert_eq!(
+            literal("_FillValue", Number(-9999.0), Some(CdlType::Float)),
+            "-9999.0f"
+        );
+        assert_eq!(
+            literal("_FillValue", String("NaN".into()), Some(CdlType::Float)),
+            "NaNf"
+        );
+        assert_eq!(
+            literal(
+                "valid_range",
+                Array(vec![Number(0.0), Number(100.0)]),
+                Some(CdlType::Short)
+            ),
+            "0s, 100s"
+        );
+        assert_eq!(
+            literal(
+                "_FillValue",
+                UnsignedInteger(u64::MAX),
+                Some(CdlType::UInt64)
+            ),
+            "18446744073709551615ULL"
+        );
+        assert_eq!(literal("flag", Boolean(true), None), "1b");
+        assert_eq!(
+            literal(
+                "flag_meanings",
+                Array(vec![String("a".into()), String("b".into())]),
+                None
+            ),
+            "string \"a\", \"b\""
+        );
+        assert_eq!(
+            literal("levels", Array(vec![Number(1.0), Number(2.5)]), None),
+            "1.0, 2.5"
+        );
+
+        let mut object = HashMap::new();
+        object.insert("b".to_string(), Number(1.0));
+        object.insert("a".to_string(), String("x".into()));
+        assert_eq!(
+            literal("meta", Object(object), None),
+            r#""{\"a\":\"x\",\"b\":1.0}""#
+        );
+        assert!(attribute_literal("nothing", &Null, None).unwrap().is_none());
+
+        // Fill values are converted to the variable type, or rejected if it cannot hold them
+        assert_eq!(
+            literal("_FillValue", Integer(-1), Some(CdlType::UByte)),
+            "255UB"
+        );
+        assert_eq!(
+            literal("_FillValue", Number(-1.0), Some(CdlType::UInt64)),
+            "18446744073709551615ULL"
+        );
+        assert_eq!(
+            literal("valid_max", Number(1e4), Some(CdlType::Short)),
+            "10000s"
+        );
+        let error = TypedAttribute::new("_FillValue", &Number(1e6), Some(CdlType::Short))
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error, "1000000 is not representable as short");
+        assert!(attribute_literal("_FillValue", &Number(0.5), Some(CdlType::Int)).is_err());
+        assert!(attribute_literal("_FillValue", &Integer(-129), Some(CdlType::UByte)).is_err());
+    }
+
+    #[test]
+    fn test_escaping() {
+        assert_eq!(quote("say \"hi\"\n\\"), r#""say \"hi\"\n\\""#);
+        assert_eq!(escape_name("air temp"), "air\\ temp");
+        assert_eq!(escape_name("2m_temp"), "\\2m_temp");
+        assert_eq!(escape_name("lat-bnds.v2"), "lat-bnds.v2");
+        assert_eq!(CdlType::from_dtype("<u2"), Some(CdlType::UShort));
+        assert_eq!(CdlType::from_dtype("|S10"), Some(CdlType::String));
+        assert_eq!(CdlType::from_dtype("<c8"), None);
+    }
+
+    #[test]
+    fn test_common_group() {
+        assert_eq!(common_group("model/historical", "model"), "model");
+        assert_eq!(common_group("model/a", "model/b"), "model");
+        assert_eq!(common_group("model", "obs"), "");
+        assert_eq!(common_group("", "model"), "");
+    }
+}
@@ -1,27 +1,8 @@
+use crate::cf_report::{CfReport, IssueCode};
 use crate::metadata::{AttributeValue, Variable, ZarrMetadata};
 use crate::store::ZarrStore;
 use anyhow::Result;
-use std::collections::HashSet;
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Level {
-    Info,
-    Warning,
-    Error,
-}
-
-#[derive(Debug, Clone)]
-struct Issue {
-    level: Level,
-    message: String,
-}
-
-#[derive(Debug, Default, Clone)]
-pub struct CfReport {
-    issues: Vec<Issue>,
-    warnings: usize,
-    errors: usize,
-}
+use std::collections::{BTreeSet, HashSet};
 
 #[derive(Debug, Clone)]
 pub struct CfAxisSummary {
@@ -39,54 +20,6 @@ pub struct CfSummary {
     pub candidate_data_vars: Vec<String>,
 }
 
-impl CfReport {
-    fn info(&mut self, msg: impl Into<String>) {
-        self.issues.push(Issue {
-            level: Level::Info,
-            message: msg.into(),
-        });
-    }
-
-    fn warn(&mut self, msg: impl Into<String>) {
-        self.warnings += 1;
-        self.issues.push(Issue {
-            level: Level::Warning,
-            message: msg.into(),
-        });
-    }
-
-    fn error(&mut self, msg: impl Into<String>) {
-        self.errors += 1;
-        self.issues.push(Issue {
-            level: Level::Error,
-            message: msg.into(),
-        });
-    }
-
-    pub fn has_errors(&self) -> bool {
-        self.errors > 0
-    }
-
-    pub fn print(&self) {
-        println!("cf-check {{");
-
-        for issue in &self.issues {
-            let tag = match issue.level {
-                Level::Info => "INFO",
-                Level::Warning => "WARN",
-                Level::Error => "ERROR",
-            };
-            println!("  {}: {}", tag, issue.message);
-        }
-
-        println!("}}");
-        println!(
-            "Summary: {} warnings, {} errors",
-            self.warnings, self.errors
-        );
-    }
-}
-
 pub async fn cf_check(store: &ZarrStore, metadata: &ZarrMetadata) -> Result<CfReport> {
     let mut report = CfReport::default();
 
@@ -206,10 +139,9 @@ pub fn cf_summary(metadata: &ZarrMetadata) -> CfSummary {
         .find(|a| a.axis == 'Z')
         .map(|a| a.dim.clone())
         .or_else(|| find_dimension_name(metadata, &["lev", "level", "plev", "depth", "z"]))
+        && !suggested_slice_dims.iter().any(|d| d == &z)
     {
-        if !suggested_slice_dims.iter().any(|d| d == &z) {
-            suggested_slice_dims.push(z);
-        }
+        suggested_slice_dims.push(z);
     }
 
     // If plot dims are known, don't repeat them as slice dims.
@@ -311,13 +243,8 @@ fn axis_candidate_rank(axis: char, cand: &CfAxisSummary) -> (u8, usize, &str) {
 
 fn axis_preference(axis: char, dim: &str) -> u8 {
     match axis {
-        'T' => {
-            if dim.eq_ignore_ascii_case("time") {
-                0
-            } else {
-                1
-            }
-        }
+        'T' if dim.eq_ignore_ascii_case("time") => 0,
+        'T' => 1,
         'Y' => {
             if dim.eq_ignore_ascii_case("lat") || dim.eq_ignore_ascii_case("latitude") {
                 0
@@ -336,17 +263,14 @@ fn axis_preference(axis: char, dim: &str) -> u8 {
                 2
             }
         }
-        'Z' => {
-            if dim.eq_ignore_ascii_case("lev")
-                || dim.eq_ignore_ascii_case("level")
-                || dim.eq_ignore_ascii_case("plev")
-                || dim.eq_ignore_ascii_case("depth")
-            {
-                0
-            } else {
-                1
-            }
+        'Z' if dim.eq_ignore_ascii_case("lev")
+            || dim.eq_ignore_ascii_case("level")
+            || dim.eq_ignore_ascii_case("plev")
+            || dim.eq_ignore_ascii_case("depth") =>
+        {
+            0
         }
+        'Z' => 1,
         _ => 1,
     }
 }
@@ -358,32 +282,49 @@ fn check_global_conventions(metadata: &ZarrMetadata, report: &mut CfReport) {
         .or_else(|| metadata.global_attributes.get("conventions"));
 
     match conv {
-        None => report.warn("Global attribute 'Conventions' is missing (CF datasets usually set this, e.g. 'CF-1.8')."),
+        None => report.warn(
+            IssueCode::ConventionsMissing,
+            None,
+            "Global attribute 'Conventions' is missing (CF datasets usually set this, e.g. 'CF-1.8').",
+        ),
         Some(AttributeValue::String(s)) => {
             if s.contains("CF-") {
-                report.info(format!("Conventions = '{s}'"));
+                report.info(
+                    IssueCode::ConventionsPresent,
+                    None,
+                    format!("Conventions = '{s}'"),
+                );
             } else {
-                report.warn(format!(
-                    "Global attribute 'Conventions' is present but does not contain 'CF-': '{s}'"
-                ));
+                report.warn(
+                    IssueCode::ConventionsNotCf,
+                    None,
+                    format!(
+                        "Global attribute 'Conventions' is present but does not contain 'CF-': '{s}'"
+                    ),
+                );
             }
         }
-        Some(other) => report.warn(format!(
-            "Global attribute 'Conventions' is present but not a string: {}",
-            describe_attr_value(other)
-        )),
+        Some(other) => report.warn(
+            IssueCode::ConventionsType,
+            None,
+            format!(
+                "Global attribute 'Conventions' is present but not a string: {}",
+                describe_attr_value(other)
+            ),
+        ),
     }
 }
 
 fn check_dimension_names(metadata: &ZarrMetadata, report: &mut CfReport) {
     for (path, var) in &metadata.variables {
+        let label = display_var_path(path, var);
         let dim_names_attr_present = var.attributes.contains_key("_ARRAY_DIMENSIONS")
             || var.attributes.contains_key("dimension_names");
 
         if !dim_names_attr_present && !var.shape.is_empty() {
-            report.warn(format!(
+            report.warn(IssueCode::DimNamesMissing, Some(label.as_str()), format!(
                 "Variable '{}' has no explicit dimension name list (_ARRAY_DIMENSIONS/dimension_names); CF tooling may have trouble interpreting axes.",
-                display_var_path(path, var)
+                label
             ));
         }
 
@@ -402,25 +343,34 @@ fn check_one_dimension_name_attr(
     let Some(attr) = var.attributes.get(attr_name) else {
         return;
     };
+    let label = display_var_path(path, var);
 
     let AttributeValue::Array(items) = attr else {
-        report.warn(format!(
-            "Variable '{}' attribute '{}' is present but not an array (found {}).",
-            display_var_path(path, var),
-            attr_name,
-            describe_attr_value(attr)
-        ));
+        report.warn(
+            IssueCode::DimNamesType,
+            Some(label.as_str()),
+            format!(
+                "Variable '{}' attribute '{}' is present but not an array (found {}).",
+                label,
+                attr_name,
+                describe_attr_value(attr)
+            ),
+        );
         return;
     };
 
     if items.len() != var.shape.len() {
-        report.error(format!(
-            "Variable '{}' {} length ({}) does not match shape dimensionality ({}).",
-            display_var_path(path, var),
-            attr_name,
-            items.len(),
-            var.shape.len()
-        ));
+        report.error(
+            IssueCode::DimNamesLength,
+            Some(label.as_str()),
+            format!(
+                "Variable '{}' {} length ({}) does not match shape dimensionality ({}).",
+                label,
+                attr_name,
+                items.len(),
+                var.shape.len()
+            ),
+        );
     }
 
     let mut names: Vec<&str> = Vec::new();
@@ -433,30 +383,38 @@ fn check_one_dimension_name_attr(
     }
 
     if any_non_string {
-        report.warn(format!(
-            "Variable '{}' {} contains non-string entries; expected an array of strings.",
-            display_var_path(path, var),
-            attr_name
-        ));
+        report.warn(
+            IssueCode::DimNamesNonString,
+            Some(label.as_str()),
+            format!(
+                "Variable '{}' {} contains non-string entries; expected an array of strings.",
+                label, attr_name
+            ),
+        );
     }
 
     let mut seen: HashSet<&str> = HashSet::new();
     for name in names {
         if name.trim().is_empty() {
-            report.warn(format!(
-                "Variable '{}' {} contains an empty dimension name.",
-                display_var_path(path, var),
-                attr_name
-            ));
+            report.warn(
+                IssueCode::DimNamesEmpty,
+                Some(label.as_str()),
+                format!(
+                    "Variable '{}' {} contains an empty dimension name.",
+                    label, attr_name
+                ),
+            );
         }
 
         if !seen.insert(name) {
-            report.warn(format!(
-                "Variable '{}' {} contains duplicate dimension name '{}'.",
-                display_var_path(path, var),
-                attr_name,
-                name
-            ));
+            report.warn(
+                IssueCode::DimNamesDuplicate,
+                Some(label.as_str()),
+                format!(
+                    "Variable '{}' {} contains duplicate dimension name '{}'.",
+                    label, attr_name, name
+                ),
+            );
         }
     }
 }
@@ -494,52 +452,69 @@ async fn check_coordinate_variables(
         let is_lon = is_longitude_coordinate(standard_name, units);
 
         if len == 0 {
-            report.warn(format!(
-                "Coordinate variable '{}' has length 0.",
-                coord_label
-            ));
+            report.warn(
+                IssueCode::CoordEmpty,
+                Some(coord_label.as_str()),
+                format!("Coordinate variable '{}' has length 0.", coord_label),
+            );
         }
 
         // units
         match var.attributes.get("units") {
             Some(AttributeValue::String(_)) => {}
-            Some(other) => report.warn(format!(
-                "Coordinate variable '{}' has non-string 'units' attribute: {}",
-                coord_label,
-                describe_attr_value(other)
-            )),
-            None => report.warn(format!(
-                "Coordinate variable '{}' is missing 'units' attribute.",
-                coord_label
-            )),
+            Some(other) => report.warn(
+                IssueCode::CoordUnitsType,
+                Some(coord_label.as_str()),
+                format!(
+                    "Coordinate variable '{}' has non-string 'units' attribute: {}",
+                    coord_label,
+                    describe_attr_value(other)
+                ),
+            ),
+            None => report.warn(
+                IssueCode::CoordUnitsMissing,
+                Some(coord_label.as_str()),
+                format!(
+                    "Coordinate variable '{}' is missing 'units' attribute.",
+                    coord_label
+                ),
+            ),
         }
 
         // standard_name (optional, but useful)
         if let Some(AttributeValue::String(sn)) = var.attributes.get("standard_name") {
-            report.info(format!(
-                "Coordinate variable '{}' standard_name='{}'",
-                coord_label, sn
-            ));
+            report.info(
+                IssueCode::CoordStandardName,
+                Some(coord_label.as_str()),
+                format!(
+                    "Coordinate variable '{}' standard_name='{}'",
+                    coord_label, sn
+                ),
+            );
         }
 
         // CF-ish time coordinate checks.
         if is_time {
-            if let Some(units) = units {
-                if !cf_time_units_looks_ok(units) {
-                    report.warn(format!(
-                        "Time coordinate variable '{}' has units='{}' (expected e.g. 'days since 1850-01-01').",
-                        coord_label, units
-                    ));
-                }
+            if let Some(units) = units
+                && !cf_time_units_looks_ok(units)
+            {
+                report.warn(IssueCode::TimeUnitsInvalid, Some(coord_label.as_str()), format!(
+                    "Time coordinate variable '{}' has units='{}' (expected e.g. 'days since 1850-01-01').",
+                    coord_label, units
+                ));
             }
 
             match var.attributes.get("calendar") {
                 Some(AttributeValue::String(_)) | None => {}
-                Some(other) => report.warn(format!(
-                    "Time coordinate variable '{}' has non-string 'calendar' attribute: {}",
-                    coord_label,
-                    describe_attr_value(other)
-                )),
+                Some(other) => report.warn(
+                    IssueCode::TimeCalendarType,
+                    Some(coord_label.as_str()),
+                    format!(
+                        "Time coordinate variable '{}' has non-string 'calendar' attribute: {}",
+                        coord_label,
+                        describe_attr_value(other)
+                    ),
+                ),
             }
         }
 
@@ -551,20 +526,24 @@ async fn check_coordinate_variables(
                 Some(AttributeValue::String(pos)) => {
                     let pos_lc = pos.to_ascii_lowercase();
                     if pos_lc != "up" && pos_lc != "down" {
-                        report.warn(format!(
+                        report.warn(IssueCode::VerticalPositiveInvalid, Some(coord_label.as_str()), format!(
                             "Vertical coordinate variable '{}' has positive='{}' (expected 'up' or 'down').",
                             coord_label, pos
                         ));
                     }
                 }
-                Some(other) => report.warn(format!(
-                    "Vertical coordinate variable '{}' has non-string 'positive' attribute: {}",
-                    coord_label,
-                    describe_attr_value(other)
-                )),
+                Some(other) => report.warn(
+                    IssueCode::VerticalPositiveType,
+                    Some(coord_label.as_str()),
+                    format!(
+                        "Vertical coordinate variable '{}' has non-string 'positive' attribute: {}",
+                        coord_label,
+                        describe_attr_value(other)
+                    ),
+                ),
                 None => {
                     if positive_required {
-                        report.warn(format!(
+                        report.warn(IssueCode::VerticalPositiveMissing, Some(coord_label.as_str()), format!(
                             "Vertical coordinate variable '{}' is missing 'positive' attribute (expected 'up' or 'down').",
                             coord_label
                         ));
@@ -591,67 +570,67 @@ async fn check_coordinate_variables(
 
                     if is_time {
                         match direction {
-                            Some("increasing") => report.info(format!(
+                            Some("increasing") => report.info(IssueCode::CoordMonotonic, Some(coord_label.as_str()), format!(
                                 "Time coordinate '{}' appears monotonic increasing (checked first {} values).",
                                 dim, sample
                             )),
-                            Some("decreasing") => report.warn(format!(
+                            Some("decreasing") => report.warn(IssueCode::TimeDecreasing, Some(coord_label.as_str()), format!(
                                 "Time coordinate '{}' appears monotonic decreasing (expected increasing; checked first {} values).",
                                 dim, sample
                             )),
-                            Some("constant") => report.warn(format!(
+                            Some("constant") => report.warn(IssueCode::CoordConstant, Some(coord_label.as_str()), format!(
                                 "Time coordinate '{}' appears constant (expected increasing; checked first {} values).",
                                 dim, sample
                             )),
-                            None => report.warn(format!(
+                            None => report.warn(IssueCode::CoordNotMonotonic, Some(coord_label.as_str()), format!(
                                 "Time coordinate '{}' is not monotonic (expected increasing; checked first {} values).",
                                 dim, sample
                             )),
-                            Some(other) => report.info(format!(
+                            Some(other) => report.info(IssueCode::CoordMonotonic, Some(coord_label.as_str()), format!(
                                 "Time coordinate '{}' monotonicity: {} (checked first {} values).",
                                 dim, other, sample
                             )),
                         }
                     } else {
                         match direction {
-                            Some("increasing") => report.info(format!(
+                            Some("increasing") => report.info(IssueCode::CoordMonotonic, Some(coord_label.as_str()), format!(
                                 "Coordinate '{}' appears monotonic increasing (checked first {} values).",
                                 dim, sample
                             )),
-                            Some("decreasing") => report.info(format!(
+                            Some("decreasing") => report.info(IssueCode::CoordMonotonic, Some(coord_label.as_str()), format!(
                                 "Coordinate '{}' appears monotonic decreasing (checked first {} values).",
                                 dim, sample
                             )),
-                            Some("constant") => report.warn(format!(
+                            Some("constant") => report.warn(IssueCode::CoordConstant, Some(coord_label.as_str()), format!(
                                 "Coordinate '{}' appears constant (checked first {} values).",
                                 dim, sample
                             )),
-                            None => report.warn(format!(
+                            None => report.warn(IssueCode::CoordNotMonotonic, Some(coord_label.as_str()), format!(
                                 "Coordinate '{}' is not monotonic (checked first {} values).",
                                 dim, sample
                             )),
-                            Some(other) => report.info(format!(
+                            Some(other) => report.info(IssueCode::CoordMonotonic, Some(coord_label.as_str()), format!(
                                 "Coordinate '{}' monotonicity: {} (checked first {} values).",
                                 dim, other, sample
                             )),
                         }
                     }
 
-                    if is_lat || is_lon {
-                        if let Some((min, max)) = sample_min_max(&data, &missing_values) {
-                            if is_lat && (min < -90.0 - 1e-6 || max > 90.0 + 1e-6) {
-                                report.warn(format!(
-                                    "Latitude coordinate '{}' sample range [{:.6}, {:.6}] looks out of bounds for degrees_north.",
-                                    dim, min, max
-                                ));
-                            }
-
-                            if is_lon && (min < -360.0 - 1e-6 || max > 360.0 + 1e-6) {
-                                report.warn(format!(
-                                    "Longitude coordinate '{}' sample range [{:.6}, {:.6}] looks out of bounds for degrees_east.",
-                                    dim, min, max
-                                ));
-                            }
+                    if (is_lat || is_lon)
+                        && let Some((min, max)) = sample_min_max(&data, &missing_values)
+                    {
+                        if is_lat && (min < -90.0 - 1e-6 || max > 90.0 + 1e-6) {
+                            report.warn(IssueCode::LatRange, Some(coord_label.as_str()), format!(
+                                "Latitude coordinate '{}' sample range [{:.6}, {:.6}] looks out of bounds for degrees_north.",
+                                dim, min, max
+                            ));
+                        }
+
+                        if is_lon && (min < -360.0 - 1e-6 || max > 360.0 + 1e-6) {
+                            report.warn(IssueCode::LonRange, Some(coord_label.as_str()), format!(
+                                "Longitude coordinate '{}' sample range [{:.6}, {:.6}] looks out of bounds for degrees_east.",
+                                dim, min, max
+                            ));
                         }
                     }
                 }
@@ -661,10 +640,14 @@ async fn check_coordinate_variables(
                     } else {
                         "coordinate"
                     };
-                    report.warn(format!(
-                        "Skipping monotonicity check for {} '{}' ({}): {}",
-                        kind, dim, coord_label, err
-                    ));
+                    report.warn(
+                        IssueCode::CoordReadSkipped,
+                        Some(coord_label.as_str()),
+                        format!(
+                            "Skipping monotonicity check for {} '{}' ({}): {}",
+                            kind, dim, coord_label, err
+                        ),
+                    );
                 }
             }
         }
@@ -675,7 +658,11 @@ async fn check_coordinate_variables(
 
     // If we have no coordinate variables at all, mention it once.
     if coord_vars.is_empty() && !metadata.dimensions.is_empty() {
-        report.warn("No coordinate variables detected (1D vars named like their dimension). Many CF datasets include them for axes like time/lat/lon." );
+        report.warn(
+            IssueCode::CoordsNone,
+            None,
+            "No coordinate variables detected (1D vars named like their dimension). Many CF datasets include them for axes like time/lat/lon.",
+        );
     }
 
     Ok(())
@@ -705,10 +692,14 @@ fn check_dimensions_have_coordinates(
         }
 
         if !dims_with_coord.contains(dim_name) {
-            report.warn(format!(
-                "Dimension '{}' has no coordinate variable '{}' (1D var with same name).",
-                dim_name, dim_name
-            ));
+            report.warn(
+                IssueCode::DimNoCoord,
+                None,
+                format!(
+                    "Dimension '{}' has no coordinate variable '{}' (1D var with same name).",
+                    dim_name, dim_name
+                ),
+            );
         }
     }
 }
@@ -722,27 +713,32 @@ fn check_bounds_variable(
 ) {
     let resolved = resolve_related_var(metadata, coord_path, bounds_name);
     let Some((bounds_path, bounds_var)) = resolved else {
-        report.warn(format!(
-            "Coordinate '{}' declares bounds='{}' but bounds variable was not found.",
-            coord_var.name, bounds_name
-        ));
+        report.warn(
+            IssueCode::BoundsMissing,
+            Some(display_var_path(coord_path, coord_var).as_str()),
+            format!(
+                "Coordinate '{}' declares bounds='{}' but bounds variable was not found.",
+                coord_var.name, bounds_name
+            ),
+        );
         return;
     };
 
     let coord_len = coord_var.shape.first().copied().unwrap_or(0);
+    let bounds_label = display_var_path(bounds_path, bounds_var);
     if bounds_var.shape.len() < 2 {
-        report.warn(format!(
+        report.warn(IssueCode::BoundsShape, Some(bounds_label.as_str()), format!(
             "Bounds variable '{}' has shape {:?}; expected at least 2 dimensions (e.g. (n, 2)).",
-            display_var_path(bounds_path, bounds_var),
+            bounds_label,
             bounds_var.shape
         ));
         return;
     }
 
     if bounds_var.shape[0] != coord_len {
-        report.warn(format!(
+        report.warn(IssueCode::BoundsLengthMismatch, Some(bounds_label.as_str()), format!(
             "Bounds variable '{}' first dimension size {} does not match coordinate '{}' length {}.",
-            display_var_path(bounds_path, bounds_var),
+            bounds_label,
             bounds_var.shape[0],
             coord_var.name,
             coord_len
@@ -750,46 +746,61 @@ fn check_bounds_variable(
     }
 
     if bounds_var.shape[1] != 2 {
-        report.warn(format!(
-            "Bounds variable '{}' second dimension size is {} (often 2 in CF).",
-            display_var_path(bounds_path, bounds_var),
-            bounds_var.shape[1]
-        ));
+        report.warn(
+            IssueCode::BoundsVertices,
+            Some(bounds_label.as_str()),
+            format!(
+                "Bounds variable '{}' second dimension size is {} (often 2 in CF).",
+                bounds_label, bounds_var.shape[1]
+            ),
+        );
     }
 }
 
 fn check_grid_mappings(metadata: &ZarrMetadata, report: &mut CfReport) {
     for (path, var) in &metadata.variables {
+        let label = display_var_path(path, var);
         let Some(AttributeValue::String(grid_mapping)) = var.attributes.get("grid_mapping") else {
             continue;
         };
 
         let resolved = resolve_related_var(metadata, path, grid_mapping);
         let Some((gm_path, gm_var)) = resolved else {
-            report.warn(format!(
+            report.warn(IssueCode::GridMappingMissing, Some(label.as_str()), format!(
                 "Variable '{}' references grid_mapping='{}' but mapping variable was not found.",
-                display_var_path(path, var),
+                label,
                 grid_mapping
             ));
             continue;
         };
 
+        let gm_label = display_var_path(gm_path, gm_var);
         match gm_var.attributes.get("grid_mapping_name") {
-            Some(AttributeValue::String(name)) => report.info(format!(
-                "grid_mapping '{}' found (grid_mapping_name='{}') for variable '{}'.",
-                display_var_path(gm_path, gm_var),
-                name,
-                display_var_path(path, var)
-            )),
-            Some(other) => report.warn(format!(
-                "grid_mapping '{}' exists but grid_mapping_name is not a string: {}",
-                display_var_path(gm_path, gm_var),
-                describe_attr_value(other)
-            )),
-            None => report.warn(format!(
-                "grid_mapping '{}' exists but is missing grid_mapping_name attribute.",
-                display_var_path(gm_path, gm_var)
-            )),
+            Some(AttributeValue::String(name)) => report.info(
+                IssueCode::GridMappingFound,
+                Some(label.as_str()),
+                format!(
+                    "grid_mapping '{}' found (grid_mapping_name='{}') for variable '{}'.",
+                    gm_label, name, label
+                ),
+            ),
+            Some(other) => report.warn(
+                IssueCode::GridMappingNameType,
+                Some(gm_label.as_str()),
+                format!(
+                    "grid_mapping '{}' exists but grid_mapping_name is not a string: {}",
+                    gm_label,
+                    describe_attr_value(other)
+                ),
+            ),
+            None => report.warn(
+                IssueCode::GridMappingNameMissing,
+                Some(gm_label.as_str()),
+                format!(
+                    "grid_mapping '{}' exists but is missing grid_mapping_name attribute.",
+                    gm_label
+                ),
+            ),
         }
     }
 }
@@ -802,18 +813,68 @@ fn check_coordinates_attribute_refs(metadata: &ZarrMetadata, report: &mut CfRepo
 
         for name in coords.split_whitespace() {
             if resolve_related_var(metadata, path, name).is_none() {
-                report.warn(format!(
-                    "Variable '{}' lists coordinates='{}' but '{}' was not found.",
-                    display_var_path(path, var),
-                    coords,
-                    name
-                ));
+                let label = display_var_path(path, var);
+                report.warn(
+                    IssueCode::CoordinatesRefMissing,
+                    Some(label.as_str()),
+                    format!(
+                        "Variable '{}' lists coordinates='{}' but '{}' was not found.",
+                        label, coords, name
+                    ),
+                );
+            }
+        }
+    }
+}
+
+/// Paths of `paths` plus every variable they depend on: coordinate variables of their
+/// dimensions, `coordinates`, `bounds` and `grid_mapping` variables (followed transitively)
+pub(crate) fn with_related_variables(
+    metadata: &ZarrMetadata,
+    paths: impl IntoIterator<Item = String>,
+) -> BTreeSet<String> {
+    let mut selected = BTreeSet::new();
+    let mut pending: Vec<String> = paths.into_iter().collect();
+
+    while let Some(path) = pending.pop() {
+        let Some(var) = metadata.variables.get(&path) else {
+            continue;
+        };
+        if !selected.insert(path.clone()) {
+            continue;
+        }
+
+        let mut names: Vec<&str> = var.dimensions.iter().map(|d| d.name.as_str()).collect();
+        if let Some(coords) = attr_string(var, "coordinates") {
+            names.extend(coords.split_whitespace());
+        }
+        if let Some(bounds) = attr_string(var, "bounds") {
+            names.push(bounds);
+        }
+        if let Some(grid_mapping) = attr_string(var, "grid_mapping") {
+            // Either a variable name or the extended "crs: lat lon" form
+            let mapping_names: Vec<&str> = grid_mapping
+                .split_whitespace()
+                .filter_map(|token| token.strip_suffix(':'))
+                .collect();
+            if mapping_names.is_empty() {
+                names.push(grid_mapping.trim());
+            } else {
+                names.extend(mapping_names);
+            }
+        }
+
+        for name in names {
+            if let Some((related_path, _)) = resolve_related_var(metadata, &path, name) {
+                pending.push(related_path.clone());
             }
         }
     }
+
+    selected
 }
 
-fn resolve_related_var<'a>(
+pub(crate) fn resolve_related_var<'a>(
     metadata: &'a ZarrMetadata,
     source_path: &str,
     name: &str,
@@ -1040,36 +1101,80 @@ fn monotonic_direction(values: &[f64], missing_values: &[f64]) -> Option<&'stati
     }
 }
 
-fn collect_missing_values_f64(var: &Variable) -> Vec<f64> {
-    let mut out: Vec<f64> = Vec::new();
+/// Fill and missing values of `var` (`_FillValue`, `missing_value` and the array fill value)
+pub(crate) fn collect_missing_values_f64(var: &Variable) -> Vec<f64> {
+    let mut out = collect_missing_values(var, AttributeValue::as_f64);
+    out.sort_by(|a, b| a.total_cmp(b));
+    out.dedup();
+    out
+}
+
+/// Integral fill and missing values of `var`, exact for 64-bit integer variables
+pub(crate) fn collect_missing_values_i128(var: &Variable) -> Vec<i128> {
+    let mut out = collect_missing_values(var, |value| match value {
+        AttributeValue::Integer(i) => Some(i128::from(*i)),
+        AttributeValue::UnsignedInteger(i) => Some(i128::from(*i)),
+        AttributeValue::Number(v) if v.is_finite() && v.fract() == 0.0 => Some(*v as i128),
+        _ => None,
+    });
+    out.sort();
+    out.dedup();
+    out
+}
+
+fn collect_missing_values<T>(
+    var: &Variable,
+    convert: impl Fn(&AttributeValue) -> Option<T> + Copy,
+) -> Vec<T> {
+    let mut out = Vec::new();
 
     if let Some(v) = var.attributes.get("_FillValue") {
-        push_missing_values_attr(&mut out, v);
+        push_missing_values_attr(&mut out, v, convert);
     }
 
     if let Some(v) = var.attributes.get("missing_value") {
-        push_missing_values_attr(&mut out, v);
+        push_missing_values_attr(&mut out, v, convert);
     }
 
     if let Some(v) = &var.fill_value {
-        push_missing_values_attr(&mut out, v);
+        push_missing_values_attr(&mut out, v, convert);
     }
 
-    out.sort_by(|a, b| a.total_cmp(b));
-    out.dedup();
     out
 }
 
-fn push_missing_values_attr(out: &mut Vec<f64>, value: &AttributeValue) {
+fn push_missing_values_attr<T>(
+    out: &mut Vec<T>,
+    value: &AttributeValue,
+    convert: impl Fn(&AttributeValue) -> Option<T> + Copy,
+) {
     match value {
-        AttributeValue::Number(v) => out.push(*v),
-        AttributeValue::Integer(v) => out.push(*v as f64),
         AttributeValue::Array(values) => {
             for v in values {
-                push_missing_values_attr(out, v);
+                push_missing_values_attr(out, v, convert);
             }
         }
-        _ => {}
+        other => out.extend(convert(other)),
+    }
+}
+
+/// Whether `value` is one of `missing_values` (a NaN fill value matches NaN data)
+pub(crate) fn is_missing_value(value: f64, missing_values: &[f64]) -> bool {
+    missing_values
+        .iter()
+        .any(|m| *m == value || (m.is_nan() && value.is_nan()))
+}
+
+/// Replace fill/missing values of `var` in `data` with NaN, so they are treated as masked
+pub(crate) fn mask_missing_values(var: &Variable, data: &mut [f64]) {
+    let missing_values = collect_missing_values_f64(var);
+    if missing_values.is_empty() {
+        return;
+    }
+    for value in data.iter_mut() {
+        if is_missing_value(*value, &missing_values) {
+            *value = f64::NAN;
+        }
     }
 }
 
@@ -1086,7 +1191,7 @@ fn describe_attr_value(value: &AttributeValue) -> String {
     match value {
         AttributeValue::String(_) => "string".to_string(),
         AttributeValue::Number(_) => "number".to_string(),
-        AttributeValue::Integer(_) => "integer".to_string(),
+        AttributeValue::Integer(_) | AttributeValue::UnsignedInteger(_) => "integer".to_string(),
         AttributeValue::Boolean(_) => "boolean".to_string(),
         AttributeValue::Array(_) => "array".to_string(),
         AttributeValue::Object(_) => "object".to_string(),
@@ -1097,6 +1202,7 @@ fn describe_attr_value(value: &AttributeValue) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::codec::CodecPipeline;
     use std::collections::HashMap;
 
     #[test]
@@ -1167,10 +1273,10 @@ mod tests {
             dtype: "<f8".to_string(),
             shape: vec![10],
             chunks: vec![10],
-            compressor: None,
+            codecs: CodecPipeline::default(),
             fill_value: None,
             order: "C".to_string(),
-            filters: vec![],
+            dimension_separator: ".".to_string(),
             attributes: HashMap::new(),
             dimensions: vec![],
         };
@@ -1182,10 +1288,10 @@ mod tests {
             dtype: "<f8".to_string(),
             shape: vec![10],
             chunks: vec![10],
-            compressor: None,
+            codecs: CodecPipeline::default(),
             fill_value: None,
             order: "C".to_string(),
-            filters: vec![],
+            dimension_separator: ".".to_string(),
             attributes: HashMap::new(),
             dimensions: vec![],
         };
@@ -1195,4 +1301,71 @@ mod tests {
         assert!(resolve_related_var(&md, "grp/temp", "lat").is_some());
         assert!(resolve_related_var(&md, "grp/temp", "missing").is_none());
     }
+
+    #[test]
+    fn test_with_related_variables() {
+        let mut md = ZarrMetadata::new();
+        let variable = |name: &str, attrs: &[(&str, &str)]| Variable {
+            name: name.to_string(),
+            path: name.to_string(),
+            dtype: "<f8".to_string(),
+            shape: vec![],
+            chunks: vec![],
+            codecs: CodecPipeline::default(),
+            fill_value: None,
+            order: "C".to_string(),
+            dimension_separator: ".".to_string(),
+            attributes: attrs
+                .iter()
+                .map(|(k, v)| (k.to_string(), AttributeValue::String(v.to_string())))
+                .collect(),
+            dimensions: vec![],
+        };
+        for var in [
+            variable(
+                "tas",
+                &[("coordinates", "lat lon"), ("grid_mapping", "crs: lat lon")],
+            ),
+            variable("lat", &[("bounds", "lat_bnds")]),
+            variable("lat_bnds", &[]),
+            variable("lon", &[]),
+            variable("crs", &[]),
+            variable("pr", &[]),
+        ] {
+            md.variables.insert(var.path.clone(), var);
+        }
+
+        let selected = with_related_variables(&md, ["tas".to_string()]);
+        let selected: Vec<&str> = selected.iter().map(String::as_str).collect();
+        assert_eq!(selected, ["crs", "lat", "lat_bnds", "lon", "tas"]);
+    }
+
+    #[test]
+    fn test_mask_missing_values() {
+        let mut attributes = HashMap::new();
+        attributes.insert("missing_value".to_string(), AttributeValue::Number(-1.0));
+        let var = Variable {
+            name: "sst".to_string(),
+            path: "sst".to_string(),
+            dtype: "<f8".to_string(),
+            shape: vec![4],
+            chunks: vec![4],
+            codecs: CodecPipeline::default(),
+            fill_value: Some(AttributeValue::String("Infinity".to_string())),
+            order: "C".to_string(),
+            dimension_separator: ".".to_string(),
+            attributes,
+            dimensions: vec![],
+        };
+
+        let mut data = [1.0, -1.0, f64::INFINITY, 2.0];
+        mask_missing_values(&var, &mut data);
+        assert_eq!(data[0], 1.0);
+        assert!(data[1].is_nan());
+        assert!(data[2].is_nan());
+        assert_eq!(data[3], 2.0);
+
+        assert!(is_missing_value(f64::NAN, &[f64::NAN]));
+        assert!(!is_missing_value(f64::NAN, &[0.0]));
+    }
 }
@@ -0,0 +1,557 @@
+use serde::Serialize;
+use serde_json::json;
+
+/// Version of the JSON document emitted by `cf-check --format json`.
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Level {
+    fn tag(self) -> &'static str {
+        match self {
+            Level::Info => "INFO",
+            Level::Warning => "WARN",
+            Level::Error => "ERROR",
+        }
+    }
+
+    fn sarif_level(self) -> &'static str {
+        match self {
+            Level::Info => "note",
+            Level::Warning => "warning",
+            Level::Error => "error",
+        }
+    }
+}
+
+/// Stable identifiers for every check performed by `cf-check`.
+///
+/// The string form (see [`IssueCode::as_str`]) is part of the public output and must not
+/// change once released; add new variants instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum IssueCode {
+    ConventionsPresent,
+    ConventionsMissing,
+    ConventionsNotCf,
+    ConventionsType,
+    DimNamesMissing,
+    DimNamesType,
+    DimNamesLength,
+    DimNamesNonString,
+    DimNamesEmpty,
+    DimNamesDuplicate,
+    CoordEmpty,
+    CoordUnitsMissing,
+    CoordUnitsType,
+    CoordStandardName,
+    CoordMonotonic,
+    CoordConstant,
+    CoordNotMonotonic,
+    CoordReadSkipped,
+    CoordsNone,
+    DimNoCoord,
+    TimeUnitsInvalid,
+    TimeCalendarType,
+    TimeDecreasing,
+    VerticalPositiveInvalid,
+    VerticalPositiveType,
+    VerticalPositiveMissing,
+    LatRange,
+    LonRange,
+    BoundsMissing,
+    BoundsShape,
+    BoundsLengthMismatch,
+    BoundsVertices,
+    GridMappingFound,
+    GridMappingMissing,
+    GridMappingNameMissing,
+    GridMappingNameType,
+    CoordinatesRefMissing,
+}
+
+impl IssueCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IssueCode::ConventionsPresent => "CF-CONVENTIONS-PRESENT",
+            IssueCode::ConventionsMissing => "CF-CONVENTIONS-MISSING",
+            IssueCode::ConventionsNotCf => "CF-CONVENTIONS-NOT-CF",
+            IssueCode::ConventionsType => "CF-CONVENTIONS-TYPE",
+            IssueCode::DimNamesMissing => "CF-DIMNAMES-MISSING",
+            IssueCode::DimNamesType => "CF-DIMNAMES-TYPE",
+            IssueCode::DimNamesLength => "CF-DIMNAMES-LENGTH",
+            IssueCode::DimNamesNonString => "CF-DIMNAMES-NON-STRING",
+            IssueCode::DimNamesEmpty => "CF-DIMNAMES-EMPTY",
+            IssueCode::DimNamesDuplicate => "CF-DIMNAMES-DUPLICATE",
+            IssueCode::CoordEmpty => "CF-COORD-EMPTY",
+            IssueCode::CoordUnitsMissing => "CF-COORD-UNITS-MISSING",
+            IssueCode::CoordUnitsType => "CF-COORD-UNITS-TYPE",
+            IssueCode::CoordStandardName => "CF-COORD-STANDARD-NAME",
+            IssueCode::CoordMonotonic => "CF-COORD-MONOTONIC",
+            IssueCode::CoordConstant => "CF-COORD-CONSTANT",
+            IssueCode::CoordNotMonotonic => "CF-COORD-NOT-MONOTONIC",
+            IssueCode::CoordReadSkipped => "CF-COORD-READ-SKIPPED",
+            IssueCode::CoordsNone => "CF-COORDS-NONE",
+            IssueCode::DimNoCoord => "CF-DIM-NO-COORD",
+            IssueCode::TimeUnitsInvalid => "CF-TIME-UNITS-INVALID",
+            IssueCode::TimeCalendarType => "CF-TIME-CALENDAR-TYPE",
+            IssueCode::TimeDecreasing => "CF-TIME-DECREASING",
+            IssueCode::VerticalPositiveInvalid => "CF-VERTICAL-POSITIVE-INVALID",
+            IssueCode::VerticalPositiveType => "CF-VERTICAL-POSITIVE-TYPE",
+            IssueCode::VerticalPositiveMissing => "CF-VERTICAL-POSITIVE-MISSING",
+            IssueCode::LatRange => "CF-LAT-RANGE",
+            IssueCode::LonRange => "CF-LON-RANGE",
+            IssueCode::BoundsMissing => "CF-BOUNDS-MISSING",
+            IssueCode::BoundsShape => "CF-BOUNDS-SHAPE",
+            IssueCode::BoundsLengthMismatch => "CF-BOUNDS-LENGTH-MISMATCH",
+            IssueCode::BoundsVertices => "CF-BOUNDS-VERTICES",
+            IssueCode::GridMappingFound => "CF-GRID-MAPPING-FOUND",
+            IssueCode::GridMappingMissing => "CF-GRID-MAPPING-MISSING",
+            IssueCode::GridMappingNameMissing => "CF-GRID-MAPPING-NAME-MISSING",
+            IssueCode::GridMappingNameType => "CF-GRID-MAPPING-NAME-TYPE",
+            IssueCode::CoordinatesRefMissing => "CF-COORDINATES-REF-MISSING",
+        }
+    }
+
+    /// Section of the CF conventions document the check is based on, if any.
+    ///
+    /// Dimension-name checks are Zarr/xarray conventions rather than CF, so they have none.
+    pub fn cf_section(self) -> Option<&'static str> {
+        match self {
+            IssueCode::ConventionsPresent
+            | IssueCode::ConventionsMissing
+            | IssueCode::ConventionsNotCf
+            | IssueCode::ConventionsType => Some("2.6.1"),
+            IssueCode::DimNamesMissing
+            | IssueCode::DimNamesType
+            | IssueCode::DimNamesLength
+            | IssueCode::DimNamesNonString
+            | IssueCode::DimNamesEmpty
+            | IssueCode::DimNamesDuplicate
+            | IssueCode::CoordReadSkipped => None,
+            IssueCode::CoordUnitsMissing | IssueCode::CoordUnitsType => Some("3.1"),
+            IssueCode::CoordStandardName => Some("3.3"),
+            IssueCode::CoordEmpty
+            | IssueCode::CoordMonotonic
+            | IssueCode::CoordConstant
+            | IssueCode::CoordNotMonotonic
+            | IssueCode::CoordsNone
+            | IssueCode::DimNoCoord => Some("1.3"),
+            IssueCode::LatRange => Some("4.1"),
+            IssueCode::LonRange => Some("4.2"),
+            IssueCode::VerticalPositiveInvalid
+            | IssueCode::VerticalPositiveType
+            | IssueCode::VerticalPositiveMissing => Some("4.3"),
+            IssueCode::TimeUnitsInvalid | IssueCode::TimeDecreasing => Some("4.4"),
+            IssueCode::TimeCalendarType => Some("4.4.1"),
+            IssueCode::CoordinatesRefMissing => Some("5"),
+            IssueCode::GridMappingFound
+            | IssueCode::GridMappingMissing
+            | IssueCode::GridMappingNameMissing
+            | IssueCode::GridMappingNameType => Some("5.6"),
+            IssueCode::BoundsMissing
+            | IssueCode::BoundsShape
+            | IssueCode::BoundsLengthMismatch
+            | IssueCode::BoundsVertices => Some("7.1"),
+        }
+    }
+
+    /// Short, actionable suggestion for resolving the issue (None for informational codes).
+    pub fn suggestion(self) -> Option<&'static str> {
+        let s = match self {
+            IssueCode::ConventionsPresent
+            | IssueCode::CoordStandardName
+            | IssueCode::CoordMonotonic
+            | IssueCode::GridMappingFound => return None,
+            IssueCode::ConventionsMissing => {
+                "Add a global attribute Conventions = \"CF-1.8\" (or the CF version the data follows)."
+            }
+            IssueCode::ConventionsNotCf => {
+                "Include the CF version in Conventions, e.g. \"CF-1.8\" (multiple conventions may be listed)."
+            }
+            IssueCode::ConventionsType => "Store Conventions as a single string.",
+            IssueCode::DimNamesMissing => {
+                "Write dimension names via _ARRAY_DIMENSIONS (Zarr v2) or dimension_names (Zarr v3)."
+            }
+            IssueCode::DimNamesType => "Store the dimension names as an array of strings.",
+            IssueCode::DimNamesLength => "Provide exactly one dimension name per array dimension.",
+            IssueCode::DimNamesNonString => "Replace non-string entries with dimension names.",
+            IssueCode::DimNamesEmpty => "Give every dimension a non-empty name.",
+            IssueCode::DimNamesDuplicate => "Use distinct names for each dimension of the array.",
+            IssueCode::CoordEmpty => "Write the coordinate values or drop the empty coordinate.",
+            IssueCode::CoordUnitsMissing => {
+                "Add a 'units' attribute (e.g. \"degrees_north\", \"days since 1850-01-01\", \"m\")."
+            }
+            IssueCode::CoordUnitsType => "Store 'units' as a string.",
+            IssueCode::CoordConstant | IssueCode::CoordNotMonotonic => {
+                "Coordinate values must be strictly monotonic; sort the data along this dimension."
+            }
+            IssueCode::CoordReadSkipped => {
+                "Check that the coordinate chunks exist and use a supported dtype and codec."
+            }
+            IssueCode::CoordsNone | IssueCode::DimNoCoord => {
+                "Add a 1-D coordinate variable named like the dimension, holding its values."
+            }
+            IssueCode::TimeUnitsInvalid => {
+                "Use '<unit> since <reference date>', e.g. \"days since 1850-01-01\"."
+            }
+            IssueCode::TimeCalendarType => {
+                "Store 'calendar' as a string, e.g. \"standard\" or \"noleap\"."
+            }
+            IssueCode::TimeDecreasing => "Store time values in increasing order.",
+            IssueCode::VerticalPositiveInvalid | IssueCode::VerticalPositiveType => {
+                "Set positive = \"up\" or positive = \"down\"."
+            }
+            IssueCode::VerticalPositiveMissing => {
+                "Add positive = \"up\" or \"down\" to indicate the direction of increasing values."
+            }
+            IssueCode::LatRange => {
+                "Latitude values in degrees_north must lie within [-90, 90]; check units and values."
+            }
+            IssueCode::LonRange => {
+                "Longitude values in degrees_east should lie within [-360, 360]; check units and values."
+            }
+            IssueCode::BoundsMissing => {
+                "Add the referenced bounds variable or remove the 'bounds' attribute."
+            }
+            IssueCode::BoundsShape | IssueCode::BoundsLengthMismatch => {
+                "Bounds variables should have shape (n, 2) where n is the coordinate length."
+            }
+            IssueCode::BoundsVertices => {
+                "1-D coordinates normally have 2 bounds per cell; check the trailing dimension."
+            }
+            IssueCode::GridMappingMissing => {
+                "Add the referenced grid mapping variable or fix the 'grid_mapping' attribute."
+            }
+            IssueCode::GridMappingNameMissing | IssueCode::GridMappingNameType => {
+                "Set grid_mapping_name to a CF grid mapping name, e.g. \"latitude_longitude\"."
+            }
+            IssueCode::CoordinatesRefMissing => {
+                "List only existing variables in 'coordinates', or add the missing variable."
+            }
+        };
+        Some(s)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Issue {
+    pub level: Level,
+    pub code: IssueCode,
+    /// Path of the variable the issue concerns (`None` for store-level issues)
+    pub variable: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct CfReport {
+    issues: Vec<Issue>,
+    warnings: usize,
+    errors: usize,
+}
+
+impl CfReport {
+    fn push(&mut self, level: Level, code: IssueCode, variable: Option<&str>, msg: String) {
+        self.issues.push(Issue {
+            level,
+            code,
+            variable: variable.map(|v| v.to_string()),
+            message: msg,
+        });
+    }
+
+    pub fn info(&mut self, code: IssueCode, variable: Option<&str>, msg: impl Into<String>) {
+        self.push(Level::Info, code, variable, msg.into());
+    }
+
+    pub fn warn(&mut self, code: IssueCode, variable: Option<&str>, msg: impl Into<String>) {
+        self.warnings += 1;
+        self.push(Level::Warning, code, variable, msg.into());
+    }
+
+    pub fn error(&mut self, code: IssueCode, variable: Option<&str>, msg: impl Into<String>) {
+        self.errors += 1;
+        self.push(Level::Error, code, variable, msg.into());
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.errors > 0
+    }
+
+    pub fn print(&self) {
+        println!("cf-check {{");
+
+        for issue in &self.issues {
+            println!(
+                "  {}: [{}] {}",
+                issue.level.tag(),
+                issue.code.as_str(),
+                issue.message
+            );
+        }
+
+        println!("}}");
+        println!(
+            "Summary: {} warnings, {} errors",
+            self.warnings, self.errors
+        );
+    }
+
+    pub fn to_json(&self, store: &str) -> serde_json::Value {
+        let issues: Vec<serde_json::Value> = self
+            .issues
+            .iter()
+            .map(|issue| {
+                json!({
+                    "code": issue.code.as_str(),
+                    "level": issue.level,
+                    "variable": issue.variable,
+                    "cf_section": issue.code.cf_section(),
+                    "message": issue.message,
+                    "suggestion": issue.code.suggestion(),
+                })
+            })
+            .collect();
+
+        json!({
+            "schema_version": SCHEMA_VERSION,
+            "store": store,
+            "summary": {
+                "info": self.issues.len() - self.warnings - self.errors,
+                "warnings": self.warnings,
+                "errors": self.errors,
+            },
+            "issues": issues,
+        })
+    }
+
+    /// Render the report as a SARIF 2.1.0 log.
+    pub fn to_sarif(&self, store: &str) -> serde_json::Value {
+        let mut codes: Vec<IssueCode> = self.issues.iter().map(|i| i.code).collect();
+        codes.sort();
+        codes.dedup();
+
+        let rules: Vec<serde_json::Value> = codes
+            .iter()
+            .map(|code| {
+                let mut rule = json!({
+                    "id": code.as_str(),
+                    "properties": { "cf_section": code.cf_section() },
+                });
+                if let Some(fix) = code.suggestion() {
+                    rule["help"] = json!({ "text": fix });
+                }
+                rule
+            })
+            .collect();
+
+        let results: Vec<serde_json::Value> = self
+            .issues
+            .iter()
+            .map(|issue| {
+                let uri = match &issue.variable {
+                    Some(var) => format!("{}/{}", store.trim_end_matches('/'), var),
+                    None => store.to_string(),
+                };
+                let mut location = json!({
+                    "physicalLocation": { "artifactLocation": { "uri": uri } },
+                });
+                if let Some(var) = &issue.variable {
+                    location["logicalLocations"] =
+                        json!([{ "fullyQualifiedName": var, "kind": "variable" }]);
+                }
+                json!({
+                    "ruleId": issue.code.as_str(),
+                    "level": issue.level.sarif_level(),
+                    "message": { "text": issue.message },
+                    "locations": [location],
+                })
+            })
+            .collect();
+
+        json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "zarr-dump cf-check",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "rules": rules,
+                    }
+                },
+                "results": results,
+            }],
+        })
+    }
+
+    /// Render the report as a JUnit XML document.
+    ///
+    /// Each variable (plus a `<global>` case for store-level checks) becomes a test case;
+    /// warnings and errors are reported as failures, informational notes as system-out.
+    pub fn to_junit(&self, store: &str, variables: &[String]) -> String {
+        const GLOBAL: &str = "<global>";
+
+        let mut cases: Vec<&str> = vec![GLOBAL];
+        let mut sorted_vars: Vec<&str> = variables.iter().map(|s| s.as_str()).collect();
+        sorted_vars.sort();
+        cases.extend(sorted_vars);
+        for issue in &self.issues {
+            if let Some(var) = issue.variable.as_deref()
+                && !cases.contains(&var)
+            {
+                cases.push(var);
+            }
+        }
+
+        let failing_cases = cases
+            .iter()
+            .filter(|case| {
+                self.issues.iter().any(|i| {
+                    i.level != Level::Info && i.variable.as_deref().unwrap_or(GLOBAL) == **case
+                })
+            })
+            .count();
+
+        let mut out = String::new();
+        out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str(&format!(
+            "<testsuites name=\"cf-check\" tests=\"{}\" failures=\"{}\">\n",
+            cases.len(),
+            failing_cases
+        ));
+        out.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\">\n",
+            xml_escape(store),
+            cases.len(),
+            failing_cases
+        ));
+
+        for case in cases {
+            let issues: Vec<&Issue> = self
+                .issues
+                .iter()
+                .filter(|i| i.variable.as_deref().unwrap_or(GLOBAL) == case)
+                .collect();
+
+            out.push_str(&format!(
+                "    <testcase classname=\"cf-check\" name=\"{}\">\n",
+                xml_escape(case)
+            ));
+            for issue in issues.iter().filter(|i| i.level != Level::Info) {
+                let mut body = issue.message.clone();
+                if let Some(section) = issue.code.cf_section() {
+                    body.push_str(&format!("\nCF section: {}", section));
+                }
+                if let Some(fix) = issue.code.suggestion() {
+                    body.push_str(&format!("\nSuggestion: {}", fix));
+                }
+                out.push_str(&format!(
+                    "      <failure type=\"{}\" message=\"{}\">{}</failure>\n",
+                    issue.code.as_str(),
+                    xml_escape(&format!("{}: {}", issue.level.tag(), issue.message)),
+                    xml_escape(&body)
+                ));
+            }
+            let notes: Vec<String> = issues
+                .iter()
+                .filter(|i| i.level == Level::Info)
+                .map(|i| format!("[{}] {}", i.code.as_str(), i.message))
+                .collect();
+            if !notes.is_empty() {
+                out.push_str(&format!(
+                    "      <system-out>{}</system-out>\n",
+                    xml_escape(&notes.join("\n"))
+                ));
+            }
+            out.push_str("    </testcase>\n");
+        }
+
+        out.push_str("  </testsuite>\n");
+        out.push_str("</testsuites>\n");
+        out
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_report() -> CfReport {
+        let mut report = CfReport::default();
+        report.warn(IssueCode::ConventionsMissing, None, "Conventions missing");
+        report.warn(
+            IssueCode::CoordUnitsMissing,
+            Some("lat"),
+            "Coordinate variable 'lat' is missing 'units' attribute.",
+        );
+        report.error(
+            IssueCode::DimNamesLength,
+            Some("grp/t<2>"),
+            "length mismatch",
+        );
+        report.info(IssueCode::CoordMonotonic, Some("lat"), "increasing");
+        report
+    }
+
+    #[test]
+    fn test_json_report_fields() {
+        let json = sample_report().to_json("store.zarr");
+        assert_eq!(json["summary"]["warnings"], 2);
+        assert_eq!(json["summary"]["errors"], 1);
+        assert_eq!(json["summary"]["info"], 1);
+
+        let issue = &json["issues"][1];
+        assert_eq!(issue["code"], "CF-COORD-UNITS-MISSING");
+        assert_eq!(issue["level"], "warning");
+        assert_eq!(issue["variable"], "lat");
+        assert_eq!(issue["cf_section"], "3.1");
+        assert!(issue["suggestion"].as_str().unwrap().contains("units"));
+        assert!(json["issues"][0]["variable"].is_null());
+    }
+
+    #[test]
+    fn test_sarif_report() {
+        let sarif = sample_report().to_sarif("store.zarr");
+        assert_eq!(sarif["version"], "2.1.0");
+        let run = &sarif["runs"][0];
+        assert_eq!(run["results"].as_array().unwrap().len(), 4);
+        assert_eq!(run["results"][2]["level"], "error");
+        assert_eq!(
+            run["results"][1]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "store.zarr/lat"
+        );
+        // Rules are deduplicated
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_junit_report() {
+        let xml = sample_report().to_junit("store.zarr", &["lat".to_string(), "lon".to_string()]);
+        assert!(xml.contains("tests=\"4\" failures=\"3\""));
+        assert!(xml.contains("<testcase classname=\"cf-check\" name=\"lon\">"));
+        assert!(xml.contains("name=\"grp/t&lt;2&gt;\""));
+        assert!(xml.contains("<failure type=\"CF-COORD-UNITS-MISSING\""));
+        assert!(xml.contains("<system-out>[CF-COORD-MONOTONIC] increasing</system-out>"));
+    }
+}
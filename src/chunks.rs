@@ -0,0 +1,116 @@
+//! Chunk inventory report of the `chunks` subcommand
+
+use crate::format::format_table;
+use crate::metadata::{ChunkInventory, StoredChunk};
+use serde_json::{Value, json};
+
+/// Byte count with a binary unit (`512 B`, `1.5 KiB`, `2.0 GiB`)
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Aligned text table with one row per variable; chunks are shown as stored/expected
+pub fn render_table(rows: &[(String, ChunkInventory)]) -> String {
+    let bytes = |v: Option<u64>| v.map_or_else(|| "-".to_string(), format_bytes);
+    let chunk = |c: &Option<StoredChunk>| {
+        c.as_ref().map_or_else(
+            || "-".to_string(),
+            |c| format!("{} ({})", format_bytes(c.size), c.key),
+        )
+    };
+
+    let mut table = vec![
+        [
+            "variable",
+            "chunks",
+            "stored",
+            "uncompressed",
+            "ratio",
+            "smallest",
+            "largest",
+        ]
+        .map(String::from)
+        .to_vec(),
+    ];
+    for (name, inventory) in rows {
+        table.push(vec![
+            name.clone(),
+            format!("{}/{}", inventory.stored_chunks, inventory.expected_chunks),
+            format_bytes(inventory.stored_bytes),
+            bytes(inventory.array_bytes),
+            inventory
+                .compression_ratio()
+                .map_or_else(|| "-".to_string(), |r| format!("{:.2}", r)),
+            chunk(&inventory.smallest),
+            chunk(&inventory.largest),
+        ]);
+    }
+    format_table(&table)
+}
+
+pub fn to_json(inventory: &ChunkInventory) -> Value {
+    let chunk = |c: &Option<StoredChunk>| {
+        c.as_ref()
+            .map_or(Value::Null, |c| json!({"key": c.key, "bytes": c.size}))
+    };
+    json!({
+        "expected_chunks": inventory.expected_chunks,
+        "stored_chunks": inventory.stored_chunks,
+        "missing_chunks": inventory.expected_chunks - inventory.stored_chunks,
+        "stored_bytes": inventory.stored_bytes,
+        "uncompressed_bytes": inventory.array_bytes,
+        "compression_ratio": inventory.compression_ratio(),
+        "smallest_chunk": chunk(&inventory.smallest),
+        "largest_chunk": chunk(&inventory.largest),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 << 30), "3.0 GiB");
+    }
+
+    #[test]
+    fn test_chunk_table() {
+        let inventory = ChunkInventory {
+            expected_chunks: 4,
+            stored_chunks: 2,
+            stored_bytes: 200,
+            chunk_bytes: Some(400),
+            array_bytes: Some(1600),
+            smallest: Some(StoredChunk {
+                key: "0.1".to_string(),
+                size: 50,
+            }),
+            largest: Some(StoredChunk {
+                key: "0.0".to_string(),
+                size: 150,
+            }),
+        };
+        assert_eq!(
+            render_table(&[("tas".to_string(), inventory.clone())]),
+            "variable  chunks  stored  uncompressed  ratio    smallest      largest\n\
+             tas          2/4   200 B       1.6 KiB   4.00  50 B (0.1)  150 B (0.0)\n"
+        );
+        let json = to_json(&inventory);
+        assert_eq!(json["missing_chunks"], 2);
+        assert_eq!(json["compression_ratio"], 4.0);
+    }
+}
@@ -0,0 +1,540 @@
+//! Typed model of the codecs Zarr arrays are encoded with.
+//!
+//! Zarr v2 arrays declare an optional `compressor` and a list of `filters`, while v3 arrays
+//! declare a single `codecs` chain. Both are parsed into [`Codec`] values so compression
+//! settings can be compared across stores and formats.
+
+use crate::metadata::ZarrV3Codec;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use std::fmt;
+
+/// A single codec and its configuration.
+///
+/// Well-known codecs are parsed into typed variants; anything else (or a well-known codec
+/// with an unexpected configuration) is kept as [`Codec::Other`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Codec {
+    Blosc {
+        cname: String,
+        clevel: i64,
+        /// `noshuffle`, `shuffle`, `bitshuffle` (or `auto` for v2's -1)
+        shuffle: String,
+        blocksize: u64,
+        typesize: Option<u64>,
+    },
+    Zstd {
+        level: i64,
+        checksum: bool,
+    },
+    Gzip {
+        level: i64,
+    },
+    Zlib {
+        level: i64,
+    },
+    Bz2 {
+        level: i64,
+    },
+    Lz4 {
+        acceleration: i64,
+    },
+    Delta {
+        dtype: String,
+        astype: Option<String>,
+    },
+    FixedScaleOffset {
+        offset: f64,
+        scale: f64,
+        dtype: String,
+        astype: Option<String>,
+    },
+    /// The v3 `bytes` codec (`endian` is omitted for single-byte data types)
+    Bytes {
+        endian: Option<String>,
+    },
+    Transpose {
+        order: Vec<u64>,
+    },
+    Crc32c,
+    ShardingIndexed(Box<ShardingInfo>),
+    /// A numcodecs codec declared on a v3 array as `numcodecs.<id>`: `codec` is the codec with
+    /// that id, and `name` keeps the declared name for display and comparison
+    Numcodecs {
+        name: String,
+        codec: Box<Codec>,
+    },
+    Other {
+        name: String,
+        configuration: Map<String, Value>,
+    },
+}
+
+/// Configuration of the v3 `sharding_indexed` codec: each chunk of the array is a shard
+/// holding a grid of inner chunks plus an index of their byte ranges
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShardingInfo {
+    pub inner_chunk_shape: Vec<u64>,
+    /// Codecs applied to each inner chunk
+    pub codecs: Vec<Codec>,
+    /// Codecs applied to the shard index
+    pub index_codecs: Vec<Codec>,
+    /// `"start"` or `"end"` of the shard
+    pub index_location: String,
+}
+
+#[derive(Deserialize)]
+struct ShardingConfiguration {
+    chunk_shape: Vec<u64>,
+    codecs: Vec<ZarrV3Codec>,
+    #[serde(default)]
+    index_codecs: Vec<ZarrV3Codec>,
+    #[serde(default = "default_index_location")]
+    index_location: String,
+}
+
+fn default_index_location() -> String {
+    "end".to_string()
+}
+
+impl Codec {
+    /// Parse a v2 `compressor` or `filters` entry (an object with an `id` and its parameters)
+    pub fn from_v2(value: &Value) -> Codec {
+        let name = value
+            .get("id")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown")
+            .to_string();
+        // Rebuilt rather than removing `id`, so the remaining parameters keep their order
+        let configuration: Map<String, Value> = value
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter(|(key, _)| *key != "id")
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        Self::parse(&name, &configuration).unwrap_or(Codec::Other {
+            name,
+            configuration,
+        })
+    }
+
+    /// Parse an entry of a v3 `codecs` chain
+    pub fn from_v3(codec: &ZarrV3Codec) -> Result<Codec> {
+        let configuration = match &codec.configuration {
+            Some(Value::Object(map)) => map.clone(),
+            _ => Map::new(),
+        };
+
+        if codec.name == "sharding_indexed" {
+            let sharding: ShardingConfiguration =
+                serde_json::from_value(Value::Object(configuration))
+                    .context("Invalid sharding_indexed configuration")?;
+            let parse_all = |codecs: &[ZarrV3Codec]| -> Result<Vec<Codec>> {
+                codecs.iter().map(Codec::from_v3).collect()
+            };
+            return Ok(Codec::ShardingIndexed(Box::new(ShardingInfo {
+                inner_chunk_shape: sharding.chunk_shape,
+                codecs: parse_all(&sharding.codecs)?,
+                index_codecs: parse_all(&sharding.index_codecs)?,
+                index_location: sharding.index_location,
+            })));
+        }
+
+        // zarr-python exposes numcodecs codecs to v3 arrays as `numcodecs.<id>`
+        if let Some(id) = codec.name.strip_prefix("numcodecs.")
+            && let Some(parsed) = Self::parse(id, &configuration)
+        {
+            return Ok(Codec::Numcodecs {
+                name: codec.name.clone(),
+                codec: Box::new(parsed),
+            });
+        }
+        Ok(
+            Self::parse(&codec.name, &configuration).unwrap_or(Codec::Other {
+                name: codec.name.clone(),
+                configuration,
+            }),
+        )
+    }
+
+    /// Typed codec for a well-known `name`, or `None` if the name or configuration is not
+    /// recognized
+    fn parse(name: &str, config: &Map<String, Value>) -> Option<Codec> {
+        let string = |key: &str| config.get(key).and_then(Value::as_str).map(str::to_string);
+        let int = |key: &str| config.get(key).and_then(Value::as_i64);
+        let float = |key: &str| config.get(key).and_then(Value::as_f64);
+
+        Some(match name {
+            "blosc" => Codec::Blosc {
+                cname: string("cname")?,
+                clevel: int("clevel")?,
+                shuffle: match config.get("shuffle") {
+                    None => "shuffle".to_string(),
+                    Some(Value::String(s)) => s.clone(),
+                    Some(v) => match v.as_i64()? {
+                        -1 => "auto",
+                        0 => "noshuffle",
+                        1 => "shuffle",
+                        2 => "bitshuffle",
+                        _ => return None,
+                    }
+                    .to_string(),
+                },
+                blocksize: config.get("blocksize").map_or(Some(0), Value::as_u64)?,
+                typesize: config.get("typesize").and_then(Value::as_u64),
+            },
+            "zstd" => Codec::Zstd {
+                level: int("level").unwrap_or(0),
+                checksum: config
+                    .get("checksum")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false),
+            },
+            "gzip" => Codec::Gzip {
+                level: int("level")?,
+            },
+            "zlib" => Codec::Zlib {
+                level: int("level")?,
+            },
+            "bz2" => Codec::Bz2 {
+                level: int("level")?,
+            },
+            "lz4" => Codec::Lz4 {
+                acceleration: int("acceleration").unwrap_or(1),
+            },
+            "delta" => Codec::Delta {
+                dtype: string("dtype")?,
+                astype: string("astype"),
+            },
+            "fixedscaleoffset" => Codec::FixedScaleOffset {
+                offset: float("offset")?,
+                scale: float("scale")?,
+                dtype: string("dtype")?,
+                astype: string("astype"),
+            },
+            "bytes" => Codec::Bytes {
+                endian: string("endian"),
+            },
+            "transpose" => Codec::Transpose {
+                order: config
+                    .get("order")?
+                    .as_array()?
+                    .iter()
+                    .map(Value::as_u64)
+                    .collect::<Option<_>>()?,
+            },
+            "crc32c" => Codec::Crc32c,
+            _ => return None,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Codec::Blosc { .. } => "blosc",
+            Codec::Zstd { .. } => "zstd",
+            Codec::Gzip { .. } => "gzip",
+            Codec::Zlib { .. } => "zlib",
+            Codec::Bz2 { .. } => "bz2",
+            Codec::Lz4 { .. } => "lz4",
+            Codec::Delta { .. } => "delta",
+            Codec::FixedScaleOffset { .. } => "fixedscaleoffset",
+            Codec::Bytes { .. } => "bytes",
+            Codec::Transpose { .. } => "transpose",
+            Codec::Crc32c => "crc32c",
+            Codec::ShardingIndexed(_) => "sharding_indexed",
+            Codec::Numcodecs { name, .. } | Codec::Other { name, .. } => name,
+        }
+    }
+
+    /// Parameters of the codec, in the order they are usually written
+    pub fn configuration(&self) -> Map<String, Value> {
+        let value = match self {
+            Codec::Blosc {
+                cname,
+                clevel,
+                shuffle,
+                blocksize,
+                typesize,
+            } => {
+                let mut value = json!({
+                    "cname": cname,
+                    "clevel": clevel,
+                    "shuffle": shuffle,
+                    "blocksize": blocksize,
+                });
+                if let Some(typesize) = typesize {
+                    value["typesize"] = json!(typesize);
+                }
+                value
+            }
+            Codec::Zstd { level, checksum } => json!({"level": level, "checksum": checksum}),
+            Codec::Gzip { level } | Codec::Zlib { level } | Codec::Bz2 { level } => {
+                json!({"level": level})
+            }
+            Codec::Lz4 { acceleration } => json!({"acceleration": acceleration}),
+            Codec::Delta { dtype, astype } => {
+                let mut value = json!({"dtype": dtype});
+                if let Some(astype) = astype {
+                    value["astype"] = json!(astype);
+                }
+                value
+            }
+            Codec::FixedScaleOffset {
+                offset,
+                scale,
+                dtype,
+                astype,
+            } => {
+                let mut value = json!({"offset": offset, "scale": scale, "dtype": dtype});
+                if let Some(astype) = astype {
+                    value["astype"] = json!(astype);
+                }
+                value
+            }
+            Codec::Bytes { endian } => match endian {
+                Some(endian) => json!({"endian": endian}),
+                None => json!({}),
+            },
+            Codec::Transpose { order } => json!({"order": order}),
+            Codec::Crc32c => json!({}),
+            Codec::ShardingIndexed(sharding) => json!({
+                "chunk_shape": sharding.inner_chunk_shape,
+                "codecs": sharding.codecs.iter().map(Codec::to_json).collect::<Vec<_>>(),
+                "index_codecs": sharding.index_codecs.iter().map(Codec::to_json).collect::<Vec<_>>(),
+                "index_location": sharding.index_location,
+            }),
+            Codec::Numcodecs { codec, .. } => return codec.configuration(),
+            Codec::Other { configuration, .. } => return configuration.clone(),
+        };
+        match value {
+            Value::Object(map) => map,
+            _ => unreachable!("codec configurations are JSON objects"),
+        }
+    }
+
+    /// `{"name": ..., "configuration": {...}}`, as in v3 array metadata
+    pub fn to_json(&self) -> Value {
+        json!({"name": self.name(), "configuration": self.configuration()})
+    }
+
+    /// The codec this one is an alias of (the `<id>` codec of `numcodecs.<id>`), or itself
+    pub fn resolved(&self) -> &Codec {
+        match self {
+            Codec::Numcodecs { codec, .. } => codec,
+            _ => self,
+        }
+    }
+
+    /// Whether the codec turns an array into bytes (v3 requires exactly one per chain)
+    fn is_array_to_bytes(&self) -> bool {
+        matches!(
+            self.resolved(),
+            Codec::Bytes { .. } | Codec::ShardingIndexed(_)
+        ) || matches!(self, Codec::Other { name, .. } if name == "endian" || name.starts_with("vlen-"))
+    }
+}
+
+/// `name(key=value, ...)`, e.g. `blosc(cname=lz4, clevel=5, shuffle=shuffle, blocksize=0)`
+impl fmt::Display for Codec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let params: Vec<String> = match self {
+            // Nested codec chains are written with the same notation
+            Codec::ShardingIndexed(sharding) => vec![
+                format!("chunk_shape={:?}", sharding.inner_chunk_shape),
+                format!("codecs=[{}]", join_codecs(&sharding.codecs)),
+                format!("index_codecs=[{}]", join_codecs(&sharding.index_codecs)),
+                format!("index_location={}", sharding.index_location),
+            ],
+            _ => self
+                .configuration()
+                .iter()
+                .map(|(key, value)| match value {
+                    Value::String(s) => format!("{}={}", key, s),
+                    other => format!("{}={}", key, other),
+                })
+                .collect(),
+        };
+        if params.is_empty() {
+            write!(f, "{}", self.name())
+        } else {
+            write!(f, "{}({})", self.name(), params.join(", "))
+        }
+    }
+}
+
+fn join_codecs(codecs: &[Codec]) -> String {
+    codecs
+        .iter()
+        .map(Codec::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The codecs an array's chunks are encoded with
+#[derive(Debug, Clone, PartialEq)]
+pub enum CodecPipeline {
+    /// Zarr v2: `filters` applied in order, then the optional `compressor`
+    V2 {
+        filters: Vec<Codec>,
+        compressor: Option<Codec>,
+    },
+    /// Zarr v3 `codecs` chain
+    V3(Vec<Codec>),
+}
+
+impl Default for CodecPipeline {
+    fn default() -> Self {
+        CodecPipeline::V2 {
+            filters: Vec::new(),
+            compressor: None,
+        }
+    }
+}
+
+impl CodecPipeline {
+    /// All codecs in the order they are applied when encoding
+    pub fn codecs(&self) -> Vec<&Codec> {
+        match self {
+            CodecPipeline::V2 {
+                filters,
+                compressor,
+            } => filters.iter().chain(compressor).collect(),
+            CodecPipeline::V3(codecs) => codecs.iter().collect(),
+        }
+    }
+
+    /// The v2 compressor, or the first compression codec applied to the bytes of a v3 chunk
+    pub fn compressor(&self) -> Option<&Codec> {
+        match self {
+            CodecPipeline::V2 { compressor, .. } => compressor.as_ref(),
+            CodecPipeline::V3(codecs) => codecs
+                .iter()
+                .skip_while(|c| !c.is_array_to_bytes())
+                .skip(1)
+                .find(|c| !matches!(c.resolved(), Codec::Crc32c)),
+        }
+    }
+
+    /// The v2 filters, or the array-to-array codecs of a v3 chain (e.g. `transpose`)
+    pub fn filters(&self) -> Vec<&Codec> {
+        match self {
+            CodecPipeline::V2 { filters, .. } => filters.iter().collect(),
+            CodecPipeline::V3(codecs) => codecs
+                .iter()
+                .take_while(|c| !c.is_array_to_bytes())
+                .collect(),
+        }
+    }
+
+    /// Configuration of the `sharding_indexed` codec, if the array is sharded
+    pub fn sharding(&self) -> Option<&ShardingInfo> {
+        self.codecs().into_iter().find_map(|codec| match codec {
+            Codec::ShardingIndexed(sharding) => Some(sharding.as_ref()),
+            _ => None,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.codecs().is_empty()
+    }
+}
+
+/// Codecs separated by `, `, e.g. `delta(dtype=<i4), zlib(level=5)`
+impl fmt::Display for CodecPipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let codecs: Vec<Codec> = self.codecs().into_iter().cloned().collect();
+        write!(f, "{}", join_codecs(&codecs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_v2_codecs() {
+        let blosc = Codec::from_v2(
+            &json!({"id": "blosc", "cname": "lz4", "clevel": 5, "shuffle": 2, "blocksize": 0}),
+        );
+        assert_eq!(
+            blosc,
+            Codec::Blosc {
+                cname: "lz4".to_string(),
+                clevel: 5,
+                shuffle: "bitshuffle".to_string(),
+                blocksize: 0,
+                typesize: None,
+            }
+        );
+        assert_eq!(
+            blosc.to_string(),
+            "blosc(cname=lz4, clevel=5, shuffle=bitshuffle, blocksize=0)"
+        );
+
+        let scale = Codec::from_v2(&json!({
+            "id": "fixedscaleoffset", "offset": 0, "scale": 100, "dtype": "<f8", "astype": "<i2"
+        }));
+        assert_eq!(
+            scale.to_string(),
+            "fixedscaleoffset(offset=0.0, scale=100.0, dtype=<f8, astype=<i2)"
+        );
+
+        // Unknown codecs keep their parameters
+        let other = Codec::from_v2(&json!({"id": "quantize", "digits": 3, "dtype": "<f4"}));
+        assert_eq!(other.name(), "quantize");
+        assert_eq!(other.to_string(), "quantize(digits=3, dtype=<f4)");
+
+        let pipeline = CodecPipeline::V2 {
+            filters: vec![Codec::from_v2(&json!({"id": "delta", "dtype": "<i4"}))],
+            compressor: Some(Codec::from_v2(&json!({"id": "zlib", "level": 5}))),
+        };
+        assert_eq!(pipeline.to_string(), "delta(dtype=<i4), zlib(level=5)");
+        assert_eq!(pipeline.compressor().unwrap().name(), "zlib");
+        assert_eq!(pipeline.filters().len(), 1);
+    }
+
+    #[test]
+    fn test_v3_codecs() {
+        let parse = |value: Value| {
+            Codec::from_v3(&serde_json::from_value::<ZarrV3Codec>(value).unwrap()).unwrap()
+        };
+        let pipeline = CodecPipeline::V3(vec![
+            parse(json!({"name": "transpose", "configuration": {"order": [1, 0]}})),
+            parse(json!({"name": "bytes", "configuration": {"endian": "little"}})),
+            parse(json!({"name": "zstd", "configuration": {"level": 3, "checksum": true}})),
+            parse(json!({"name": "crc32c"})),
+        ]);
+        assert_eq!(
+            pipeline.to_string(),
+            "transpose(order=[1,0]), bytes(endian=little), zstd(level=3, checksum=true), crc32c"
+        );
+        assert_eq!(pipeline.compressor().unwrap().name(), "zstd");
+        assert_eq!(pipeline.filters()[0].name(), "transpose");
+        assert!(pipeline.sharding().is_none());
+
+        let sharded = CodecPipeline::V3(vec![parse(json!({
+            "name": "sharding_indexed",
+            "configuration": {
+                "chunk_shape": [2, 2],
+                "codecs": [{"name": "bytes", "configuration": {"endian": "little"}},
+                           {"name": "numcodecs.zlib", "configuration": {"level": 1}}],
+                "index_codecs": [{"name": "bytes", "configuration": {"endian": "little"}},
+                                 {"name": "crc32c"}]
+            }
+        }))]);
+        let sharding = sharded.sharding().unwrap();
+        assert_eq!(sharding.inner_chunk_shape, vec![2, 2]);
+        // The declared name is kept, while the configuration is parsed as the `zlib` codec
+        assert_eq!(sharding.codecs[1].name(), "numcodecs.zlib");
+        assert_eq!(sharding.codecs[1].resolved(), &Codec::Zlib { level: 1 });
+        assert_eq!(sharding.codecs[1].to_string(), "numcodecs.zlib(level=1)");
+        assert_eq!(sharding.index_location, "end");
+        assert!(sharded.compressor().is_none());
+        assert_eq!(
+            sharded.codecs()[0].to_json()["configuration"]["codecs"][1],
+            json!({"name": "numcodecs.zlib", "configuration": {"level": 1}})
+        );
+    }
+}
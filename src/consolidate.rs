@@ -0,0 +1,141 @@
+//! Writing and checking consolidated metadata for the `consolidate` subcommand
+
+use crate::diff::ChangeKind;
+use crate::fsutil::write_atomically;
+use anyhow::{Context, Result};
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// A consolidated metadata entry that does not match the per-node metadata files
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaleEntry {
+    /// `Added` for nodes missing from the consolidated metadata, `Removed` for entries of nodes
+    /// no longer in the store and `Changed` for entries that differ from the node's file
+    pub kind: ChangeKind,
+    pub key: String,
+}
+
+/// Entries of the consolidated metadata (`stored`) that differ from the per-node metadata
+/// documents (`scanned`), sorted by key
+pub fn stale_entries(
+    stored: &BTreeMap<String, Value>,
+    scanned: &BTreeMap<String, Value>,
+) -> Vec<StaleEntry> {
+    let keys: BTreeSet<&String> = stored.keys().chain(scanned.keys()).collect();
+    keys.into_iter()
+        .filter_map(|key| {
+            let kind = match (stored.get(key), scanned.get(key)) {
+                (None, Some(_)) => ChangeKind::Added,
+                (Some(_), None) => ChangeKind::Removed,
+                (Some(a), Some(b)) if a != b => ChangeKind::Changed,
+                _ => return None,
+            };
+            Some(StaleEntry {
+                kind,
+                key: key.clone(),
+            })
+        })
+        .collect()
+}
+
+fn count_entries(count: usize) -> String {
+    format!("{} {}", count, if count == 1 { "entry" } else { "entries" })
+}
+
+/// Text report of `consolidate --check`
+pub fn render_check(entries: &[StaleEntry], total: usize) -> String {
+    let mut out = String::new();
+    for entry in entries {
+        let problem = match entry.kind {
+            ChangeKind::Added => "missing from the consolidated metadata",
+            ChangeKind::Removed => "no longer in the store",
+            ChangeKind::Changed => "differs from the store",
+        };
+        let _ = writeln!(out, "{}: {}", entry.key, problem);
+    }
+    if entries.is_empty() {
+        let _ = writeln!(
+            out,
+            "Consolidated metadata is up to date ({})",
+            count_entries(total)
+        );
+    } else {
+        let _ = writeln!(
+            out,
+            "\nConsolidated metadata is stale: {} out of date",
+            count_entries(entries.len())
+        );
+    }
+    out
+}
+
+fn write_json(writer: &mut impl Write, document: &Value) -> Result<()> {
+    serde_json::to_writer_pretty(&mut *writer, document)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+/// Write consolidated metadata holding `documents` to a directory store: `.zmetadata` for v2, or
+/// the `consolidated_metadata` block of the root `zarr.json` for v3. Returns the written file.
+pub fn write_consolidated(
+    root: &Path,
+    zarr_format: u8,
+    documents: BTreeMap<String, Value>,
+) -> Result<PathBuf> {
+    let metadata: serde_json::Map<String, Value> = documents.into_iter().collect();
+    if zarr_format == 3 {
+        let path = root.join("zarr.json");
+        let data =
+            std::fs::read(&path).with_context(|| format!("Failed to read '{}'", path.display()))?;
+        let mut document: Value = serde_json::from_slice(&data)
+            .with_context(|| format!("Failed to parse '{}'", path.display()))?;
+        let object = document
+            .as_object_mut()
+            .ok_or_else(|| anyhow::anyhow!("'{}' is not a JSON object", path.display()))?;
+        object.insert(
+            "consolidated_metadata".to_string(),
+            json!({"kind": "inline", "must_understand": false, "metadata": metadata}),
+        );
+        write_atomically(&path, |writer| write_json(writer, &document))?;
+        Ok(path)
+    } else {
+        let path = root.join(".zmetadata");
+        let document = json!({"metadata": metadata, "zarr_consolidated_format": 1});
+        write_atomically(&path, |writer| write_json(writer, &document))?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stale_entries() {
+        let stored: BTreeMap<String, Value> = [
+            (".zgroup", json!({"zarr_format": 2})),
+            ("tas/.zarray", json!({"shape": [10]})),
+            ("old/.zarray", json!({"shape": [1]})),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+        let mut scanned = stored.clone();
+        scanned.remove("old/.zarray");
+        scanned.insert("tas/.zarray".to_string(), json!({"shape": [12]}));
+        scanned.insert("pr/.zarray".to_string(), json!({"shape": [12]}));
+
+        let entries = stale_entries(&stored, &scanned);
+        assert_eq!(
+            render_check(&entries, scanned.len()),
+            "old/.zarray: no longer in the store\n\
+             pr/.zarray: missing from the consolidated metadata\n\
+             tas/.zarray: differs from the store\n\
+             \nConsolidated metadata is stale: 3 entries out of date\n"
+        );
+        assert!(stale_entries(&scanned, &scanned).is_empty());
+    }
+}
@@ -0,0 +1,529 @@
+//! Comparison of two stores for the `diff` subcommand
+
+use crate::cdl::{CdlType, HIDDEN_ATTRIBUTES, sorted_json};
+use crate::format::format_significant;
+use crate::metadata::{AttributeValue, Variable, ZarrMetadata};
+use crate::store::ZarrStore;
+use anyhow::{Context, Result};
+use serde_json::{Value, json};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+use std::ops::Range;
+
+/// Whether something was added, removed or changed in the second store
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl ChangeKind {
+    fn name(self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Changed => "changed",
+        }
+    }
+
+    fn marker(self) -> char {
+        match self {
+            ChangeKind::Added => '+',
+            ChangeKind::Removed => '-',
+            ChangeKind::Changed => '~',
+        }
+    }
+}
+
+/// One difference between the stores
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    pub kind: ChangeKind,
+    /// Node path (`/` for the root group), or the dimension name for dimensions
+    pub path: String,
+    /// What differs: `variable`, `group`, `dimension`, `dtype`, `attribute 'units'`, `data`, ...
+    pub item: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+impl Difference {
+    fn changed(path: &str, item: &str, old: String, new: String) -> Self {
+        Self {
+            kind: ChangeKind::Changed,
+            path: path.to_string(),
+            item: item.to_string(),
+            old: Some(old),
+            new: Some(new),
+        }
+    }
+}
+
+/// Tolerance of the data comparison: values `a` and `b` are equal if
+/// `|a - b| <= atol + rtol * |b|` (as in numpy's `isclose`), or if both are NaN
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Tolerance {
+    pub rtol: f64,
+    pub atol: f64,
+}
+
+impl Tolerance {
+    fn is_close(&self, a: f64, b: f64) -> bool {
+        a == b || (a.is_nan() && b.is_nan()) || (a - b).abs() <= self.atol + self.rtol * b.abs()
+    }
+}
+
+fn node_label(path: &str) -> &str {
+    if path.is_empty() { "/" } else { path }
+}
+
+fn attribute_text(value: &AttributeValue) -> String {
+    sorted_json(value).to_string()
+}
+
+fn list_text(values: &[u64]) -> String {
+    format!("{:?}", values)
+}
+
+/// Attribute additions, removals and value changes of one node
+fn diff_attributes(
+    path: &str,
+    old: &HashMap<String, AttributeValue>,
+    new: &HashMap<String, AttributeValue>,
+    differences: &mut Vec<Difference>,
+) {
+    let names: BTreeSet<&String> = old
+        .keys()
+        .chain(new.keys())
+        .filter(|name| !HIDDEN_ATTRIBUTES.contains(&name.as_str()))
+        .collect();
+    for name in names {
+        let (old_value, new_value) = (old.get(name), new.get(name));
+        let kind = match (old_value, new_value) {
+            (None, Some(_)) => ChangeKind::Added,
+            (Some(_), None) => ChangeKind::Removed,
+            (Some(a), Some(b)) if sorted_json(a) != sorted_json(b) => ChangeKind::Changed,
+            _ => continue,
+        };
+        differences.push(Difference {
+            kind,
+            path: node_label(path).to_string(),
+            item: format!("attribute '{}'", name),
+            old: old_value.map(attribute_text),
+            new: new_value.map(attribute_text),
+        });
+    }
+}
+
+/// Differences between the definitions of a variable present in both stores
+fn diff_variable(path: &str, old: &Variable, new: &Variable, differences: &mut Vec<Difference>) {
+    let path = node_label(path);
+    if old.dtype != new.dtype {
+        differences.push(Difference::changed(
+            path,
+            "dtype",
+            old.dtype.clone(),
+            new.dtype.clone(),
+        ));
+    }
+    if old.shape != new.shape {
+        differences.push(Difference::changed(
+            path,
+            "shape",
+            list_text(&old.shape),
+            list_text(&new.shape),
+        ));
+    }
+    let dimension_names = |var: &Variable| {
+        let names: Vec<&str> = var.dimensions.iter().map(|d| d.name.as_str()).collect();
+        format!("({})", names.join(", "))
+    };
+    if dimension_names(old) != dimension_names(new) {
+        differences.push(Difference::changed(
+            path,
+            "dimensions",
+            dimension_names(old),
+            dimension_names(new),
+        ));
+    }
+    if old.chunks != new.chunks {
+        differences.push(Difference::changed(
+            path,
+            "chunks",
+            list_text(&old.chunks),
+            list_text(&new.chunks),
+        ));
+    }
+    let codecs = |var: &Variable| match var.codecs.to_string() {
+        text if text.is_empty() => "none".to_string(),
+        text => text,
+    };
+    if codecs(old) != codecs(new) {
+        differences.push(Difference::changed(
+            path,
+            "codecs",
+            codecs(old),
+            codecs(new),
+        ));
+    }
+    let fill_value = |var: &Variable| {
+        var.fill_value
+            .as_ref()
+            .map_or_else(|| "null".to_string(), attribute_text)
+    };
+    if fill_value(old) != fill_value(new) {
+        differences.push(Difference::changed(
+            path,
+            "fill_value",
+            fill_value(old),
+            fill_value(new),
+        ));
+    }
+    diff_attributes(path, &old.attributes, &new.attributes, differences);
+}
+
+/// Differences between the metadata of two stores: variables, groups, dimensions, definitions and
+/// attributes, sorted by path
+pub fn diff_metadata(old: &ZarrMetadata, new: &ZarrMetadata) -> Vec<Difference> {
+    let mut differences = Vec::new();
+
+    diff_attributes(
+        "",
+        &old.global_attributes,
+        &new.global_attributes,
+        &mut differences,
+    );
+
+    let group_paths: BTreeSet<&String> = old
+        .groups
+        .keys()
+        .chain(new.groups.keys())
+        .filter(|path| !path.is_empty())
+        .collect();
+    for path in group_paths {
+        match (old.groups.get(path), new.groups.get(path)) {
+            (Some(a), Some(b)) => {
+                diff_attributes(path, &a.attributes, &b.attributes, &mut differences)
+            }
+            (a, _) => differences.push(Difference {
+                kind: if a.is_some() {
+                    ChangeKind::Removed
+                } else {
+                    ChangeKind::Added
+                },
+                path: path.clone(),
+                item: "group".to_string(),
+                old: None,
+                new: None,
+            }),
+        }
+    }
+
+    let variable_paths: BTreeSet<&String> =
+        old.variables.keys().chain(new.variables.keys()).collect();
+    for path in variable_paths {
+        match (old.variables.get(path), new.variables.get(path)) {
+            (Some(a), Some(b)) => diff_variable(path, a, b, &mut differences),
+            (a, b) => differences.push(Difference {
+                kind: if a.is_some() {
+                    ChangeKind::Removed
+                } else {
+                    ChangeKind::Added
+                },
+                path: node_label(path).to_string(),
+                item: "variable".to_string(),
+                old: a.map(|v| format!("{} {}", v.dtype, list_text(&v.shape))),
+                new: b.map(|v| format!("{} {}", v.dtype, list_text(&v.shape))),
+            }),
+        }
+    }
+
+    let dimension_names: BTreeSet<&String> =
+        old.dimensions.keys().chain(new.dimensions.keys()).collect();
+    for name in dimension_names {
+        let (a, b) = (
+            old.dimensions.get(name).map(|d| d.max_length),
+            new.dimensions.get(name).map(|d| d.max_length),
+        );
+        let kind = match (a, b) {
+            (None, Some(_)) => ChangeKind::Added,
+            (Some(_), None) => ChangeKind::Removed,
+            (Some(a), Some(b)) if a != b => ChangeKind::Changed,
+            _ => continue,
+        };
+        differences.push(Difference {
+            kind,
+            path: name.clone(),
+            item: "dimension".to_string(),
+            old: a.map(|n| n.to_string()),
+            new: b.map(|n| n.to_string()),
+        });
+    }
+
+    sort_differences(&mut differences);
+    differences
+}
+
+/// Sort differences by path, then by item
+pub fn sort_differences(differences: &mut [Difference]) {
+    differences.sort_by(|a, b| (&a.path, &a.item).cmp(&(&b.path, &b.item)));
+}
+
+/// Index ranges of every chunk of a variable's chunk grid, in C order
+fn chunk_regions(var: &Variable) -> Vec<Vec<Range<u64>>> {
+    if var.shape.contains(&0) {
+        return Vec::new();
+    }
+    let chunks: Vec<u64> = var
+        .shape
+        .iter()
+        .enumerate()
+        .map(|(i, &size)| {
+            var.chunks
+                .get(i)
+                .copied()
+                .filter(|&c| c > 0)
+                .unwrap_or(size)
+        })
+        .collect();
+    let grid: Vec<u64> = var
+        .shape
+        .iter()
+        .zip(&chunks)
+        .map(|(size, chunk)| size.div_ceil(*chunk))
+        .collect();
+
+    let total: u64 = grid.iter().product();
+    let mut regions = Vec::with_capacity(total as usize);
+    let mut indices = vec![0u64; grid.len()];
+    for _ in 0..total {
+        regions.push(
+            indices
+                .iter()
+                .zip(&chunks)
+                .zip(&var.shape)
+                .map(|((&i, &chunk), &size)| i * chunk..((i + 1) * chunk).min(size))
+                .collect(),
+        );
+        for d in (0..indices.len()).rev() {
+            indices[d] += 1;
+            if indices[d] < grid[d] {
+                break;
+            }
+            indices[d] = 0;
+        }
+    }
+    regions
+}
+
+/// Compare the values of a variable present in both stores, one chunk (of the first store's
+/// chunk grid) at a time. Returns `None` if the values match within the tolerance.
+pub fn diff_data(
+    path: &str,
+    old_store: &ZarrStore,
+    old: &Variable,
+    new_store: &ZarrStore,
+    new: &Variable,
+    tolerance: Tolerance,
+) -> Result<Option<Difference>> {
+    let numeric =
+        |var: &Variable| CdlType::from_dtype(&var.dtype).is_some_and(|t| t != CdlType::String);
+    if old.shape != new.shape || !numeric(old) || !numeric(new) {
+        // Shape and data type changes are reported with the metadata
+        return Ok(None);
+    }
+
+    let mut differing = 0u64;
+    let mut first: Option<Vec<u64>> = None;
+    let mut max_difference = 0f64;
+    for region in chunk_regions(old) {
+        let read = |store: &ZarrStore, var: &Variable| {
+            store
+                .read_array_subset_f64(var, &region)
+                .with_context(|| format!("Failed to read '{}'", node_label(path)))
+        };
+        let (a, b) = (read(old_store, old)?, read(new_store, new)?);
+        for (offset, (&x, &y)) in a.iter().zip(&b).enumerate() {
+            if tolerance.is_close(x, y) {
+                continue;
+            }
+            differing += 1;
+            max_difference = max_difference.max((x - y).abs());
+            if first.is_none() {
+                // Index of the element within the array from its C-order offset in the region
+                let mut rest = offset as u64;
+                let mut index = vec![0u64; region.len()];
+                for d in (0..region.len()).rev() {
+                    let len = region[d].end - region[d].start;
+                    index[d] = region[d].start + rest % len;
+                    rest /= len;
+                }
+                first = Some(index);
+            }
+        }
+    }
+
+    Ok(first.map(|index| Difference {
+        kind: ChangeKind::Changed,
+        path: node_label(path).to_string(),
+        item: "data".to_string(),
+        old: None,
+        new: Some(format!(
+            "{} value(s) differ, max abs difference {}, first at {:?}",
+            differing,
+            format_significant(max_difference, 6),
+            index
+        )),
+    }))
+}
+
+/// One line per difference, `+` for additions, `-` for removals, `~` for changes
+pub fn render_lines(differences: &[Difference]) -> String {
+    let mut out = String::new();
+    for d in differences {
+        let subject = match d.item.as_str() {
+            "variable" | "group" | "dimension" => format!("{} {}", d.item, d.path),
+            item => format!("{}: {}", d.path, item),
+        };
+        let _ = match (d.kind, &d.old, &d.new) {
+            (ChangeKind::Changed, Some(old), Some(new)) => {
+                writeln!(out, "~ {}: {} -> {}", subject, old, new)
+            }
+            (ChangeKind::Changed, None, Some(summary)) => {
+                writeln!(out, "~ {}: {}", subject, summary)
+            }
+            (_, _, Some(value)) | (_, Some(value), None) => {
+                writeln!(out, "{} {} = {}", d.kind.marker(), subject, value)
+            }
+            _ => writeln!(out, "{} {}", d.kind.marker(), subject),
+        };
+    }
+    out
+}
+
+/// Text report: the differences followed by their count
+pub fn render_text(differences: &[Difference]) -> String {
+    let mut out = render_lines(differences);
+    if differences.is_empty() {
+        out.push_str("Stores are identical\n");
+    } else {
+        let _ = writeln!(out, "\n{} difference(s)", differences.len());
+    }
+    out
+}
+
+pub fn to_json(differences: &[Difference]) -> Value {
+    Value::Array(
+        differences
+            .iter()
+            .map(|d| {
+                json!({
+                    "kind": d.kind.name(),
+                    "path": d.path,
+                    "item": d.item,
+                    "old": d.old,
+                    "new": d.new,
+                })
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::CodecPipeline;
+
+    fn variable(path: &str, dtype: &str, shape: &[u64], attributes: &[(&str, &str)]) -> Variable {
+        let mut attributes: HashMap<String, AttributeValue> = attributes
+            .iter()
+            .map(|(k, v)| (k.to_string(), AttributeValue::String(v.to_string())))
+            .collect();
+        attributes.insert(
+            "_ARRAY_DIMENSIONS".to_string(),
+            AttributeValue::Array(vec![AttributeValue::String("x".to_string())]),
+        );
+        Variable {
+            name: path.to_string(),
+            path: path.to_string(),
+            dtype: dtype.to_string(),
+            shape: shape.to_vec(),
+            chunks: shape.to_vec(),
+            codecs: CodecPipeline::default(),
+            fill_value: None,
+            order: "C".to_string(),
+            dimension_separator: ".".to_string(),
+            attributes,
+            dimensions: vec![],
+        }
+    }
+
+    fn metadata(variables: Vec<Variable>) -> ZarrMetadata {
+        let mut metadata = ZarrMetadata::new();
+        for var in variables {
+            metadata.variables.insert(var.path.clone(), var);
+        }
+        metadata.infer_dimensions();
+        metadata
+    }
+
+    #[test]
+    fn test_diff_metadata() {
+        let old = metadata(vec![
+            variable("tas", "<f4", &[4], &[("units", "K"), ("comment", "old")]),
+            variable("pr", "<f4", &[4], &[]),
+        ]);
+        let mut new = metadata(vec![
+            variable("tas", "<f8", &[5], &[("units", "degC"), ("long_name", "T")]),
+            variable("huss", "<f4", &[5], &[]),
+        ]);
+        new.global_attributes.insert(
+            "title".to_string(),
+            AttributeValue::String("run 2".to_string()),
+        );
+
+        let differences = diff_metadata(&old, &new);
+        assert_eq!(
+            render_text(&differences),
+            "+ /: attribute 'title' = \"run 2\"\n\
+             + variable huss = <f4 [5]\n\
+             - variable pr = <f4 [4]\n\
+             - tas: attribute 'comment' = \"old\"\n\
+             + tas: attribute 'long_name' = \"T\"\n\
+             ~ tas: attribute 'units': \"K\" -> \"degC\"\n\
+             ~ tas: chunks: [4] -> [5]\n\
+             ~ tas: dtype: <f4 -> <f8\n\
+             ~ tas: shape: [4] -> [5]\n\
+             ~ dimension x: 4 -> 5\n\
+             \n10 difference(s)\n"
+        );
+        assert_eq!(to_json(&differences)[9]["kind"], "changed");
+        assert!(diff_metadata(&old, &old).is_empty());
+        assert_eq!(render_text(&[]), "Stores are identical\n");
+    }
+
+    #[test]
+    fn test_tolerance_and_chunk_regions() {
+        let exact = Tolerance::default();
+        assert!(exact.is_close(1.0, 1.0) && exact.is_close(f64::NAN, f64::NAN));
+        assert!(!exact.is_close(1.0, 1.0 + 1e-9) && !exact.is_close(f64::NAN, 1.0));
+        let loose = Tolerance {
+            rtol: 1e-3,
+            atol: 0.0,
+        };
+        assert!(loose.is_close(1000.5, 1000.0) && !loose.is_close(1002.0, 1000.0));
+
+        let mut var = variable("tas", "<f4", &[3, 5], &[]);
+        var.chunks = vec![2, 4];
+        assert_eq!(
+            chunk_regions(&var),
+            vec![
+                vec![0..2, 0..4],
+                vec![0..2, 4..5],
+                vec![2..3, 0..4],
+                vec![2..3, 4..5]
+            ]
+        );
+    }
+}
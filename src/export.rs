@@ -0,0 +1,548 @@
+//! Export of a store, or a subset of it, to other file formats
+
+use crate::cdl::{CdlType, HIDDEN_ATTRIBUTES, TypedAttribute, variable_label};
+use crate::cf;
+use crate::fsutil::write_atomically;
+use crate::hyperslab::{self, Hyperslab, IndexSelection};
+use crate::metadata::{AttributeValue, Variable, ZarrMetadata};
+use crate::netcdf::{self, NcAttribute, NcDimension, NcFile, NcVariable};
+use crate::store::ZarrStore;
+use anyhow::{Result, bail};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+
+/// Apply `--isel` to the variables of `paths`, rejecting dimensions none of them use
+pub fn select_hyperslabs(
+    metadata: &ZarrMetadata,
+    paths: &[String],
+    isel: &HashMap<String, IndexSelection>,
+) -> Result<Vec<Hyperslab>> {
+    for dim in isel.keys() {
+        let used = paths.iter().any(|path| {
+            metadata.variables[path]
+                .dimensions
+                .iter()
+                .any(|d| &d.name == dim)
+        });
+        if !used {
+            bail!(
+                "Dimension '{}' is not used by any of the selected variables",
+                dim
+            );
+        }
+    }
+
+    paths
+        .iter()
+        .map(|path| {
+            let var = &metadata.variables[path];
+            let mut selections = HashMap::new();
+            for (dim, &size) in var.dimensions.iter().zip(&var.shape) {
+                if let Some(selection) = isel.get(&dim.name) {
+                    selections.insert(dim.name.clone(), selection.resolve(&dim.name, size)?);
+                }
+            }
+            Ok(hyperslab::build_hyperslab(var, &selections))
+        })
+        .collect()
+}
+
+/// Write the variables of `paths` (with the `isel` selection) to a netCDF file at `output`.
+/// Variables and attributes the classic data model cannot hold are skipped with a warning.
+/// Returns the number of variables written.
+pub fn export_netcdf(
+    metadata: &ZarrMetadata,
+    store: &ZarrStore,
+    paths: &[String],
+    isel: &HashMap<String, IndexSelection>,
+    output: &Path,
+) -> Result<usize> {
+    let slabs = select_hyperslabs(metadata, paths, isel)?;
+
+    let mut file = NcFile {
+        attributes: nc_attributes("", &metadata.global_attributes, None),
+        ..NcFile::default()
+    };
+    let mut exported: Vec<(&Variable, Hyperslab)> = Vec::new();
+    for (path, slab) in paths.iter().zip(slabs) {
+        let var = &metadata.variables[path];
+        let nc_type = match CdlType::from_dtype(&var.dtype) {
+            Some(CdlType::String) | None => {
+                eprintln!(
+                    "Warning: skipping variable '{}': data type '{}' is not supported in netCDF classic files",
+                    variable_label(var),
+                    var.dtype
+                );
+                continue;
+            }
+            Some(nc_type) => nc_type,
+        };
+
+        let mut dimensions = Vec::new();
+        for (dim, length) in var.dimensions.iter().zip(slab.shape()) {
+            let id = match file.dimensions.iter().position(|d| d.name == dim.name) {
+                Some(id) if file.dimensions[id].length != length => bail!(
+                    "Dimension '{}' has length {} in variable '{}' but {} in another variable",
+                    dim.name,
+                    length,
+                    variable_label(var),
+                    file.dimensions[id].length
+                ),
+                Some(id) => id,
+                None => {
+                    file.dimensions.push(NcDimension {
+                        name: dim.name.clone(),
+                        length,
+                    });
+                    file.dimensions.len() - 1
+                }
+            };
+            dimensions.push(id);
+        }
+
+        let mut attributes = var.attributes.clone();
+        if let Some(fill_value) = &var.fill_value
+            && !matches!(fill_value, AttributeValue::Null)
+        {
+            attributes
+                .entry("_FillValue".to_string())
+                .or_insert_with(|| fill_value.clone());
+        }
+
+        file.variables.push(NcVariable {
+            // netCDF classic files have no groups
+            name: variable_label(var).replace('/', "_"),
+            dimensions,
+            nc_type,
+            attributes: nc_attributes(variable_label(var), &attributes, Some(nc_type)),
+        });
+        exported.push((var, slab));
+    }
+
+    let skipped_groups = metadata
+        .groups
+        .values()
+        .filter(|g| !g.path.is_empty() && !g.attributes.is_empty())
+        .count();
+    if skipped_groups > 0 {
+        eprintln!(
+            "Warning: attributes of {} group(s) are not exported (netCDF classic files have no groups)",
+            skipped_groups
+        );
+    }
+
+    let (header, _) = file.header()?;
+    write_atomically(output, |writer| {
+        writer.write_all(&header)?;
+        for ((var, slab), nc_var) in exported.iter().zip(&file.variables) {
+            // Record variables have no data, since the record dimension is always empty
+            if slab.shape().first() == Some(&0) {
+                continue;
+            }
+            write_values(store, var, slab, nc_var.nc_type, Endianness::Big, writer)?;
+            netcdf::write_padding(writer, file.data_size(nc_var))?;
+        }
+        Ok(())
+    })?;
+    Ok(exported.len())
+}
+
+/// File formats holding the values of a single variable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArrayFormat {
+    /// One row per element, with coordinate columns
+    Csv,
+    /// NumPy `.npy` file with the native data type and shape
+    Npy,
+    /// Little-endian values of the native data type in C order, without a header
+    Raw,
+}
+
+/// Write the `isel` selection of the variable at `path` to `output`. Returns a description of
+/// the data written (data type and shape), which raw files need to be read back.
+pub fn export_array(
+    metadata: &ZarrMetadata,
+    store: &ZarrStore,
+    path: &str,
+    isel: &HashMap<String, IndexSelection>,
+    format: ArrayFormat,
+    output: &Path,
+) -> Result<String> {
+    let slab = select_hyperslabs(metadata, &[path.to_string()], isel)?.remove(0);
+    let var = &metadata.variables[path];
+    let nc_type = CdlType::from_dtype(&var.dtype)
+        .filter(|t| *t != CdlType::String)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Variable '{}' has data type '{}', which cannot be exported as numbers",
+                variable_label(var),
+                var.dtype
+            )
+        })?;
+    let shape = slab.shape();
+    let descr = npy_descr(&var.dtype, nc_type);
+
+    match format {
+        ArrayFormat::Csv => {
+            let columns = coordinate_columns(metadata, store, var, &slab)?;
+            write_atomically(output, |writer| {
+                write_csv(store, var, &slab, &columns, writer)
+            })?;
+        }
+        ArrayFormat::Npy | ArrayFormat::Raw => {
+            write_atomically(output, |writer| {
+                if format == ArrayFormat::Npy {
+                    writer.write_all(&npy_header(descr, &shape))?;
+                }
+                write_values(store, var, &slab, nc_type, Endianness::Little, writer)
+            })?;
+        }
+    }
+    Ok(format!("dtype {}, shape {}", descr, shape_tuple(&shape)))
+}
+
+/// NumPy type string of a variable's data, always little-endian
+fn npy_descr(dtype: &str, nc_type: CdlType) -> &'static str {
+    if matches!(
+        dtype.trim_start_matches(['<', '>', '|', '=']),
+        "b1" | "?" | "bool"
+    ) {
+        return "|b1";
+    }
+    match nc_type {
+        CdlType::Byte => "|i1",
+        CdlType::UByte => "|u1",
+        CdlType::Short => "<i2",
+        CdlType::UShort => "<u2",
+        CdlType::Int => "<i4",
+        CdlType::UInt => "<u4",
+        CdlType::Int64 => "<i8",
+        CdlType::UInt64 => "<u8",
+        CdlType::Float => "<f4",
+        CdlType::Double | CdlType::String => "<f8",
+    }
+}
+
+/// Python tuple notation of a shape: `()`, `(3,)` or `(2, 3)`
+fn shape_tuple(shape: &[u64]) -> String {
+    match shape {
+        [n] => format!("({},)", n),
+        _ => {
+            let sizes: Vec<String> = shape.iter().map(u64::to_string).collect();
+            format!("({})", sizes.join(", "))
+        }
+    }
+}
+
+/// Version 1.0 `.npy` header, padded so that the data starts at a multiple of 64 bytes
+fn npy_header(descr: &str, shape: &[u64]) -> Vec<u8> {
+    let mut dict = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+        descr,
+        shape_tuple(shape)
+    );
+    let unpadded = 10 + dict.len() + 1;
+    dict.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+    dict.push('\n');
+
+    let mut header = b"\x93NUMPY\x01\x00".to_vec();
+    header.extend_from_slice(&(dict.len() as u16).to_le_bytes());
+    header.extend_from_slice(dict.as_bytes());
+    header
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Endianness {
+    /// netCDF files
+    Big,
+    /// `.npy` and raw files
+    Little,
+}
+
+/// Stream the values of a selection to `writer` as binary values of `nc_type`. 64-bit integers
+/// are read as such rather than through `f64`, which cannot hold all of them exactly.
+fn write_values(
+    store: &ZarrStore,
+    var: &Variable,
+    slab: &Hyperslab,
+    nc_type: CdlType,
+    endianness: Endianness,
+    writer: &mut impl Write,
+) -> Result<()> {
+    let mut bytes = Vec::new();
+    let mut write_bits = |values: &mut dyn Iterator<Item = u64>| {
+        bytes.clear();
+        for bits in values {
+            bytes.extend_from_slice(&match endianness {
+                Endianness::Big => bits.to_be_bytes(),
+                Endianness::Little => bits.to_le_bytes(),
+            });
+        }
+        Ok(writer.write_all(&bytes)?)
+    };
+    match nc_type {
+        // Two's complement keeps the bits of negative values
+        CdlType::Int64 => store.read_hyperslab_batches_as(var, slab, |data: &[i64]| {
+            write_bits(&mut data.iter().map(|&v| v as u64))
+        }),
+        CdlType::UInt64 => store.read_hyperslab_batches_as(var, slab, |data: &[u64]| {
+            write_bits(&mut data.iter().copied())
+        }),
+        _ => store.read_hyperslab_batches(var, slab, |data| {
+            bytes.clear();
+            match endianness {
+                Endianness::Big => netcdf::encode_values(nc_type, data, &mut bytes),
+                Endianness::Little => encode_little_endian(nc_type, data, &mut bytes),
+            }
+            Ok(writer.write_all(&bytes)?)
+        }),
+    }
+}
+
+/// Append values converted to `nc_type` as little-endian bytes (NaN becomes 0 for integer types)
+fn encode_little_endian(nc_type: CdlType, values: &[f64], out: &mut Vec<u8>) {
+    for &v in values {
+        match nc_type {
+            CdlType::Byte => out.extend_from_slice(&(v as i8).to_le_bytes()),
+            CdlType::UByte => out.extend_from_slice(&(v as u8).to_le_bytes()),
+            CdlType::Short => out.extend_from_slice(&(v as i16).to_le_bytes()),
+            CdlType::UShort => out.extend_from_slice(&(v as u16).to_le_bytes()),
+            CdlType::Int => out.extend_from_slice(&(v as i32).to_le_bytes()),
+            CdlType::UInt => out.extend_from_slice(&(v as u32).to_le_bytes()),
+            CdlType::Int64 => out.extend_from_slice(&(v as i64).to_le_bytes()),
+            CdlType::UInt64 => out.extend_from_slice(&(v as u64).to_le_bytes()),
+            CdlType::Float => out.extend_from_slice(&(v as f32).to_le_bytes()),
+            CdlType::Double | CdlType::String => out.extend_from_slice(&v.to_le_bytes()),
+        }
+    }
+}
+
+/// A CSV column holding the coordinates of each element
+struct CoordinateColumn {
+    name: String,
+    /// Positions of the coordinate's dimensions among the variable's dimensions
+    dims: Vec<usize>,
+    /// Coordinate values over the selection, or `None` for plain indices of a dimension
+    values: Option<Vec<f64>>,
+}
+
+/// Columns for each dimension (the values of its CF coordinate variable, or the selected indices
+/// when there is none) and for the auxiliary coordinates listed in the `coordinates` attribute
+fn coordinate_columns(
+    metadata: &ZarrMetadata,
+    store: &ZarrStore,
+    var: &Variable,
+    slab: &Hyperslab,
+) -> Result<Vec<CoordinateColumn>> {
+    let dim_names: Vec<&str> = var.dimensions.iter().map(|d| d.name.as_str()).collect();
+    let mut names: Vec<&str> = dim_names.clone();
+    if let Some(AttributeValue::String(coordinates)) = var.attributes.get("coordinates") {
+        names.extend(
+            coordinates
+                .split_whitespace()
+                .filter(|name| !dim_names.contains(name)),
+        );
+    }
+
+    let mut columns = Vec::new();
+    for name in names {
+        let coordinate = cf::resolve_related_var(metadata, &var.path, name).map(|(_, c)| c);
+        if coordinate.is_some_and(|c| c.path == var.path) {
+            // A coordinate variable is exported: its own column suffices
+            continue;
+        }
+        // Positions of the coordinate's dimensions, if it spans a subset of the variable's
+        let dims: Option<Vec<usize>> = coordinate.and_then(|c| {
+            c.dimensions
+                .iter()
+                .map(|d| dim_names.iter().position(|n| *n == d.name))
+                .collect()
+        });
+
+        match (coordinate, dims) {
+            (Some(coordinate), Some(dims))
+                if !dims.is_empty()
+                    && dims
+                        .iter()
+                        .zip(&coordinate.shape)
+                        .all(|(&d, &size)| var.shape[d] == size) =>
+            {
+                let coordinate_slab = Hyperslab {
+                    dims: dims.iter().map(|&i| slab.dims[i]).collect(),
+                };
+                let mut values = Vec::new();
+                store.read_hyperslab_batches(coordinate, &coordinate_slab, |data| {
+                    values.extend_from_slice(data);
+                    Ok(())
+                })?;
+                cf::mask_missing_values(coordinate, &mut values);
+                columns.push(CoordinateColumn {
+                    name: name.to_string(),
+                    dims,
+                    values: Some(values),
+                });
+            }
+            _ => {
+                if let Some(i) = dim_names.iter().position(|n| *n == name) {
+                    columns.push(CoordinateColumn {
+                        name: name.to_string(),
+                        dims: vec![i],
+                        values: None,
+                    });
+                }
+            }
+        }
+    }
+    Ok(columns)
+}
+
+/// Write one CSV row per selected element: its coordinates, then its value. Fill and missing
+/// values are left empty.
+fn write_csv(
+    store: &ZarrStore,
+    var: &Variable,
+    slab: &Hyperslab,
+    columns: &[CoordinateColumn],
+    writer: &mut impl Write,
+) -> Result<()> {
+    let header: Vec<String> = columns
+        .iter()
+        .map(|c| csv_field(&c.name))
+        .chain([csv_field(variable_label(var))])
+        .collect();
+    writeln!(writer, "{}", header.join(","))?;
+
+    let shape = slab.shape();
+    let mut index = vec![0u64; shape.len()];
+    let mut line = String::new();
+    // CSV values go through f64, which rounds 64-bit integers beyond 2^53
+    let wide = matches!(
+        CdlType::from_dtype(&var.dtype),
+        Some(CdlType::Int64 | CdlType::UInt64)
+    );
+    let mut rounded = false;
+    store.read_hyperslab_batches(var, slab, |data| {
+        let mut data = data.to_vec();
+        cf::mask_missing_values(var, &mut data);
+        for value in data {
+            rounded |= wide && value.abs() > 9_007_199_254_740_992.0;
+            line.clear();
+            for column in columns {
+                match &column.values {
+                    Some(values) => {
+                        // C-order offset of the element within the coordinate's selection
+                        let offset = column
+                            .dims
+                            .iter()
+                            .fold(0, |acc, &d| acc * shape[d] + index[d]);
+                        line.push_str(&csv_number(values[offset as usize]));
+                    }
+                    None => {
+                        let d = column.dims[0];
+                        line.push_str(
+                            &(slab.dims[d].start + index[d] * slab.dims[d].step).to_string(),
+                        );
+                    }
+                }
+                line.push(',');
+            }
+            line.push_str(&csv_number(value));
+            writeln!(writer, "{}", line)?;
+
+            // Advance to the next element in C order
+            for d in (0..index.len()).rev() {
+                index[d] += 1;
+                if index[d] < shape[d] {
+                    break;
+                }
+                index[d] = 0;
+            }
+        }
+        Ok(())
+    })?;
+    if rounded {
+        eprintln!(
+            "Warning: values of '{}' beyond ±2^53 are rounded in CSV output; export to .npy or raw for exact values",
+            variable_label(var)
+        );
+    }
+    Ok(())
+}
+
+fn csv_number(value: f64) -> String {
+    if value.is_nan() {
+        String::new()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Typed netCDF attributes of a Zarr attribute map, sorted by name. Values the variable type
+/// cannot hold are left out with a warning naming them as `owner:name` (`:name` for globals).
+fn nc_attributes(
+    owner: &str,
+    attributes: &HashMap<String, AttributeValue>,
+    var_type: Option<CdlType>,
+) -> Vec<NcAttribute> {
+    let mut sorted: Vec<_> = attributes
+        .iter()
+        .filter(|(key, _)| !HIDDEN_ATTRIBUTES.contains(&key.as_str()))
+        .collect();
+    sorted.sort_by_key(|(key, _)| key.as_str());
+    sorted
+        .into_iter()
+        .filter_map(
+            |(key, value)| match TypedAttribute::new(key, value, var_type) {
+                Ok(value) => Some(NcAttribute {
+                    name: key.clone(),
+                    value: value?,
+                }),
+                Err(e) => {
+                    eprintln!("Warning: leaving out attribute {}:{}: {}", owner, key, e);
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_npy_header() {
+        assert_eq!(shape_tuple(&[]), "()");
+        assert_eq!(shape_tuple(&[3]), "(3,)");
+        assert_eq!(shape_tuple(&[2, 3]), "(2, 3)");
+
+        let header = npy_header("<i2", &[3]);
+        assert_eq!(header.len() % 64, 0);
+        assert!(header.ends_with(b"\n"));
+        let dict = String::from_utf8_lossy(&header[10..]);
+        assert!(dict.starts_with("{'descr': '<i2', 'fortran_order': False, 'shape': (3,), }"));
+
+        assert_eq!(npy_descr("|b1", CdlType::UByte), "|b1");
+        assert_eq!(npy_descr(">u2", CdlType::UShort), "<u2");
+    }
+
+    #[test]
+    fn test_csv_fields() {
+        assert_eq!(csv_field("tas"), "tas");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_number(f64::NAN), "");
+        assert_eq!(csv_number(3.0), "3");
+        assert_eq!(csv_number(-0.5), "-0.5");
+    }
+}
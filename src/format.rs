@@ -0,0 +1,87 @@
+//! Plain-text formatting of numbers and tables shared by the reports
+
+/// Align the cells of a text table in columns separated by two spaces: the first column (names)
+/// to the left, the others (numbers) to the right
+pub fn format_table(rows: &[Vec<String>]) -> String {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|i| {
+            rows.iter()
+                .filter_map(|row| row.get(i))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut out = String::new();
+    for row in rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(i, (cell, &width))| {
+                if i == 0 {
+                    format!("{:<width$}", cell)
+                } else {
+                    format!("{:>width$}", cell)
+                }
+            })
+            .collect();
+        out.push_str(cells.join("  ").trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// Format `value` with `digits` significant digits like C's `%g` (as used by ncdump -p)
+pub fn format_significant(value: f64, digits: usize) -> String {
+    if !value.is_finite() || value == 0.0 {
+        return format!("{}", value);
+    }
+
+    let digits = digits.max(1);
+    let exponent = value.abs().log10().floor() as i32;
+    let trim = |s: String| {
+        if s.contains('.') {
+            s.trim_end_matches('0').trim_end_matches('.').to_string()
+        } else {
+            s
+        }
+    };
+
+    if exponent < -4 || exponent >= digits as i32 {
+        let formatted = format!("{:.*e}", digits - 1, value);
+        match formatted.split_once('e') {
+            Some((mantissa, exp)) => format!("{}e{}", trim(mantissa.to_string()), exp),
+            None => formatted,
+        }
+    } else {
+        let decimals = (digits as i32 - 1 - exponent).max(0) as usize;
+        trim(format!("{:.*}", decimals, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_significant() {
+        assert_eq!(format_significant(1.23456789, 6), "1.23457");
+        assert_eq!(format_significant(1500.0, 2), "1.5e3");
+        assert_eq!(format_significant(0.00001234, 3), "1.23e-5");
+        assert_eq!(format_significant(-2.5, 6), "-2.5");
+        assert_eq!(format_significant(0.0, 6), "0");
+        assert_eq!(format_significant(f64::NAN, 6), "NaN");
+    }
+
+    #[test]
+    fn test_format_table() {
+        let rows = vec![
+            vec!["name".to_string(), "count".to_string()],
+            vec!["tas".to_string(), "12".to_string()],
+        ];
+        assert_eq!(format_table(&rows), "name  count\ntas      12\n");
+    }
+}
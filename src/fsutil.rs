@@ -0,0 +1,41 @@
+//! File system helpers shared by the subcommands that write files
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+/// Write a file through a temporary file next to it, so that a failed write never leaves a
+/// partial file behind (or clobbers an existing one)
+pub fn write_atomically(
+    output: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> Result<()>,
+) -> Result<()> {
+    let file_name = output
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid output path '{}'", output.display()))?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(".tmp");
+    let temp_path: PathBuf = output.with_file_name(temp_name);
+
+    let result = File::create(&temp_path)
+        .with_context(|| format!("Failed to create '{}'", temp_path.display()))
+        .and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            write(&mut writer)?;
+            writer
+                .into_inner()
+                .map_err(|e| e.into_error())?
+                .sync_all()?;
+            Ok(())
+        })
+        .and_then(|()| {
+            std::fs::rename(&temp_path, output)
+                .with_context(|| format!("Failed to write '{}'", output.display()))
+        });
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
@@ -0,0 +1,576 @@
+//! Hyperslab selections for the `data` subcommand (`--isel` index and `--sel` coordinate ranges)
+
+use crate::metadata::Variable;
+use anyhow::{Context, Result, anyhow, bail};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+
+/// An index selection along one dimension, parsed from `dim=index` or `dim=start:stop[:step]`
+///
+/// Indices follow Python slice semantics: negative values count from the end and slice bounds
+/// are clamped to the dimension length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexSelection {
+    Index(i64),
+    Slice {
+        start: Option<i64>,
+        stop: Option<i64>,
+        step: u64,
+    },
+}
+
+/// A coordinate value selection along one dimension, parsed from `dim=value` (nearest
+/// coordinate) or `dim=min:max` (all coordinates within the inclusive range)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueSelection {
+    Nearest(f64),
+    Range(f64, f64),
+}
+
+/// The selected indices `start, start + step, ...` below `stop` of one dimension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DimRange {
+    pub start: u64,
+    pub stop: u64,
+    pub step: u64,
+}
+
+impl DimRange {
+    pub fn full(size: u64) -> Self {
+        Self {
+            start: 0,
+            stop: size,
+            step: 1,
+        }
+    }
+
+    /// Number of selected indices
+    pub fn count(&self) -> u64 {
+        if self.stop <= self.start {
+            0
+        } else {
+            (self.stop - self.start).div_ceil(self.step)
+        }
+    }
+
+    /// The same selection limited to its first `count` indices
+    pub fn truncated(&self, count: u64) -> Self {
+        if count >= self.count() {
+            return *self;
+        }
+        Self {
+            stop: self.start + count * self.step,
+            ..*self
+        }
+    }
+
+    /// The part of the selection from its `offset`-th index, limited to `count` indices
+    pub fn slice(&self, offset: u64, count: u64) -> Self {
+        let start = (self.start + offset * self.step).min(self.stop.max(self.start));
+        Self { start, ..*self }.truncated(count)
+    }
+
+    /// Contiguous index range covering the selection, as read from the array
+    fn read_range(&self) -> Range<u64> {
+        match self.count() {
+            0 => self.start..self.start,
+            n => self.start..self.start + (n - 1) * self.step + 1,
+        }
+    }
+
+    /// Whether the step skips whole chunks of length `chunk`, so the selected indices are better
+    /// read one at a time than through the range covering them
+    fn is_sparse(&self, chunk: Option<u64>) -> bool {
+        self.step > 1 && chunk.is_some_and(|chunk| self.step >= chunk)
+    }
+}
+
+impl fmt::Display for DimRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.count() == 1 {
+            write!(f, "{}", self.start)
+        } else if self.step == 1 {
+            write!(f, "{}:{}", self.start, self.stop)
+        } else {
+            write!(f, "{}:{}:{}", self.start, self.stop, self.step)
+        }
+    }
+}
+
+/// A selection along every dimension of a variable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hyperslab {
+    pub dims: Vec<DimRange>,
+}
+
+impl Hyperslab {
+    /// Number of selected indices along each dimension
+    pub fn shape(&self) -> Vec<u64> {
+        self.dims.iter().map(DimRange::count).collect()
+    }
+
+    /// Whether the selection covers all of an array of the given shape
+    pub fn is_full(&self, shape: &[u64]) -> bool {
+        self.dims
+            .iter()
+            .zip(shape)
+            .all(|(dim, &size)| *dim == DimRange::full(size))
+    }
+
+    /// Contiguous ranges to read with [`ZarrStore::read_array_subset_f64`](crate::store::ZarrStore::read_array_subset_f64)
+    pub fn read_ranges(&self) -> Vec<Range<u64>> {
+        self.dims.iter().map(DimRange::read_range).collect()
+    }
+
+    /// The selection split into parts read with one contiguous range per dimension. Along
+    /// dimensions whose step is at least their chunk length (`chunks`), each part holds a single
+    /// selected index, so the chunks between selected indices are not read.
+    pub fn split_sparse(&self, chunks: &[u64]) -> Vec<Hyperslab> {
+        let mut parts = vec![Hyperslab { dims: Vec::new() }];
+        for (i, dim) in self.dims.iter().enumerate() {
+            let pieces: Vec<DimRange> = if dim.is_sparse(chunks.get(i).copied()) {
+                (0..dim.count()).map(|k| dim.slice(k, 1)).collect()
+            } else {
+                vec![*dim]
+            };
+            parts = parts
+                .into_iter()
+                .flat_map(|part| {
+                    pieces.iter().map(move |piece| {
+                        let mut dims = part.dims.clone();
+                        dims.push(*piece);
+                        Hyperslab { dims }
+                    })
+                })
+                .collect();
+        }
+        parts
+    }
+
+    /// Number of indices each part of [`Self::split_sparse`] reads along each dimension
+    pub fn read_shape(&self, chunks: &[u64]) -> Vec<u64> {
+        self.dims
+            .iter()
+            .enumerate()
+            .map(|(i, dim)| {
+                let range = dim.read_range();
+                if dim.is_sparse(chunks.get(i).copied()) {
+                    range.end.min(range.start + 1) - range.start
+                } else {
+                    range.end - range.start
+                }
+            })
+            .collect()
+    }
+
+    /// Copy the values of `part`, a part of this selection from [`Self::split_sparse`], to their
+    /// place in the C-order `values` of the whole selection
+    pub fn place<T: Copy>(&self, values: &mut [T], part: &Hyperslab, data: &[T]) {
+        let shape = self.shape();
+        let part_shape = part.shape();
+        let offsets: Vec<u64> = self
+            .dims
+            .iter()
+            .zip(&part.dims)
+            .map(|(dim, piece)| (piece.start - dim.start) / dim.step)
+            .collect();
+        let mut strides = vec![1u64; shape.len()];
+        for i in (0..shape.len().saturating_sub(1)).rev() {
+            strides[i] = strides[i + 1] * shape[i + 1];
+        }
+
+        let mut index = vec![0u64; shape.len()];
+        for &value in data {
+            let offset: u64 = index
+                .iter()
+                .zip(&offsets)
+                .zip(&strides)
+                .map(|((i, offset), stride)| (i + offset) * stride)
+                .sum();
+            values[offset as usize] = value;
+
+            // Advance the multi-index in C order
+            for d in (0..index.len()).rev() {
+                index[d] += 1;
+                if index[d] < part_shape[d] {
+                    break;
+                }
+                index[d] = 0;
+            }
+        }
+    }
+
+    /// Pick the selected (strided) values out of the C-order data read for [`Self::read_ranges`]
+    pub fn subsample<T: Copy>(&self, data: Vec<T>) -> Vec<T> {
+        if self.dims.iter().all(|d| d.step == 1) {
+            return data;
+        }
+
+        let read_shape: Vec<usize> = self
+            .read_ranges()
+            .iter()
+            .map(|r| (r.end - r.start) as usize)
+            .collect();
+        let shape: Vec<usize> = self.shape().iter().map(|&n| n as usize).collect();
+        let steps: Vec<usize> = self.dims.iter().map(|d| d.step as usize).collect();
+
+        let mut read_strides = vec![1usize; read_shape.len()];
+        for i in (0..read_shape.len().saturating_sub(1)).rev() {
+            read_strides[i] = read_strides[i + 1] * read_shape[i + 1];
+        }
+
+        let total: usize = shape.iter().product();
+        let mut values = Vec::with_capacity(total);
+        let mut index = vec![0usize; shape.len()];
+        for _ in 0..total {
+            let offset: usize = index
+                .iter()
+                .zip(&steps)
+                .zip(&read_strides)
+                .map(|((i, step), stride)| i * step * stride)
+                .sum();
+            values.push(data[offset]);
+
+            // Advance the multi-index in C order
+            for d in (0..index.len()).rev() {
+                index[d] += 1;
+                if index[d] < shape[d] {
+                    break;
+                }
+                index[d] = 0;
+            }
+        }
+        values
+    }
+}
+
+fn split_dim_arg<'a>(raw: &'a str, option: &str, expected: &str) -> Result<(&'a str, &'a str)> {
+    let (name, value) = raw
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Invalid {} '{}'. Expected '{}'.", option, raw, expected))?;
+    let (name, value) = (name.trim(), value.trim());
+    if name.is_empty() || value.is_empty() {
+        bail!("Invalid {} '{}'. Expected '{}'.", option, raw, expected);
+    }
+    Ok((name, value))
+}
+
+/// Parse `--isel` values (`dim=index` or `dim=start:stop[:step]`, any part of a slice may be empty)
+pub fn parse_isel(values: &[String]) -> Result<HashMap<String, IndexSelection>> {
+    const EXPECTED: &str = "dim=index or dim=start:stop[:step]";
+    let mut selections = HashMap::new();
+
+    for raw in values {
+        let (name, value) = split_dim_arg(raw, "--isel", EXPECTED)?;
+        let parse_index = |s: &str| -> Result<Option<i64>> {
+            let s = s.trim();
+            if s.is_empty() {
+                return Ok(None);
+            }
+            s.parse()
+                .map(Some)
+                .with_context(|| format!("Invalid index '{}' in --isel '{}'", s, raw))
+        };
+
+        let parts: Vec<&str> = value.split(':').collect();
+        let selection = match parts.as_slice() {
+            [index] => IndexSelection::Index(
+                parse_index(index)?
+                    .ok_or_else(|| anyhow!("Invalid --isel '{}'. Expected '{}'.", raw, EXPECTED))?,
+            ),
+            [start, stop] | [start, stop, ""] => IndexSelection::Slice {
+                start: parse_index(start)?,
+                stop: parse_index(stop)?,
+                step: 1,
+            },
+            [start, stop, step] => {
+                let step: u64 = step
+                    .trim()
+                    .parse()
+                    .with_context(|| format!("Invalid step in --isel '{}'", raw))?;
+                if step == 0 {
+                    bail!("Invalid --isel '{}': the step must be at least 1.", raw);
+                }
+                IndexSelection::Slice {
+                    start: parse_index(start)?,
+                    stop: parse_index(stop)?,
+                    step,
+                }
+            }
+            _ => bail!("Invalid --isel '{}'. Expected '{}'.", raw, EXPECTED),
+        };
+
+        if selections.insert(name.to_string(), selection).is_some() {
+            bail!("Duplicate --isel provided for dimension '{}'.", name);
+        }
+    }
+
+    Ok(selections)
+}
+
+/// Parse `--sel` values (`dim=value` or `dim=min:max`)
+pub fn parse_sel(values: &[String]) -> Result<HashMap<String, ValueSelection>> {
+    const EXPECTED: &str = "dim=value or dim=min:max";
+    let mut selections = HashMap::new();
+
+    for raw in values {
+        let (name, value) = split_dim_arg(raw, "--sel", EXPECTED)?;
+        let parse_value = |s: &str| -> Result<f64> {
+            s.trim()
+                .parse()
+                .with_context(|| format!("Invalid coordinate value '{}' in --sel '{}'", s, raw))
+        };
+
+        let selection = match value.split_once(':') {
+            None => ValueSelection::Nearest(parse_value(value)?),
+            Some((min, max)) => ValueSelection::Range(parse_value(min)?, parse_value(max)?),
+        };
+
+        if selections.insert(name.to_string(), selection).is_some() {
+            bail!("Duplicate --sel provided for dimension '{}'.", name);
+        }
+    }
+
+    Ok(selections)
+}
+
+impl IndexSelection {
+    /// Resolve the selection against a dimension of length `size`
+    pub fn resolve(&self, dim: &str, size: u64) -> Result<DimRange> {
+        let size_i = size as i64;
+        let absolute = |index: i64| if index < 0 { index + size_i } else { index };
+
+        match *self {
+            IndexSelection::Index(index) => {
+                let resolved = absolute(index);
+                if resolved < 0 || resolved >= size_i {
+                    bail!(
+                        "Index {} out of bounds for dimension '{}' (length {}).",
+                        index,
+                        dim,
+                        size
+                    );
+                }
+                Ok(DimRange {
+                    start: resolved as u64,
+                    stop: resolved as u64 + 1,
+                    step: 1,
+                })
+            }
+            IndexSelection::Slice { start, stop, step } => {
+                let clamp = |index: i64| absolute(index).clamp(0, size_i) as u64;
+                let start = start.map_or(0, clamp);
+                let stop = stop.map_or(size, clamp).max(start);
+                Ok(DimRange { start, stop, step })
+            }
+        }
+    }
+}
+
+impl ValueSelection {
+    /// Resolve the selection against the values of the dimension's coordinate variable
+    pub fn resolve(&self, dim: &str, coordinates: &[f64]) -> Result<DimRange> {
+        match *self {
+            ValueSelection::Nearest(value) => {
+                let index = coordinates
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, c)| !c.is_nan())
+                    .min_by(|(_, a), (_, b)| (*a - value).abs().total_cmp(&(*b - value).abs()))
+                    .map(|(i, _)| i as u64)
+                    .ok_or_else(|| anyhow!("Coordinate variable '{}' has no values.", dim))?;
+                Ok(DimRange {
+                    start: index,
+                    stop: index + 1,
+                    step: 1,
+                })
+            }
+            ValueSelection::Range(a, b) => {
+                let (min, max) = (a.min(b), a.max(b));
+                // Coordinates are expected to be monotonic, so the matches are contiguous
+                let mut matching = coordinates
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, c)| **c >= min && **c <= max)
+                    .map(|(i, _)| i as u64);
+                let first = matching.next().ok_or_else(|| {
+                    anyhow!(
+                        "No values of coordinate '{}' lie within {}:{}.",
+                        dim,
+                        min,
+                        max
+                    )
+                })?;
+                let last = matching.next_back().unwrap_or(first);
+                Ok(DimRange {
+                    start: first,
+                    stop: last + 1,
+                    step: 1,
+                })
+            }
+        }
+    }
+}
+
+/// Build the hyperslab of `variable`, using the resolved per-dimension selections and the full
+/// extent of every other dimension
+pub fn build_hyperslab(variable: &Variable, selections: &HashMap<String, DimRange>) -> Hyperslab {
+    let dims = variable
+        .shape
+        .iter()
+        .enumerate()
+        .map(|(i, &size)| {
+            variable
+                .dimensions
+                .get(i)
+                .and_then(|dim| selections.get(&dim.name))
+                .copied()
+                .unwrap_or_else(|| DimRange::full(size))
+        })
+        .collect();
+    Hyperslab { dims }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_isel() {
+        let sel = parse_isel(&[
+            "time=0:10:2".to_string(),
+            "lat=3".to_string(),
+            "lon=-4:".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(
+            sel["time"],
+            IndexSelection::Slice {
+                start: Some(0),
+                stop: Some(10),
+                step: 2
+            }
+        );
+        assert_eq!(sel["lat"], IndexSelection::Index(3));
+        assert_eq!(
+            sel["lon"].resolve("lon", 10).unwrap(),
+            DimRange {
+                start: 6,
+                stop: 10,
+                step: 1
+            }
+        );
+
+        assert!(parse_isel(&["time=0:1:0".to_string()]).is_err());
+        assert!(parse_isel(&["time=a".to_string()]).is_err());
+        assert!(parse_isel(&["time".to_string()]).is_err());
+        assert!(IndexSelection::Index(10).resolve("lat", 10).is_err());
+    }
+
+    #[test]
+    fn test_value_selection() {
+        let sel = parse_sel(&["lat=-30:30".to_string(), "lon=101".to_string()]).unwrap();
+        let lat = [-60.0, -30.0, 0.0, 30.0, 60.0];
+        assert_eq!(
+            sel["lat"].resolve("lat", &lat).unwrap(),
+            DimRange {
+                start: 1,
+                stop: 4,
+                step: 1
+            }
+        );
+        // Descending coordinates select the same way
+        let descending: Vec<f64> = lat.iter().rev().copied().collect();
+        assert_eq!(sel["lat"].resolve("lat", &descending).unwrap().count(), 3);
+        assert_eq!(
+            sel["lon"]
+                .resolve("lon", &[0.0, 90.0, 180.0])
+                .unwrap()
+                .start,
+            1
+        );
+        assert!(
+            ValueSelection::Range(100.0, 200.0)
+                .resolve("lat", &lat)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_hyperslab_subsample() {
+        let slab = Hyperslab {
+            dims: vec![
+                DimRange {
+                    start: 0,
+                    stop: 3,
+                    step: 2,
+                },
+                DimRange {
+                    start: 1,
+                    stop: 4,
+                    step: 2,
+                },
+            ],
+        };
+        assert_eq!(slab.shape(), [2, 2]);
+        assert_eq!(slab.read_ranges(), [0..3, 1..4]);
+        assert_eq!(slab.dims[0].to_string(), "0:3:2");
+
+        // 3x3 block read from a 4x4 array holding 0..16, rows 0..3 and columns 1..4
+        let data = vec![1.0, 2.0, 3.0, 5.0, 6.0, 7.0, 9.0, 10.0, 11.0];
+        assert_eq!(slab.subsample(data), [1.0, 3.0, 9.0, 11.0]);
+        assert_eq!(slab.dims[1].truncated(1).count(), 1);
+
+        let range = DimRange {
+            start: 1,
+            stop: 10,
+            step: 3,
+        };
+        assert_eq!(range.slice(1, 5).to_string(), "4:10:3");
+        assert_eq!(range.slice(1, 1).to_string(), "4");
+        assert_eq!(range.slice(5, 2).count(), 0);
+    }
+
+    #[test]
+    fn test_hyperslab_split_sparse() {
+        // Rows 0 and 2 of 2-row chunks are read one at a time, columns 1 and 3 of 4-column
+        // chunks through the range covering them
+        let slab = Hyperslab {
+            dims: vec![
+                DimRange {
+                    start: 0,
+                    stop: 4,
+                    step: 2,
+                },
+                DimRange {
+                    start: 1,
+                    stop: 4,
+                    step: 2,
+                },
+            ],
+        };
+        let chunks = [2, 4];
+        assert_eq!(slab.read_shape(&chunks), [1, 3]);
+        let parts = slab.split_sparse(&chunks);
+        assert_eq!(
+            parts.iter().map(Hyperslab::read_ranges).collect::<Vec<_>>(),
+            [vec![0..1, 1..4], vec![2..3, 1..4]]
+        );
+
+        // Reassembled from a 4x4 array holding 0..16
+        let mut values = vec![0.0; 4];
+        for part in &parts {
+            let ranges = part.read_ranges();
+            let data: Vec<f64> = ranges[0]
+                .clone()
+                .flat_map(|row| ranges[1].clone().map(move |col| (row * 4 + col) as f64))
+                .collect();
+            slab.place(&mut values, part, &part.subsample(data));
+        }
+        assert_eq!(values, [1.0, 3.0, 9.0, 11.0]);
+
+        // Without chunks smaller than the step, the selection is read in one part
+        assert_eq!(slab.split_sparse(&[4, 4]), std::slice::from_ref(&slab));
+    }
+}
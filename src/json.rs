@@ -0,0 +1,340 @@
+use crate::cf::CfSummary;
+use crate::codec::Codec;
+use crate::metadata::{AttributeValue, ShardInventory, ZarrMetadata};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Version of the JSON document emitted by `--format json`.
+///
+/// Bump this whenever a field is renamed, removed or changes meaning. Adding new
+/// fields is considered backwards compatible and does not require a bump.
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize)]
+pub struct MetadataDocument {
+    pub schema_version: u32,
+    pub zarr_format: u8,
+    pub consolidated: bool,
+    pub dimensions: Vec<DimensionEntry>,
+    pub variables: Vec<VariableEntry>,
+    pub groups: Vec<GroupEntry>,
+    pub global_attributes: BTreeMap<String, serde_json::Value>,
+    pub cf: CfEntry,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coordinate_data: Option<BTreeMap<String, CoordinateValues>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shard_inventory: Option<BTreeMap<String, ShardInventoryEntry>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DimensionEntry {
+    pub name: String,
+    pub length: u64,
+    pub unlimited: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VariableEntry {
+    pub path: String,
+    pub name: String,
+    pub dtype: String,
+    pub shape: Vec<u64>,
+    pub chunks: Vec<u64>,
+    pub dimensions: Vec<String>,
+    pub order: String,
+    pub dimension_separator: String,
+    /// Name of the compressor (see `codecs` for its configuration)
+    pub compressor: Option<String>,
+    /// Names of the filters (see `codecs` for their configuration)
+    pub filters: Vec<String>,
+    /// Full codec pipeline in encoding order, as `{"name": ..., "configuration": {...}}`
+    pub codecs: Vec<serde_json::Value>,
+    pub fill_value: serde_json::Value,
+    pub attributes: BTreeMap<String, serde_json::Value>,
+    pub sharding: Option<ShardingEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShardingEntry {
+    pub inner_chunk_shape: Vec<u64>,
+    pub codecs: Vec<serde_json::Value>,
+    pub index_codecs: Vec<serde_json::Value>,
+    pub index_location: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GroupEntry {
+    pub path: String,
+    pub name: String,
+    pub attributes: BTreeMap<String, serde_json::Value>,
+    pub children: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CfEntry {
+    pub conventions: Option<String>,
+    pub axes: Vec<CfAxisEntry>,
+    pub suggested_plot_dims: Option<[String; 2]>,
+    pub suggested_slice_dims: Vec<String>,
+    pub candidate_data_vars: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CfAxisEntry {
+    pub axis: String,
+    pub dim: String,
+    pub coord_var: String,
+}
+
+/// Values of a coordinate variable, or the reason they could not be read.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum CoordinateValues {
+    Values(Vec<Option<f64>>),
+    Error { error: String },
+}
+
+impl CoordinateValues {
+    pub fn from_result(result: anyhow::Result<Vec<f64>>) -> Self {
+        match result {
+            // JSON has no representation for NaN/Inf, so emit them as null.
+            Ok(values) => Self::Values(
+                values
+                    .into_iter()
+                    .map(|v| if v.is_finite() { Some(v) } else { None })
+                    .collect(),
+            ),
+            Err(e) => Self::Error {
+                error: e.to_string(),
+            },
+        }
+    }
+}
+
+/// Shards of a sharded array, or the reason they could not be read.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum ShardInventoryEntry {
+    Inventory {
+        total_shards: u64,
+        inner_chunks_per_shard: u64,
+        shards: Vec<ShardEntry>,
+    },
+    Error {
+        error: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShardEntry {
+    pub key: String,
+    pub populated_inner_chunks: u64,
+}
+
+impl ShardInventoryEntry {
+    pub fn from_result(result: anyhow::Result<ShardInventory>) -> Self {
+        match result {
+            Ok(inventory) => Self::Inventory {
+                total_shards: inventory.total_shards,
+                inner_chunks_per_shard: inventory.inner_chunks_per_shard,
+                shards: inventory
+                    .shards
+                    .into_iter()
+                    .map(|s| ShardEntry {
+                        key: s.key,
+                        populated_inner_chunks: s.populated_inner_chunks,
+                    })
+                    .collect(),
+            },
+            Err(e) => Self::Error {
+                error: e.to_string(),
+            },
+        }
+    }
+}
+
+impl MetadataDocument {
+    pub fn new(metadata: &ZarrMetadata, summary: &CfSummary) -> Self {
+        let mut dimensions: Vec<DimensionEntry> = metadata
+            .dimensions
+            .values()
+            .map(|d| DimensionEntry {
+                name: d.name.clone(),
+                length: d.max_length,
+                unlimited: d.is_unlimited,
+            })
+            .collect();
+        dimensions.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut variables: Vec<VariableEntry> = metadata
+            .variables
+            .values()
+            .map(|v| VariableEntry {
+                path: v.path.clone(),
+                name: v.name.clone(),
+                dtype: v.dtype.clone(),
+                shape: v.shape.clone(),
+                chunks: v.chunks.clone(),
+                dimensions: v.dimensions.iter().map(|d| d.name.clone()).collect(),
+                order: v.order.clone(),
+                dimension_separator: v.dimension_separator.clone(),
+                compressor: v.codecs.compressor().map(|c| c.name().to_string()),
+                filters: v
+                    .codecs
+                    .filters()
+                    .into_iter()
+                    .map(|c| c.name().to_string())
+                    .collect(),
+                codecs: v.codecs.codecs().into_iter().map(Codec::to_json).collect(),
+                fill_value: v
+                    .fill_value
+                    .as_ref()
+                    .map(attribute_to_json)
+                    .unwrap_or(serde_json::Value::Null),
+                attributes: sorted_attributes(&v.attributes),
+                sharding: v.codecs.sharding().map(|s| ShardingEntry {
+                    inner_chunk_shape: s.inner_chunk_shape.clone(),
+                    codecs: s.codecs.iter().map(Codec::to_json).collect(),
+                    index_codecs: s.index_codecs.iter().map(Codec::to_json).collect(),
+                    index_location: s.index_location.clone(),
+                }),
+            })
+            .collect();
+        variables.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let mut groups: Vec<GroupEntry> = metadata
+            .groups
+            .values()
+            .map(|g| {
+                let mut children = g.children.clone();
+                children.sort();
+                GroupEntry {
+                    path: g.path.clone(),
+                    name: g.name.clone(),
+                    attributes: sorted_attributes(&g.attributes),
+                    children,
+                }
+            })
+            .collect();
+        groups.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let cf = CfEntry {
+            conventions: summary.conventions.clone(),
+            axes: summary
+                .axes
+                .iter()
+                .map(|a| CfAxisEntry {
+                    axis: a.axis.to_string(),
+                    dim: a.dim.clone(),
+                    coord_var: a.coord_var.clone(),
+                })
+                .collect(),
+            suggested_plot_dims: summary
+                .suggested_plot_dims
+                .as_ref()
+                .map(|(y, x)| [y.clone(), x.clone()]),
+            suggested_slice_dims: summary.suggested_slice_dims.clone(),
+            candidate_data_vars: summary.candidate_data_vars.clone(),
+        };
+
+        Self {
+            schema_version: SCHEMA_VERSION,
+            zarr_format: metadata.zarr_format,
+            consolidated: metadata.consolidated,
+            dimensions,
+            variables,
+            groups,
+            global_attributes: sorted_attributes(&metadata.global_attributes),
+            cf,
+            coordinate_data: None,
+            shard_inventory: None,
+        }
+    }
+}
+
+/// Convert attributes into a map with deterministic key order (nested objects included).
+fn sorted_attributes(
+    attributes: &HashMap<String, AttributeValue>,
+) -> BTreeMap<String, serde_json::Value> {
+    attributes
+        .iter()
+        .map(|(k, v)| (k.clone(), attribute_to_json(v)))
+        .collect()
+}
+
+fn attribute_to_json(value: &AttributeValue) -> serde_json::Value {
+    // serde_json keeps insertion order (preserve_order), so nested objects need sorting too
+    crate::cdl::sorted_json(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::CodecPipeline;
+    use crate::metadata::Variable;
+
+    #[test]
+    fn test_document_is_sorted_and_versioned() {
+        let mut md = ZarrMetadata::new();
+        for name in ["b", "a"] {
+            let mut attributes = HashMap::new();
+            attributes.insert("z".to_string(), AttributeValue::Integer(1));
+            attributes.insert("y".to_string(), AttributeValue::Number(f64::NAN));
+            let nested = ["d", "c", "b", "a"]
+                .into_iter()
+                .map(|k| (k.to_string(), AttributeValue::Null))
+                .collect();
+            attributes.insert("x".to_string(), AttributeValue::Object(nested));
+            md.variables.insert(
+                name.to_string(),
+                Variable {
+                    name: name.to_string(),
+                    path: name.to_string(),
+                    dtype: "<f4".to_string(),
+                    shape: vec![3],
+                    chunks: vec![3],
+                    codecs: CodecPipeline::V2 {
+                        filters: vec![],
+                        compressor: Some(Codec::Zlib { level: 1 }),
+                    },
+                    fill_value: Some(AttributeValue::Number(-9999.0)),
+                    order: "C".to_string(),
+                    dimension_separator: ".".to_string(),
+                    attributes,
+                    dimensions: vec![],
+                },
+            );
+        }
+        md.infer_dimensions();
+
+        let doc = MetadataDocument::new(&md, &CfSummary::default());
+        let json = serde_json::to_value(&doc).unwrap();
+
+        assert_eq!(json["schema_version"], SCHEMA_VERSION);
+        assert_eq!(json["variables"][0]["path"], "a");
+        assert_eq!(json["variables"][1]["path"], "b");
+        assert_eq!(json["variables"][0]["fill_value"], -9999.0);
+        assert_eq!(json["variables"][0]["compressor"], "zlib");
+        assert_eq!(
+            json["variables"][0]["codecs"],
+            serde_json::json!([{"name": "zlib", "configuration": {"level": 1}}])
+        );
+        assert_eq!(json["variables"][0]["dimensions"][0], "dim_0");
+        // NaN attributes are not representable in JSON and become null.
+        assert!(json["variables"][0]["attributes"]["y"].is_null());
+        assert!(json.get("coordinate_data").is_none());
+
+        let keys: Vec<&String> = json["variables"][0]["attributes"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .collect();
+        assert_eq!(keys, vec!["x", "y", "z"]);
+        let nested: Vec<&String> = json["variables"][0]["attributes"]["x"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .collect();
+        assert_eq!(nested, vec!["a", "b", "c", "d"]);
+    }
+}
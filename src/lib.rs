@@ -1,8 +1,14 @@
+pub mod codec;
+pub mod format;
+pub mod hyperslab;
 pub mod metadata;
 pub mod plot;
+pub mod storage;
 pub mod store;
 
 // Re-export commonly used types for tests
+pub use codec::{Codec, CodecPipeline};
 pub use metadata::{AttributeValue, Dimension, DimensionInfo, Group, Variable, ZarrMetadata};
 pub use plot::PlotSelection;
+pub use storage::{FilesystemStorage, MemoryStorage, Storage};
 pub use store::ZarrStore;
@@ -0,0 +1,316 @@
+//! Store consistency checks of the `lint` subcommand and `--verify-consolidated`
+//!
+//! Unlike `cf-check`, which judges the metadata itself, these checks compare the consolidated
+//! metadata with the per-node metadata files it was built from.
+
+use crate::store::ZarrStore;
+use anyhow::Result;
+use serde_json::{Map, Value, json};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// Stable identifiers of the lint checks; the string form is part of the JSON output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintCode {
+    /// A node on disk is missing from the consolidated metadata
+    NodeNotConsolidated,
+    /// A node in the consolidated metadata is no longer on disk
+    NodeDeleted,
+    /// An array's shape differs, typically after an append
+    ShapeMismatch,
+    /// An attribute was added, removed or changed on disk
+    AttributeDrift,
+    /// Any other metadata field differs
+    MetadataMismatch,
+}
+
+impl LintCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LintCode::NodeNotConsolidated => "CONSOLIDATED-NODE-MISSING",
+            LintCode::NodeDeleted => "CONSOLIDATED-NODE-DELETED",
+            LintCode::ShapeMismatch => "CONSOLIDATED-SHAPE-MISMATCH",
+            LintCode::AttributeDrift => "CONSOLIDATED-ATTRIBUTE-DRIFT",
+            LintCode::MetadataMismatch => "CONSOLIDATED-METADATA-MISMATCH",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintIssue {
+    pub code: LintCode,
+    /// Node path, `/` for the root group
+    pub path: String,
+    pub message: String,
+}
+
+/// Metadata of one node, split into its definition and its attributes
+#[derive(Debug, Default)]
+struct NodeMetadata {
+    /// `array` or `group`, if the node has an array or group document
+    kind: Option<&'static str>,
+    definition: Map<String, Value>,
+    attributes: Map<String, Value>,
+}
+
+fn node_kind(definition: &Map<String, Value>, is_array: bool) -> &'static str {
+    match definition.get("node_type").and_then(Value::as_str) {
+        Some("array") => "array",
+        Some(_) => "group",
+        None if is_array => "array",
+        None => "group",
+    }
+}
+
+/// Group metadata documents, keyed as in consolidated metadata, by node
+fn nodes(documents: &BTreeMap<String, Value>) -> BTreeMap<String, NodeMetadata> {
+    let mut nodes: BTreeMap<String, NodeMetadata> = BTreeMap::new();
+    for (key, document) in documents {
+        let object = document.as_object().cloned().unwrap_or_default();
+        let (path, file) = key.rsplit_once('/').unwrap_or(("", key));
+        match file {
+            // v2 documents: the node's files are separate keys
+            ".zattrs" => nodes.entry(path.to_string()).or_default().attributes = object,
+            ".zarray" | ".zgroup" => {
+                let node = nodes.entry(path.to_string()).or_default();
+                node.kind = Some(node_kind(&object, file == ".zarray"));
+                node.definition = object;
+            }
+            // v3 documents: one per node, keyed by the node path, holding its attributes
+            _ => {
+                let mut definition = object;
+                let attributes = match definition.remove("attributes") {
+                    Some(Value::Object(attributes)) => attributes,
+                    _ => Map::new(),
+                };
+                let node = nodes.entry(key.clone()).or_default();
+                node.kind = Some(node_kind(&definition, false));
+                node.definition = definition;
+                node.attributes = attributes;
+            }
+        }
+    }
+    nodes
+}
+
+fn describe(value: Option<&Value>) -> String {
+    value.map_or_else(|| "absent".to_string(), Value::to_string)
+}
+
+/// Compare consolidated metadata entries (`stored`) with the per-node metadata documents
+/// (`scanned`), both keyed as in consolidated metadata
+pub fn check_consolidated(
+    stored: &BTreeMap<String, Value>,
+    scanned: &BTreeMap<String, Value>,
+) -> Vec<LintIssue> {
+    let (stored, scanned) = (nodes(stored), nodes(scanned));
+    let paths: BTreeSet<&String> = stored.keys().chain(scanned.keys()).collect();
+
+    let mut issues = Vec::new();
+    for path in paths {
+        let label = if path.is_empty() { "/" } else { path.as_str() };
+        let mut issue = |code: LintCode, message: String| {
+            issues.push(LintIssue {
+                code,
+                path: label.to_string(),
+                message,
+            })
+        };
+
+        let (old, new) = (stored.get(path), scanned.get(path));
+        match (old.and_then(|n| n.kind), new.and_then(|n| n.kind)) {
+            (None, Some(kind)) => {
+                issue(
+                    LintCode::NodeNotConsolidated,
+                    format!("{} on disk is missing from the consolidated metadata", kind),
+                );
+                continue;
+            }
+            (Some(kind), None) => {
+                issue(
+                    LintCode::NodeDeleted,
+                    format!("{} in the consolidated metadata is no longer on disk", kind),
+                );
+                continue;
+            }
+            _ => {}
+        }
+
+        let empty = NodeMetadata::default();
+        let (old, new) = (old.unwrap_or(&empty), new.unwrap_or(&empty));
+        let fields: BTreeSet<&String> =
+            old.definition.keys().chain(new.definition.keys()).collect();
+        for field in fields {
+            let (a, b) = (old.definition.get(field), new.definition.get(field));
+            if a == b {
+                continue;
+            }
+            let code = if field == "shape" {
+                LintCode::ShapeMismatch
+            } else {
+                LintCode::MetadataMismatch
+            };
+            issue(
+                code,
+                format!(
+                    "{} is {} in the consolidated metadata but {} on disk",
+                    field,
+                    describe(a),
+                    describe(b)
+                ),
+            );
+        }
+
+        let names: BTreeSet<&String> = old.attributes.keys().chain(new.attributes.keys()).collect();
+        for name in names {
+            let message = match (old.attributes.get(name), new.attributes.get(name)) {
+                (None, Some(_)) => format!(
+                    "attribute '{}' is on disk but not in the consolidated metadata",
+                    name
+                ),
+                (Some(_), None) => format!(
+                    "attribute '{}' is in the consolidated metadata but no longer on disk",
+                    name
+                ),
+                (Some(a), Some(b)) if a != b => format!(
+                    "attribute '{}' is {} in the consolidated metadata but {} on disk",
+                    name, a, b
+                ),
+                _ => continue,
+            };
+            issue(LintCode::AttributeDrift, message);
+        }
+    }
+    issues
+}
+
+/// Check the consolidated metadata of a store against its per-node metadata files, or return
+/// `None` if the store has no consolidated metadata
+pub fn lint_store(store: &ZarrStore) -> Result<Option<Vec<LintIssue>>> {
+    let Some(stored) = store.read_consolidated_documents()? else {
+        return Ok(None);
+    };
+    let scanned = store.scan_metadata_documents()?;
+    Ok(Some(check_consolidated(&stored, &scanned)))
+}
+
+/// Text report: one line per issue and a summary
+pub fn render_text(issues: &[LintIssue]) -> String {
+    let mut out = String::new();
+    for issue in issues {
+        let _ = writeln!(
+            out,
+            "{}: [{}] {}",
+            issue.path,
+            issue.code.as_str(),
+            issue.message
+        );
+    }
+    if issues.is_empty() {
+        out.push_str("Consolidated metadata matches the store\n");
+    } else {
+        let _ = writeln!(
+            out,
+            "\n{} issue(s): run `zarr-dump consolidate` to rewrite the consolidated metadata",
+            issues.len()
+        );
+    }
+    out
+}
+
+pub fn to_json(issues: &[LintIssue]) -> Value {
+    Value::Array(
+        issues
+            .iter()
+            .map(|issue| {
+                json!({
+                    "code": issue.code.as_str(),
+                    "path": issue.path,
+                    "message": issue.message,
+                })
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn documents(entries: &[(&str, Value)]) -> BTreeMap<String, Value> {
+        entries
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_check_v2_consolidated() {
+        let stored = documents(&[
+            (".zgroup", json!({"zarr_format": 2})),
+            ("tas/.zarray", json!({"shape": [10, 5], "chunks": [10, 5]})),
+            ("tas/.zattrs", json!({"units": "K", "history": "created"})),
+            ("old/.zarray", json!({"shape": [1], "chunks": [1]})),
+        ]);
+        let scanned = documents(&[
+            (".zgroup", json!({"zarr_format": 2})),
+            ("tas/.zarray", json!({"shape": [12, 5], "chunks": [10, 5]})),
+            (
+                "tas/.zattrs",
+                json!({"units": "degC", "comment": "appended"}),
+            ),
+            ("pr/.zarray", json!({"shape": [12, 5], "chunks": [10, 5]})),
+        ]);
+
+        let issues = check_consolidated(&stored, &scanned);
+        assert_eq!(
+            render_text(&issues),
+            "old: [CONSOLIDATED-NODE-DELETED] array in the consolidated metadata is no longer on disk\n\
+             pr: [CONSOLIDATED-NODE-MISSING] array on disk is missing from the consolidated metadata\n\
+             tas: [CONSOLIDATED-SHAPE-MISMATCH] shape is [10,5] in the consolidated metadata but [12,5] on disk\n\
+             tas: [CONSOLIDATED-ATTRIBUTE-DRIFT] attribute 'comment' is on disk but not in the consolidated metadata\n\
+             tas: [CONSOLIDATED-ATTRIBUTE-DRIFT] attribute 'history' is in the consolidated metadata but no longer on disk\n\
+             tas: [CONSOLIDATED-ATTRIBUTE-DRIFT] attribute 'units' is \"K\" in the consolidated metadata but \"degC\" on disk\n\
+             \n6 issue(s): run `zarr-dump consolidate` to rewrite the consolidated metadata\n"
+        );
+        assert_eq!(to_json(&issues)[2]["code"], "CONSOLIDATED-SHAPE-MISMATCH");
+        assert!(check_consolidated(&scanned, &scanned).is_empty());
+    }
+
+    #[test]
+    fn test_check_v3_consolidated() {
+        let group = json!({"zarr_format": 3, "node_type": "group", "attributes": {}});
+        let stored = documents(&[
+            ("g", group.clone()),
+            (
+                "g/x",
+                json!({"node_type": "array", "shape": [4], "attributes": {"a": 1}}),
+            ),
+        ]);
+        let scanned = documents(&[
+            ("g", group),
+            (
+                "g/x",
+                json!({"node_type": "array", "shape": [4], "fill_value": 0, "attributes": {"a": 1}}),
+            ),
+            ("g/y", json!({"node_type": "group", "attributes": {}})),
+        ]);
+
+        let issues = check_consolidated(&stored, &scanned);
+        let codes: Vec<(&str, LintCode)> = issues
+            .iter()
+            .map(|issue| (issue.path.as_str(), issue.code))
+            .collect();
+        assert_eq!(
+            codes,
+            [
+                ("g/x", LintCode::MetadataMismatch),
+                ("g/y", LintCode::NodeNotConsolidated)
+            ]
+        );
+        assert_eq!(
+            issues[1].message,
+            "group on disk is missing from the consolidated metadata"
+        );
+    }
+}
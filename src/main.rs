@@ -1,13 +1,33 @@
+mod attrs;
+mod cdl;
 mod cf;
+mod cf_report;
+mod chunks;
+mod codec;
+mod consolidate;
+mod diff;
+mod export;
+mod format;
+mod fsutil;
+mod hyperslab;
+mod json;
+mod lint;
 mod metadata;
+mod netcdf;
 mod plot;
+mod stats;
+mod storage;
 mod store;
+mod verify;
 mod visualize;
 
 use anyhow::Context;
-use clap::{Parser, Subcommand};
-use metadata::{AttributeValue, ZarrMetadata};
-use std::path::PathBuf;
+use cdl::{GroupLayout, variable_label};
+use clap::{Parser, Subcommand, ValueEnum};
+use codec::{Codec, ShardingInfo};
+use metadata::{AttributeValue, ShardInventory, ZarrMetadata};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use std::process;
 use store::ZarrStore;
 
@@ -17,7 +37,274 @@ enum Command {
     CfCheck {
         /// Path to the Zarr store root directory
         path: PathBuf,
+
+        /// Report format
+        #[arg(long, value_enum, default_value_t = CfReportFormat::Text)]
+        format: CfReportFormat,
+    },
+    /// Print variable data values in CDL layout (like ncdump -v), optionally for a hyperslab
+    Data {
+        /// Path to the Zarr store root directory
+        path: PathBuf,
+
+        /// Variables to print (comma separated names or paths); all variables if omitted
+        #[arg(short = 'v', long = "variables", value_name = "VAR1,VAR2")]
+        variables: Option<String>,
+
+        /// Index selection 'dim=index' or 'dim=start:stop[:step]' (negative indices count from
+        /// the end)
+        #[arg(long, value_name = "DIM=START:STOP:STEP")]
+        isel: Vec<String>,
+
+        /// Coordinate value selection 'dim=value' (nearest) or 'dim=min:max' (inclusive)
+        #[arg(long, value_name = "DIM=MIN:MAX")]
+        sel: Vec<String>,
+
+        /// Print at most this many rows (values along all but the last dimension) per variable
+        #[arg(long, value_name = "N")]
+        max_rows: Option<usize>,
+
+        /// Print at most this many values per row (along the last dimension)
+        #[arg(long, value_name = "N")]
+        max_cols: Option<usize>,
+
+        /// Significant digits for non-integer values (like ncdump -p)
+        #[arg(short = 'p', long, value_name = "DIGITS")]
+        precision: Option<usize>,
+    },
+    /// Compute statistics of variables (valid, fill/missing and NaN counts, min, max, mean,
+    /// standard deviation), streaming their data
+    Stats {
+        /// Path to the Zarr store root directory
+        path: PathBuf,
+
+        /// Variables to summarize (comma separated names or paths); all numeric variables if
+        /// omitted
+        #[arg(short = 'v', long = "variables", value_name = "VAR1,VAR2")]
+        variables: Option<String>,
+
+        /// Index selection 'dim=index' or 'dim=start:stop[:step]' (negative indices count from
+        /// the end)
+        #[arg(long, value_name = "DIM=START:STOP:STEP")]
+        isel: Vec<String>,
+
+        /// Report format
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+    },
+    /// Report how many chunks of each variable are stored and how large they are
+    Chunks {
+        /// Path to the Zarr store root directory
+        path: PathBuf,
+
+        /// Variables to report (comma separated names or paths); all variables if omitted
+        #[arg(short = 'v', long = "variables", value_name = "VAR1,VAR2")]
+        variables: Option<String>,
+
+        /// Report format
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+    },
+    /// Check the chunks of every array: missing, orphaned (outside the chunk grid), corrupt
+    /// (failing to decode) and wrong-size chunks
+    Verify {
+        /// Path to the Zarr store root directory
+        path: PathBuf,
+
+        /// Variables to check (comma separated names or paths); all variables if omitted
+        #[arg(short = 'v', long = "variables", value_name = "VAR1,VAR2")]
+        variables: Option<String>,
+
+        /// Also fail when chunks are missing (by default they are reported, since Zarr does not
+        /// store chunks that only hold the fill value)
+        #[arg(long)]
+        require_all_chunks: bool,
+
+        /// Report format
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+    },
+    /// Export the store, or a subset of it, to another file format
+    Export {
+        /// Path to the Zarr store root directory
+        path: PathBuf,
+
+        /// Output file format (netcdf writes classic files, not netCDF-4)
+        #[arg(long = "to", value_enum, value_name = "FORMAT")]
+        to: ExportFormat,
+
+        /// Output file
+        output: PathBuf,
+
+        /// Variables to export (comma separated names or paths), with the coordinate, bounds and
+        /// grid mapping variables they use; all variables if omitted. csv, npy and raw files
+        /// hold exactly one variable.
+        #[arg(short = 'v', long = "variables", value_name = "VAR1,VAR2")]
+        variables: Option<String>,
+
+        /// Index selection 'dim=index' or 'dim=start:stop[:step]' (negative indices count from
+        /// the end)
+        #[arg(long, value_name = "DIM=START:STOP:STEP")]
+        isel: Vec<String>,
+    },
+    /// Write consolidated metadata (v2 `.zmetadata`, or the v3 root `zarr.json`
+    /// `consolidated_metadata` block) from the per-node metadata files
+    Consolidate {
+        /// Path to the Zarr store root directory
+        path: PathBuf,
+
+        /// Only check whether the existing consolidated metadata matches the per-node metadata
+        /// files, failing if it is missing or stale
+        #[arg(long)]
+        check: bool,
+    },
+    /// Check that the consolidated metadata matches the per-node metadata files: nodes missing
+    /// from it or deleted from disk, shape mismatches after appends and attribute drift
+    Lint {
+        /// Path to the Zarr store root directory
+        path: PathBuf,
+
+        /// Report format
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
     },
+    /// Edit an attribute of a variable, group or the store (like ncatted): set, delete, rename
+    /// or append to it
+    Attrs {
+        /// What to do with the attribute
+        #[arg(value_enum)]
+        action: AttrsAction,
+
+        /// Path to the Zarr store root directory
+        path: PathBuf,
+
+        /// Attribute to edit as 'VAR:ATTR' (a variable name or path, or a group path), or
+        /// ':ATTR' for a global attribute
+        #[arg(value_name = "VAR:ATTR")]
+        target: String,
+
+        /// Value to set or append, or the new name for rename
+        #[arg(allow_hyphen_values = true)]
+        value: Option<String>,
+
+        /// Type of the value (comma separated values make a list for int, float and bool)
+        #[arg(short = 't', long = "type", value_enum, default_value_t = AttrType::Auto)]
+        value_type: AttrType,
+
+        /// Show the change without writing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Compare two stores: added and removed variables, groups and dimensions, changed data types,
+    /// shapes, chunking, codecs and attributes, and optionally the data values
+    Diff {
+        /// Path to the first (old) Zarr store
+        path: PathBuf,
+
+        /// Path to the second (new) Zarr store
+        other: PathBuf,
+
+        /// Also compare the values of the variables present in both stores, chunk by chunk
+        #[arg(long)]
+        data: bool,
+
+        /// Relative tolerance of the data comparison
+        #[arg(long, value_name = "RTOL", default_value_t = 0.0, requires = "data")]
+        rtol: f64,
+
+        /// Absolute tolerance of the data comparison
+        #[arg(long, value_name = "ATOL", default_value_t = 0.0, requires = "data")]
+        atol: f64,
+
+        /// Variables to compare the data of (comma separated names or paths); all variables if
+        /// omitted
+        #[arg(
+            short = 'v',
+            long = "variables",
+            value_name = "VAR1,VAR2",
+            requires = "data"
+        )]
+        variables: Option<String>,
+
+        /// Report format
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+    },
+}
+
+/// Output format of the `stats`, `chunks`, `verify`, `lint` and `diff` reports
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum ReportFormat {
+    /// Aligned text table
+    Text,
+    /// JSON document keyed by variable path
+    Json,
+}
+
+/// File format of the `export` subcommand
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum ExportFormat {
+    /// netCDF classic file (64-bit offset, or CDF-5 for unsigned and 64-bit integers), not
+    /// netCDF-4/HDF5: groups are flattened and string variables are skipped
+    Netcdf,
+    /// One row per element with coordinate columns (single variable)
+    Csv,
+    /// NumPy .npy array (single variable)
+    Npy,
+    /// Little-endian values in C order without a header (single variable)
+    Raw,
+}
+
+/// Edit made by the `attrs` subcommand
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum AttrsAction {
+    /// Create or overwrite the attribute
+    Set,
+    /// Remove the attribute
+    Delete,
+    /// Rename the attribute to VALUE
+    Rename,
+    /// Append VALUE to a string attribute, or add it to a list
+    Append,
+}
+
+/// Type of the value given to `attrs set` and `attrs append`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum AttrType {
+    /// Integer, float or true/false if the value parses as one, otherwise a string
+    Auto,
+    /// String
+    String,
+    /// 64-bit integer(s)
+    Int,
+    /// Float(s)
+    Float,
+    /// true/false
+    Bool,
+    /// Any JSON value, e.g. '[0, 100]'
+    Json,
+}
+
+/// Output format for the default metadata dump
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// NetCDF-style (ncdump -h) text
+    Text,
+    /// Versioned JSON document (see README for the schema)
+    Json,
+}
+
+/// Output format for `cf-check` reports
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum CfReportFormat {
+    /// Human-readable text
+    Text,
+    /// JSON document with stable issue codes
+    Json,
+    /// SARIF 2.1.0 log for code-scanning integrations
+    Sarif,
+    /// JUnit XML for CI test reporting
+    Junit,
 }
 
 #[derive(Parser)]
@@ -34,13 +321,43 @@ struct Args {
     path: Option<PathBuf>,
 
     /// Disable colored output
-    #[arg(long)]
+    #[arg(long, global = true)]
     no_color: bool,
 
+    /// Output format for the metadata dump
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Write the metadata dump as strictly valid CDL that `ncgen -k nc4` accepts
+    #[arg(long, conflicts_with_all = ["format", "shards", "plot"])]
+    cdl: bool,
+
     /// Show coordinate variable data values (like ncdump -c)
     #[arg(short = 'c', long = "coordinate-data")]
     coordinate_data: bool,
 
+    /// List the shards of sharded (v3 `sharding_indexed`) arrays and their populated inner chunks
+    #[arg(long)]
+    shards: bool,
+
+    /// Only show these variables (comma separated names or paths) and the coordinate, bounds and
+    /// grid_mapping variables they refer to (like ncdump -v)
+    #[arg(short = 'v', long = "variables", value_name = "VAR1,VAR2")]
+    variables: Option<String>,
+
+    /// Only show this group and its subgroups (like ncdump -g)
+    #[arg(short = 'g', long = "group", value_name = "GROUP/PATH")]
+    group: Option<String>,
+
+    /// Show special virtual attributes (chunking, storage and codecs) like ncdump -s
+    #[arg(short = 's', long)]
+    special: bool,
+
+    /// Check that the consolidated metadata matches the per-node metadata files (see `lint`),
+    /// warning about every difference and failing after the dump
+    #[arg(long)]
+    verify_consolidated: bool,
+
     /// Plot a 2D slice of a variable in an interactive window
     #[arg(long, value_name = "VAR")]
     plot: Option<String>,
@@ -68,184 +385,1160 @@ async fn main() {
     }
 }
 
+/// Check that a store path exists and is a directory, zip archive or reference file (URLs are
+/// checked on load)
+fn validate_store_path(path: &Path) -> anyhow::Result<()> {
+    let is_url = path.to_str().is_some_and(storage::is_remote_location);
+    if !is_url && !path.exists() {
+        return Err(anyhow::anyhow!(
+            "Zarr store path '{}' does not exist. Please provide a valid path to a Zarr store directory.",
+            path.display()
+        ));
+    }
+
+    if !is_url && !path.is_dir() && !storage::is_zip_path(path) && !storage::is_kerchunk_path(path)
+    {
+        return Err(anyhow::anyhow!(
+            "Path '{}' is not a directory, .zip archive or kerchunk reference .json file. Zarr stores must be directories (or zip archives of them) containing .zarray, .zgroup, or .zmetadata files.",
+            path.display()
+        ));
+    }
+
+    Ok(())
+}
+
 async fn run() -> anyhow::Result<()> {
     let args = Args::parse();
 
-    let (path, mode) = match &args.command {
-        Some(Command::CfCheck { path }) => (path.clone(), "cf-check"),
-        None => (
-            args.path
-                .clone()
-                .ok_or_else(|| anyhow::anyhow!("Missing Zarr store path"))?,
-            "default",
-        ),
+    let path = match &args.command {
+        Some(Command::CfCheck { path, .. })
+        | Some(Command::Data { path, .. })
+        | Some(Command::Stats { path, .. })
+        | Some(Command::Chunks { path, .. })
+        | Some(Command::Verify { path, .. })
+        | Some(Command::Export { path, .. })
+        | Some(Command::Consolidate { path, .. })
+        | Some(Command::Lint { path, .. })
+        | Some(Command::Attrs { path, .. })
+        | Some(Command::Diff { path, .. }) => path.clone(),
+        None => args
+            .path
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Missing Zarr store path"))?,
     };
 
-    // Validate that the path exists and is a directory
-    if !path.exists() {
+    validate_store_path(&path)?;
+
+    // Keep stdout clean for machine-readable output
+    let machine_output = match &args.command {
+        Some(Command::CfCheck { format, .. }) => *format != CfReportFormat::Text,
+        Some(Command::Stats { format, .. })
+        | Some(Command::Chunks { format, .. })
+        | Some(Command::Verify { format, .. })
+        | Some(Command::Lint { format, .. })
+        | Some(Command::Diff { format, .. }) => *format != ReportFormat::Text,
+        Some(Command::Data { .. })
+        | Some(Command::Export { .. })
+        | Some(Command::Consolidate { .. })
+        | Some(Command::Attrs { .. }) => false,
+        None => args.format == OutputFormat::Json || args.cdl,
+    };
+    if !machine_output {
+        println!("Opening Zarr store: {}", path.display());
+    }
+
+    // Create and load Zarr store
+    let store = ZarrStore::new(&path)?.quiet(machine_output);
+
+    if args.plot.is_some() && args.command.is_some() {
         return Err(anyhow::anyhow!(
-            "Zarr store path '{}' does not exist. Please provide a valid path to a Zarr store directory.",
+            "Plotting is only supported in the default mode. Use `zarr-dump STORE --plot ...`, not a subcommand."
+        ));
+    }
+
+    // These work on the per-node metadata files rather than the loaded metadata, which may come
+    // from stale consolidated metadata
+    match &args.command {
+        Some(Command::Consolidate { check, .. }) => return run_consolidate(&store, &path, *check),
+        Some(Command::Lint { format, .. }) => return run_lint(&store, &path, *format),
+        Some(Command::Attrs {
+            action,
+            target,
+            value,
+            value_type,
+            dry_run,
+            ..
+        }) => {
+            let edit = AttrsEdit {
+                action: *action,
+                target,
+                value: value.as_deref(),
+                value_type: *value_type,
+                dry_run: *dry_run,
+            };
+            return run_attrs(&store, &path, &edit);
+        }
+        _ => {}
+    }
+
+    let metadata = store
+        .load_metadata()
+        .await
+        .with_context(|| format!("Failed to load Zarr store from '{}'", path.display()))?;
+
+    match &args.command {
+        Some(Command::CfCheck { format, .. }) => {
+            run_cf_check(&store, &metadata, &path, *format).await
+        }
+        Some(Command::Data {
+            variables,
+            isel,
+            sel,
+            max_rows,
+            max_cols,
+            precision,
+            ..
+        }) => {
+            let options = DataOptions {
+                isel: hyperslab::parse_isel(isel)?,
+                sel: hyperslab::parse_sel(sel)?,
+                max_rows: *max_rows,
+                max_cols: *max_cols,
+                precision: *precision,
+            };
+            let formatter = NetCdfFormatter::new(!args.no_color);
+            formatter
+                .print_data(&metadata, variables.as_deref(), &options, &store)
+                .await
+        }
+        Some(Command::Stats {
+            variables,
+            isel,
+            format,
+            ..
+        }) => run_stats(&store, &metadata, variables.as_deref(), isel, *format),
+        Some(Command::Chunks {
+            variables, format, ..
+        }) => run_chunks(&store, &metadata, variables.as_deref(), *format),
+        Some(Command::Verify {
+            variables,
+            require_all_chunks,
+            format,
+            ..
+        }) => run_verify(
+            &store,
+            &metadata,
+            variables.as_deref(),
+            *require_all_chunks,
+            *format,
+        ),
+        Some(Command::Diff {
+            other,
+            data,
+            rtol,
+            atol,
+            variables,
+            format,
+            ..
+        }) => {
+            let data_tolerance = data.then_some(diff::Tolerance {
+                rtol: *rtol,
+                atol: *atol,
+            });
+            let options = DiffOptions {
+                data_tolerance,
+                variables: variables.as_deref(),
+                format: *format,
+                machine_output,
+            };
+            run_diff(&store, &metadata, other, &options).await
+        }
+        Some(Command::Export {
+            to,
+            output,
+            variables,
+            isel,
+            ..
+        }) => run_export(&store, &metadata, *to, output, variables.as_deref(), isel),
+        Some(Command::Consolidate { .. } | Command::Lint { .. } | Command::Attrs { .. }) => {
+            unreachable!("handled before loading the metadata")
+        }
+        None => match &args.plot {
+            Some(plot_var) => run_plot(&args, &store, &metadata, plot_var),
+            None => run_dump(&args, &store, &path, metadata).await,
+        },
+    }
+}
+
+/// `consolidate`: write the consolidated metadata, or check it with `--check`
+fn run_consolidate(store: &ZarrStore, path: &Path, check: bool) -> anyhow::Result<()> {
+    let documents = store.scan_metadata_documents()?;
+    if documents.is_empty() && store.zarr_format()? == 2 {
+        return Err(anyhow::anyhow!(
+            "No Zarr arrays or groups found in '{}'",
             path.display()
         ));
     }
 
+    if check {
+        let Some(stored) = store.read_consolidated_documents()? else {
+            return Err(anyhow::anyhow!(
+                "'{}' has no consolidated metadata",
+                path.display()
+            ));
+        };
+        let entries = consolidate::stale_entries(&stored, &documents);
+        print!("{}", consolidate::render_check(&entries, documents.len()));
+        if !entries.is_empty() {
+            return Err(anyhow::anyhow!("Consolidated metadata check failed"));
+        }
+        return Ok(());
+    }
+
     if !path.is_dir() {
         return Err(anyhow::anyhow!(
-            "Path '{}' is not a directory. Zarr stores must be directories containing .zarray, .zgroup, or .zmetadata files.",
+            "Consolidated metadata can only be written to store directories, not '{}' (use --check to check it)",
             path.display()
         ));
     }
+    let count = documents.len();
+    let written = consolidate::write_consolidated(path, store.zarr_format()?, documents)?;
+    println!(
+        "Wrote consolidated metadata for {} node document(s) to {}",
+        count,
+        written.display()
+    );
+    Ok(())
+}
 
-    println!("Opening Zarr store: {}", path.display());
+/// `lint`: compare the consolidated metadata with the per-node metadata files
+fn run_lint(store: &ZarrStore, path: &Path, format: ReportFormat) -> anyhow::Result<()> {
+    let Some(issues) = lint::lint_store(store)? else {
+        return Err(anyhow::anyhow!(
+            "'{}' has no consolidated metadata to check",
+            path.display()
+        ));
+    };
+    match format {
+        ReportFormat::Text => print!("{}", lint::render_text(&issues)),
+        ReportFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&lint::to_json(&issues))?)
+        }
+    }
+    if !issues.is_empty() {
+        return Err(anyhow::anyhow!("Lint failed"));
+    }
+    Ok(())
+}
 
-    // Create and load Zarr store
-    let store = ZarrStore::new(&path)?;
-    let metadata = store
+/// Arguments of the `attrs` subcommand
+struct AttrsEdit<'a> {
+    action: AttrsAction,
+    target: &'a str,
+    value: Option<&'a str>,
+    value_type: AttrType,
+    dry_run: bool,
+}
+
+/// `attrs`: edit one attribute in its metadata file and refresh the consolidated metadata
+fn run_attrs(store: &ZarrStore, path: &Path, edit: &AttrsEdit) -> anyhow::Result<()> {
+    if !path.is_dir() {
+        return Err(anyhow::anyhow!(
+            "Attributes can only be edited in store directories, not '{}'",
+            path.display()
+        ));
+    }
+    let (node, name) = attrs::parse_target(edit.target)?;
+    let zarr_format = store.zarr_format()?;
+    let node_path = resolve_attribute_node(store, path, zarr_format, node)?;
+
+    let action_name = edit
+        .action
+        .to_possible_value()
+        .map(|v| v.get_name().to_string());
+    let value_type = match edit.value_type {
+        AttrType::Auto => attrs::ValueType::Auto,
+        AttrType::String => attrs::ValueType::String,
+        AttrType::Int => attrs::ValueType::Int,
+        AttrType::Float => attrs::ValueType::Float,
+        AttrType::Bool => attrs::ValueType::Bool,
+        AttrType::Json => attrs::ValueType::Json,
+    };
+    let required_value = || {
+        edit.value.ok_or_else(|| {
+            anyhow::anyhow!(
+                "attrs {} needs a value",
+                action_name.clone().unwrap_or_default()
+            )
+        })
+    };
+    let attribute_edit = match edit.action {
+        AttrsAction::Set => attrs::Edit::Set(attrs::parse_value(required_value()?, value_type)?),
+        AttrsAction::Append => {
+            attrs::Edit::Append(attrs::parse_value(required_value()?, value_type)?)
+        }
+        AttrsAction::Rename => attrs::Edit::Rename(required_value()?.to_string()),
+        AttrsAction::Delete if edit.value.is_some() => {
+            return Err(anyhow::anyhow!("attrs delete does not take a value"));
+        }
+        AttrsAction::Delete => attrs::Edit::Delete,
+    };
+
+    let mut document = attrs::AttributeDocument::open(path, &node_path, zarr_format)?;
+    let label = if node_path.is_empty() {
+        "/"
+    } else {
+        &node_path
+    };
+    let differences = attrs::apply_edit(document.attributes_mut()?, label, name, attribute_edit)?;
+    if differences.is_empty() {
+        println!("'{}' already has this value", edit.target);
+        return Ok(());
+    }
+    print!("{}", diff::render_lines(&differences));
+    if edit.dry_run {
+        println!("Dry run: '{}' not changed", document.file.display());
+        return Ok(());
+    }
+    document.save()?;
+    println!("Wrote {}", document.file.display());
+
+    // Keep consolidated metadata in step with the edited file
+    if store.read_consolidated_documents()?.is_some() {
+        let documents = store.scan_metadata_documents()?;
+        let written = consolidate::write_consolidated(path, zarr_format, documents)?;
+        println!("Refreshed consolidated metadata in {}", written.display());
+    }
+    Ok(())
+}
+
+/// `cf-check`: report CF convention issues, failing on errors
+async fn run_cf_check(
+    store: &ZarrStore,
+    metadata: &ZarrMetadata,
+    path: &Path,
+    format: CfReportFormat,
+) -> anyhow::Result<()> {
+    let report = cf::cf_check(store, metadata).await?;
+    let store_label = path.display().to_string();
+    match format {
+        CfReportFormat::Text => report.print(),
+        CfReportFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&report.to_json(&store_label))?
+        ),
+        CfReportFormat::Sarif => println!(
+            "{}",
+            serde_json::to_string_pretty(&report.to_sarif(&store_label))?
+        ),
+        CfReportFormat::Junit => {
+            let variables: Vec<String> = metadata
+                .variables
+                .values()
+                .map(|v| {
+                    if v.path.is_empty() {
+                        "root".to_string()
+                    } else {
+                        v.path.clone()
+                    }
+                })
+                .collect();
+            print!("{}", report.to_junit(&store_label, &variables));
+        }
+    }
+    if report.has_errors() {
+        return Err(anyhow::anyhow!("CF check failed"));
+    }
+    Ok(())
+}
+
+/// `stats`: statistics of the numeric variables
+fn run_stats(
+    store: &ZarrStore,
+    metadata: &ZarrMetadata,
+    variables: Option<&str>,
+    isel: &[String],
+    format: ReportFormat,
+) -> anyhow::Result<()> {
+    let isel = hyperslab::parse_isel(isel)?;
+    let mut paths = select_variable_paths(metadata, variables)?;
+    // Only numbers have statistics
+    paths.retain(|path| {
+        let var = &metadata.variables[path];
+        let numeric =
+            cdl::CdlType::from_dtype(&var.dtype).is_some_and(|t| t != cdl::CdlType::String);
+        if !numeric && variables.is_some() {
+            eprintln!(
+                "Warning: skipping variable '{}': data type '{}' is not numeric",
+                variable_label(var),
+                var.dtype
+            );
+        }
+        numeric
+    });
+
+    let slabs = export::select_hyperslabs(metadata, &paths, &isel)?;
+    let mut rows = Vec::new();
+    for (path, slab) in paths.iter().zip(&slabs) {
+        let var = &metadata.variables[path];
+        rows.push((
+            variable_label(var).to_string(),
+            stats::compute_stats(store, var, slab)?,
+        ));
+    }
+
+    match format {
+        ReportFormat::Text => print!("{}", stats::render_table(&rows)),
+        ReportFormat::Json => {
+            let document: serde_json::Map<String, serde_json::Value> = rows
+                .iter()
+                .map(|(name, stats)| (name.clone(), stats.to_json()))
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&document)?);
+        }
+    }
+    Ok(())
+}
+
+/// `chunks`: how many chunks of each variable are written, and their sizes
+fn run_chunks(
+    store: &ZarrStore,
+    metadata: &ZarrMetadata,
+    variables: Option<&str>,
+    format: ReportFormat,
+) -> anyhow::Result<()> {
+    let mut rows = Vec::new();
+    for path in select_variable_paths(metadata, variables)? {
+        let var = &metadata.variables[&path];
+        // Arrays zarrs cannot open (e.g. unsupported data types) are left out of the report
+        match store.read_chunk_inventory(var) {
+            Ok(inventory) => rows.push((variable_label(var).to_string(), inventory)),
+            Err(e) => eprintln!(
+                "Warning: cannot list the chunks of '{}': {:#}",
+                variable_label(var),
+                e
+            ),
+        }
+    }
+
+    match format {
+        ReportFormat::Text => print!("{}", chunks::render_table(&rows)),
+        ReportFormat::Json => {
+            let document: serde_json::Map<String, serde_json::Value> = rows
+                .iter()
+                .map(|(name, inventory)| (name.clone(), chunks::to_json(inventory)))
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&document)?);
+        }
+    }
+    Ok(())
+}
+
+/// `verify`: check every chunk, failing on missing (with `--require-all-chunks`), orphaned,
+/// corrupt or wrong-size chunks
+fn run_verify(
+    store: &ZarrStore,
+    metadata: &ZarrMetadata,
+    variables: Option<&str>,
+    require_all_chunks: bool,
+    format: ReportFormat,
+) -> anyhow::Result<()> {
+    let mut rows = Vec::new();
+    for path in select_variable_paths(metadata, variables)? {
+        let var = &metadata.variables[&path];
+        let other_nodes: Vec<&str> = metadata
+            .variables
+            .keys()
+            .chain(metadata.groups.keys())
+            .map(String::as_str)
+            .filter(|node| !node.is_empty() && *node != path)
+            .collect();
+        // Arrays zarrs cannot open (e.g. unsupported codecs) cannot be checked
+        match store.verify_array(var, &other_nodes) {
+            Ok(verification) => rows.push((variable_label(var).to_string(), verification)),
+            Err(e) => eprintln!("Warning: cannot verify '{}': {:#}", variable_label(var), e),
+        }
+    }
+
+    match format {
+        ReportFormat::Text => print!("{}", verify::render_text(&rows, require_all_chunks)),
+        ReportFormat::Json => {
+            let document: serde_json::Map<String, serde_json::Value> = rows
+                .iter()
+                .map(|(name, verification)| (name.clone(), verify::to_json(verification)))
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&document)?);
+        }
+    }
+    let failed = rows.iter().any(|(_, verification)| {
+        verification.has_errors() || (require_all_chunks && !verification.missing.is_empty())
+    });
+    if failed {
+        return Err(anyhow::anyhow!("Store verification failed"));
+    }
+    Ok(())
+}
+
+/// Options of the `diff` subcommand
+struct DiffOptions<'a> {
+    /// Compare the data values too (`--data`), with this tolerance
+    data_tolerance: Option<diff::Tolerance>,
+    variables: Option<&'a str>,
+    format: ReportFormat,
+    machine_output: bool,
+}
+
+/// `diff`: compare the metadata, and with `--data` the values, of two stores
+async fn run_diff(
+    store: &ZarrStore,
+    metadata: &ZarrMetadata,
+    other: &Path,
+    options: &DiffOptions<'_>,
+) -> anyhow::Result<()> {
+    validate_store_path(other)?;
+    if !options.machine_output {
+        println!("Opening Zarr store: {}", other.display());
+    }
+    let other_store = ZarrStore::new(other)?.quiet(options.machine_output);
+    let other_metadata = other_store
         .load_metadata()
         .await
-        .with_context(|| format!("Failed to load Zarr store from '{}'", path.display()))?;
+        .with_context(|| format!("Failed to load Zarr store from '{}'", other.display()))?;
+
+    let mut differences = diff::diff_metadata(metadata, &other_metadata);
+    if let Some(tolerance) = options.data_tolerance {
+        for path in select_variable_paths(metadata, options.variables)? {
+            let Some(other_var) = other_metadata.variables.get(&path) else {
+                continue;
+            };
+            let var = &metadata.variables[&path];
+            // Arrays zarrs cannot open (e.g. unsupported codecs) cannot be compared
+            match diff::diff_data(&path, store, var, &other_store, other_var, tolerance) {
+                Ok(difference) => differences.extend(difference),
+                Err(e) => eprintln!(
+                    "Warning: cannot compare the data of '{}': {:#}",
+                    variable_label(var),
+                    e
+                ),
+            }
+        }
+        diff::sort_differences(&mut differences);
+    }
+
+    match options.format {
+        ReportFormat::Text => print!("{}", diff::render_text(&differences)),
+        ReportFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&diff::to_json(&differences))?
+        ),
+    }
+    if !differences.is_empty() {
+        return Err(anyhow::anyhow!("Stores differ"));
+    }
+    Ok(())
+}
+
+/// `export`: write the selected variables to a netCDF file, or a single one to csv/npy/raw
+fn run_export(
+    store: &ZarrStore,
+    metadata: &ZarrMetadata,
+    to: ExportFormat,
+    output: &Path,
+    variables: Option<&str>,
+    isel: &[String],
+) -> anyhow::Result<()> {
+    let isel = hyperslab::parse_isel(isel)?;
+    let array_format = match to {
+        ExportFormat::Netcdf => None,
+        ExportFormat::Csv => Some(export::ArrayFormat::Csv),
+        ExportFormat::Npy => Some(export::ArrayFormat::Npy),
+        ExportFormat::Raw => Some(export::ArrayFormat::Raw),
+    };
+    let selected = select_variable_paths(metadata, variables)?;
+
+    if let Some(format) = array_format {
+        let format_name = to.to_possible_value().map(|v| v.get_name().to_string());
+        let (Some(_), [path]) = (variables, selected.as_slice()) else {
+            return Err(anyhow::anyhow!(
+                "--to {} exports a single variable: select it with -v (a full path if its name is ambiguous)",
+                format_name.unwrap_or_default()
+            ));
+        };
+        let description = export::export_array(metadata, store, path, &isel, format, output)?;
+        println!(
+            "Wrote {} to {} ({})",
+            variable_label(&metadata.variables[path]),
+            output.display(),
+            description
+        );
+        return Ok(());
+    }
+
+    // Selected variables bring their coordinate, bounds and grid mapping variables along
+    let paths: Vec<String> = if variables.is_some() {
+        cf::with_related_variables(metadata, selected)
+            .into_iter()
+            .collect()
+    } else {
+        selected
+    };
+    let count = export::export_netcdf(metadata, store, &paths, &isel, output)?;
+    println!("Wrote {} variable(s) to {}", count, output.display());
+    Ok(())
+}
+
+/// `--plot`: show a 2D slice of a variable, with navigation through the other dimensions
+fn run_plot(
+    args: &Args,
+    store: &ZarrStore,
+    metadata: &ZarrMetadata,
+    plot_var: &str,
+) -> anyhow::Result<()> {
+    if args.coordinate_data {
+        return Err(anyhow::anyhow!(
+            "--coordinate-data (-c) is not supported with --plot. Run the commands separately."
+        ));
+    }
+    if args.format != OutputFormat::Text {
+        return Err(anyhow::anyhow!(
+            "--format is not supported with --plot. Run the commands separately."
+        ));
+    }
+
+    let plot_dims = args
+        .plot_dims
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("--plot-dims is required when using --plot"))?;
+    let (dim_y, dim_x) = plot::parse_plot_dims(plot_dims)?;
+    let slices = plot::parse_slices(&args.slice)?;
+
+    let var_key = normalize_plot_variable_key(plot_var);
+    let variable = metadata.variables.get(&var_key).ok_or_else(|| {
+        let mut keys: Vec<&String> = metadata.variables.keys().collect();
+        keys.sort();
+        let shown = keys.len().min(20);
+        let preview = keys
+            .into_iter()
+            .take(shown)
+            .map(|k| if k.is_empty() { "root" } else { k.as_str() })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        anyhow::anyhow!(
+            "Variable '{}' not found in store. Available variables (first {}): {}",
+            plot_var,
+            shown,
+            preview
+        )
+    })?;
+
+    let selection = plot::build_plot_selection(variable, &dim_y, &dim_x, &slices)?;
+    let mut data = store
+        .read_array_subset_f64(variable, &selection.ranges)
+        .with_context(|| format!("Failed to read data for variable '{}'", plot_var))?;
+    // Fill/missing values are drawn as masked (non-finite) pixels
+    cf::mask_missing_values(variable, &mut data);
+
+    let title_base = format!(
+        "{}: {},{}",
+        if var_key.is_empty() { "root" } else { plot_var },
+        selection.dim_y_name,
+        selection.dim_x_name
+    );
+
+    let mut nav_dims = Vec::new();
+    for (i, dim) in variable.dimensions.iter().enumerate() {
+        if dim.name == dim_y || dim.name == dim_x {
+            continue;
+        }
+
+        let idx = *slices
+            .get(&dim.name)
+            .expect("slice indices should be validated by build_plot_selection");
+        let size = variable.shape[i];
+        if size == 0 {
+            return Err(anyhow::anyhow!(
+                "Dimension '{}' has length 0 in variable '{}' (cannot navigate).",
+                dim.name,
+                variable.name
+            ));
+        }
+
+        nav_dims.push(visualize::SliceDimension {
+            name: dim.name.clone(),
+            index: idx,
+            max: size - 1,
+        });
+    }
+
+    let view = visualize::ImageView {
+        width: selection.width,
+        height: selection.height,
+        stride_y: selection.stride_y,
+        stride_x: selection.stride_x,
+    };
+
+    if nav_dims.is_empty() {
+        visualize::show_viridis_image(&title_base, &data, view)?;
+    } else {
+        println!(
+            "Navigation: Tab=next dim, ←/→ or ↑/↓=±1 (Shift=×10), PgUp/PgDn=±10 (Shift=×100), Home/End=min/max, Esc/q=quit"
+        );
+        visualize::show_viridis_image_with_navigation(&title_base, data, view, nav_dims, |dims| {
+            let slices: std::collections::HashMap<String, u64> =
+                dims.iter().map(|d| (d.name.clone(), d.index)).collect();
+            let selection = plot::build_plot_selection(variable, &dim_y, &dim_x, &slices)?;
+            let mut data = store.read_array_subset_f64(variable, &selection.ranges)?;
+            cf::mask_missing_values(variable, &mut data);
+            Ok(data)
+        })?;
+    }
+    Ok(())
+}
+
+/// Default mode: dump the metadata as text, CDL or JSON
+async fn run_dump(
+    args: &Args,
+    store: &ZarrStore,
+    path: &Path,
+    mut metadata: ZarrMetadata,
+) -> anyhow::Result<()> {
+    // Warn before the dump, which shows the (possibly stale) consolidated metadata
+    let consolidated_issues = if args.verify_consolidated {
+        match lint::lint_store(store)? {
+            Some(issues) => issues,
+            None => {
+                eprintln!("Warning: the store has no consolidated metadata to verify");
+                Vec::new()
+            }
+        }
+    } else {
+        Vec::new()
+    };
+    for issue in &consolidated_issues {
+        eprintln!(
+            "Warning: {}: [{}] {}",
+            issue.path,
+            issue.code.as_str(),
+            issue.message
+        );
+    }
+
+    filter_metadata(
+        &mut metadata,
+        args.variables.as_deref(),
+        args.group.as_deref(),
+    )?;
+    match args.format {
+        OutputFormat::Text if args.cdl => {
+            let coordinate_data = if args.coordinate_data {
+                Some(read_coordinate_values(&metadata, store).await)
+            } else {
+                None
+            };
+            let writer = cdl::CdlWriter::new(&metadata, coordinate_data.as_ref(), args.special);
+            print!("{}", writer.render(&dataset_name(path)));
+        }
+        OutputFormat::Text => {
+            let options = DumpOptions {
+                coordinate_data: args.coordinate_data,
+                shards: args.shards,
+                special: args.special,
+            };
+            print_metadata_summary(&metadata, args.no_color, &options, store).await?
+        }
+        OutputFormat::Json => {
+            print_metadata_json(&metadata, args.coordinate_data, args.shards, store).await?
+        }
+    }
+
+    if !consolidated_issues.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Consolidated metadata does not match the store ({} issue(s)); run `zarr-dump consolidate` to rewrite it",
+            consolidated_issues.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Name of a variable in `data` output: its path, so variables of the same name in different
+/// groups can be told apart (`root` for an array at the store root)
+fn data_label(var: &metadata::Variable) -> &str {
+    if var.path.is_empty() {
+        variable_label(var)
+    } else {
+        &var.path
+    }
+}
+
+/// Read the values of a hyperslab formatted for `data`: integer types as exact integers (64-bit
+/// ones are not read through f64), floats with `precision` significant digits if given
+fn read_formatted_values(
+    store: &ZarrStore,
+    var: &metadata::Variable,
+    slab: &hyperslab::Hyperslab,
+    precision: Option<usize>,
+) -> anyhow::Result<Vec<String>> {
+    use cdl::CdlType;
+
+    let missing_integers = cf::collect_missing_values_i128(var);
+    let integer = |value: i128| {
+        if missing_integers.contains(&value) {
+            "_".to_string()
+        } else {
+            value.to_string()
+        }
+    };
+    let mut values = Vec::new();
+    match CdlType::from_dtype(&var.dtype) {
+        Some(CdlType::Int64) => store.read_hyperslab_batches_as(var, slab, |data: &[i64]| {
+            values.extend(data.iter().map(|&v| integer(i128::from(v))));
+            Ok(())
+        })?,
+        Some(CdlType::UInt64) => store.read_hyperslab_batches_as(var, slab, |data: &[u64]| {
+            values.extend(data.iter().map(|&v| integer(i128::from(v))));
+            Ok(())
+        })?,
+        // Smaller integers are exact in f64
+        Some(
+            CdlType::Byte
+            | CdlType::UByte
+            | CdlType::Short
+            | CdlType::UShort
+            | CdlType::Int
+            | CdlType::UInt,
+        ) => {
+            let data = store.read_hyperslab_f64(var, slab)?;
+            values.extend(data.iter().map(|&v| integer(v as i128)));
+        }
+        _ => {
+            let missing_values = cf::collect_missing_values_f64(var);
+            let data = store.read_hyperslab_f64(var, slab)?;
+            values.extend(
+                data.iter()
+                    .map(|&v| format_float_value(v, &missing_values, precision)),
+            );
+        }
+    }
+    Ok(values)
+}
+
+/// A float as printed by ncdump-like output: `_` for fill/missing values, integral values
+/// without a decimal point, and others with `precision` significant digits if given
+fn format_float_value(value: f64, missing_values: &[f64], precision: Option<usize>) -> String {
+    if cf::is_missing_value(value, missing_values) {
+        "_".to_string()
+    } else if value.fract() == 0.0 && value.abs() < 1e10 {
+        format!("{}", value as i64)
+    } else if let Some(digits) = precision {
+        format::format_significant(value, digits)
+    } else if value.abs() >= 1e6 || (value.abs() < 1e-3 && value != 0.0) {
+        format!("{:e}", value)
+    } else {
+        format!("{}", value)
+    }
+}
+
+fn normalize_plot_variable_key(raw: &str) -> String {
+    let s = raw.trim();
+    if s == "/" || s.eq_ignore_ascii_case("root") {
+        return String::new();
+    }
+    s.trim_start_matches('/').to_string()
+}
 
-    if mode == "cf-check" {
-        let report = cf::cf_check(&store, &metadata).await?;
-        report.print();
-        if report.has_errors() {
-            return Err(anyhow::anyhow!("CF check failed"));
-        }
-        return Ok(());
+/// Name of the store for the `netcdf name {` line of CDL output, e.g. `era5` for `/data/era5.zarr`
+fn dataset_name(path: &std::path::Path) -> String {
+    let location = path.to_string_lossy();
+    let last = location
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or_default();
+    let stem = last
+        .strip_suffix(".zarr")
+        .or_else(|| last.strip_suffix(".zip"))
+        .or_else(|| last.strip_suffix(".json"))
+        .unwrap_or(last);
+    if stem.is_empty() || stem == "." {
+        "store".to_string()
+    } else {
+        stem.to_string()
     }
+}
 
-    if let Some(plot_var) = &args.plot {
-        if args.command.is_some() {
+/// Restrict the dump to the group subtree selected with `-g` and the variables selected with `-v`
+fn filter_metadata(
+    metadata: &mut ZarrMetadata,
+    variables: Option<&str>,
+    group: Option<&str>,
+) -> anyhow::Result<()> {
+    if let Some(group) = group.map(|g| g.trim_matches('/')).filter(|g| !g.is_empty()) {
+        if !metadata.groups.contains_key(group) {
+            let mut groups: Vec<&str> = metadata.groups.keys().map(String::as_str).collect();
+            groups.sort();
             return Err(anyhow::anyhow!(
-                "Plotting is only supported in the default mode. Use `zarr-dump STORE --plot ...`, not a subcommand."
+                "Group '{}' not found in store. Available groups: {}",
+                group,
+                if groups.is_empty() {
+                    "(none)".to_string()
+                } else {
+                    groups.join(", ")
+                }
             ));
         }
-        if args.coordinate_data {
-            return Err(anyhow::anyhow!(
-                "--coordinate-data (-c) is not supported with --plot. Run the commands separately."
-            ));
+
+        let prefix = format!("{}/", group);
+        let in_subtree = |path: &str| path == group || path.starts_with(&prefix);
+        // Ancestor groups are kept so the subtree is still printed at its place in the hierarchy,
+        // but only attributes inside the subtree are shown
+        metadata
+            .groups
+            .retain(|path, _| in_subtree(path) || group.starts_with(&format!("{}/", path)));
+        for (path, g) in metadata.groups.iter_mut() {
+            if !in_subtree(path) {
+                g.attributes.clear();
+            }
         }
+        metadata.global_attributes.clear();
+        metadata.root_group.attributes.clear();
+        metadata.retain_variables(|v| in_subtree(&v.path));
+    }
 
-        let plot_dims = args
-            .plot_dims
-            .as_deref()
-            .ok_or_else(|| anyhow::anyhow!("--plot-dims is required when using --plot"))?;
-        let (dim_y, dim_x) = plot::parse_plot_dims(plot_dims)?;
-        let slices = plot::parse_slices(&args.slice)?;
+    if variables.is_some() {
+        let selected = select_variable_paths(metadata, variables)?;
+        let keep = cf::with_related_variables(metadata, selected);
+        metadata.retain_variables(|v| keep.contains(&v.path));
+    }
 
-        let var_key = normalize_plot_variable_key(plot_var);
-        let variable = metadata.variables.get(&var_key).ok_or_else(|| {
-            let mut keys: Vec<&String> = metadata.variables.keys().collect();
-            keys.sort();
-            let shown = keys.len().min(20);
-            let preview = keys
-                .into_iter()
-                .take(shown)
-                .map(|k| if k.is_empty() { "root" } else { k.as_str() })
-                .collect::<Vec<_>>()
-                .join(", ");
+    Ok(())
+}
 
-            anyhow::anyhow!(
-                "Variable '{}' not found in store. Available variables (first {}): {}",
-                plot_var,
-                shown,
-                preview
-            )
-        })?;
+/// Paths of the variables named in a comma separated `-v` list (by name or path, each once in
+/// the order first named), or of all variables in path order without `-v`
+fn select_variable_paths(
+    metadata: &ZarrMetadata,
+    variables: Option<&str>,
+) -> anyhow::Result<Vec<String>> {
+    let Some(variables) = variables else {
+        let mut paths: Vec<String> = metadata.variables.keys().cloned().collect();
+        paths.sort();
+        return Ok(paths);
+    };
+    let mut selected = Vec::new();
+    for name in variables
+        .split(',')
+        .map(|n| n.trim().trim_start_matches('/'))
+        .filter(|n| !n.is_empty())
+    {
+        let mut matches: Vec<String> = metadata
+            .variables
+            .values()
+            .filter(|v| v.path == name || v.name == name)
+            .map(|v| v.path.clone())
+            .collect();
+        if matches.is_empty() {
+            return Err(anyhow::anyhow!("Variable '{}' not found in store", name));
+        }
+        matches.sort();
+        // A variable named more than once (e.g. by name and by path) is selected once
+        for path in matches {
+            if !selected.contains(&path) {
+                selected.push(path);
+            }
+        }
+    }
+    Ok(selected)
+}
 
-        let selection = plot::build_plot_selection(variable, &dim_y, &dim_x, &slices)?;
-        let data = store
-            .read_array_subset_f64(variable, &selection.ranges)
-            .with_context(|| format!("Failed to read data for variable '{}'", plot_var))?;
+/// Path of the node whose attributes `attrs` edits: empty for the root group, a group path, or a
+/// variable name or path. Nodes are looked up in the per-node metadata files, since consolidated
+/// metadata may be stale.
+fn resolve_attribute_node(
+    store: &ZarrStore,
+    root: &Path,
+    zarr_format: u8,
+    node: &str,
+) -> anyhow::Result<String> {
+    if node.is_empty() || attrs::node_exists(root, node, zarr_format) {
+        return Ok(node.to_string());
+    }
+    match attrs::arrays_named(&store.scan_metadata_documents()?, node).as_slice() {
+        [] => Err(anyhow::anyhow!(
+            "No variable or group '{}' in '{}'",
+            node,
+            root.display()
+        )),
+        [path] => Ok(path.clone()),
+        paths => Err(anyhow::anyhow!(
+            "Variable name '{}' is ambiguous ({}): use its full path",
+            node,
+            paths.join(", ")
+        )),
+    }
+}
 
-        let title_base = format!(
-            "{}: {},{}",
-            if var_key.is_empty() { "root" } else { plot_var },
-            selection.dim_y_name,
-            selection.dim_x_name
-        );
+/// Hyperslab and layout options of the `data` subcommand
+struct DataOptions {
+    isel: HashMap<String, hyperslab::IndexSelection>,
+    sel: HashMap<String, hyperslab::ValueSelection>,
+    max_rows: Option<usize>,
+    max_cols: Option<usize>,
+    precision: Option<usize>,
+}
 
-        let mut nav_dims = Vec::new();
-        for (i, dim) in variable.dimensions.iter().enumerate() {
-            if dim.name == dim_y || dim.name == dim_x {
-                continue;
-            }
+/// What the text dump includes besides the metadata itself
+struct DumpOptions {
+    /// Coordinate variable values (`-c`)
+    coordinate_data: bool,
+    /// Shard inventory of sharded arrays (`--shards`)
+    shards: bool,
+    /// Special virtual attributes (`-s`)
+    special: bool,
+}
 
-            let idx = *slices
-                .get(&dim.name)
-                .expect("slice indices should be validated by build_plot_selection");
-            let size = variable.shape[i];
-            if size == 0 {
-                return Err(anyhow::anyhow!(
-                    "Dimension '{}' has length 0 in variable '{}' (cannot navigate).",
-                    dim.name,
-                    variable.name
-                ));
-            }
+async fn print_metadata_summary(
+    metadata: &ZarrMetadata,
+    no_color: bool,
+    options: &DumpOptions,
+    store: &ZarrStore,
+) -> anyhow::Result<()> {
+    let formatter = NetCdfFormatter::new(!no_color);
+    formatter.print_header(metadata, options, store).await?;
+    Ok(())
+}
 
-            nav_dims.push(visualize::SliceDimension {
-                name: dim.name.clone(),
-                index: idx,
-                max: size - 1,
-            });
-        }
+/// Virtual attributes describing how a variable is stored, shown by `-s` like ncdump's
+/// `_ChunkSizes`/`_Storage`/`_Endianness`
+fn special_attributes(variable: &metadata::Variable) -> HashMap<String, AttributeValue> {
+    let string = |s: &str| AttributeValue::String(s.to_string());
+    let mut attributes = HashMap::new();
 
-        let view = visualize::ImageView {
-            width: selection.width,
-            height: selection.height,
-            stride_y: selection.stride_y,
-            stride_x: selection.stride_x,
-        };
+    let storage = if variable.codecs.sharding().is_some() {
+        "sharded"
+    } else {
+        "chunked"
+    };
+    attributes.insert("_Storage".to_string(), string(storage));
+    attributes.insert(
+        "_ChunkSizes".to_string(),
+        AttributeValue::Array(
+            variable
+                .chunks
+                .iter()
+                .map(|&c| AttributeValue::Integer(c as i64))
+                .collect(),
+        ),
+    );
+    if let Some(endianness) = variable.endianness() {
+        attributes.insert("_Endianness".to_string(), string(endianness));
+    }
+    if let Some(compressor) = variable.codecs.compressor() {
+        attributes.insert("_Compressor".to_string(), string(&compressor.to_string()));
+    }
+    let filters = variable.codecs.filters();
+    if !filters.is_empty() {
+        let filters: Vec<String> = filters.iter().map(|f| f.to_string()).collect();
+        attributes.insert("_Filters".to_string(), string(&filters.join(", ")));
+    }
 
-        if nav_dims.is_empty() {
-            visualize::show_viridis_image(&title_base, &data, view)?;
-        } else {
-            println!(
-                "Navigation: Tab=next dim, ←/→ or ↑/↓=±1 (Shift=×10), PgUp/PgDn=±10 (Shift=×100), Home/End=min/max, Esc/q=quit"
-            );
-            visualize::show_viridis_image_with_navigation(
-                &title_base,
-                data,
-                view,
-                nav_dims,
-                |dims| {
-                    let slices: std::collections::HashMap<String, u64> =
-                        dims.iter().map(|d| (d.name.clone(), d.index)).collect();
-                    let selection = plot::build_plot_selection(variable, &dim_y, &dim_x, &slices)?;
-                    store.read_array_subset_f64(variable, &selection.ranges)
-                },
-            )?;
-        }
+    // Where the `_FillValue` shown in the dump comes from
+    let fill_value_source = if variable.attributes.contains_key("_FillValue") {
+        Some("attribute")
+    } else if variable
+        .fill_value
+        .as_ref()
+        .is_some_and(|v| !matches!(v, AttributeValue::Null))
+    {
+        Some("metadata")
     } else {
-        if args.command.is_some() {
-            return Err(anyhow::anyhow!(
-                "This subcommand does not support metadata dump output."
-            ));
-        }
-        print_metadata_summary(&metadata, args.no_color, args.coordinate_data, &store).await?;
+        None
+    };
+    if let Some(source) = fill_value_source {
+        attributes.insert("_FillValueSource".to_string(), string(source));
     }
 
-    Ok(())
+    attributes.insert("_Order".to_string(), string(&variable.order));
+    attributes.insert(
+        "_DimensionSeparator".to_string(),
+        string(&variable.dimension_separator),
+    );
+    attributes
 }
 
-fn normalize_plot_variable_key(raw: &str) -> String {
-    let s = raw.trim();
-    if s == "/" || s.eq_ignore_ascii_case("root") {
-        return String::new();
+/// Shard inventory of every sharded array, keyed by array path
+fn read_shard_inventories(
+    metadata: &ZarrMetadata,
+    store: &ZarrStore,
+) -> BTreeMap<String, anyhow::Result<ShardInventory>> {
+    metadata
+        .variables
+        .values()
+        .filter(|var| var.codecs.sharding().is_some())
+        .map(|var| (var.path.clone(), store.read_shard_inventory(var)))
+        .collect()
+}
+
+/// Values of the coordinate variables (1D variables along a known dimension), for `-c`
+async fn read_coordinate_values(
+    metadata: &ZarrMetadata,
+    store: &ZarrStore,
+) -> BTreeMap<String, anyhow::Result<Vec<f64>>> {
+    let mut values = BTreeMap::new();
+    for var in metadata.variables.values() {
+        if var.dimensions.len() == 1 && metadata.dimensions.contains_key(&var.dimensions[0].name) {
+            values.insert(var.path.clone(), store.read_coordinate_data(var).await);
+        }
     }
-    s.trim_start_matches('/').to_string()
+    values
 }
 
-async fn print_metadata_summary(
+/// Everything the nested CDL header printer needs besides the metadata
+struct HeaderContext<'a> {
+    metadata: &'a ZarrMetadata,
+    layout: GroupLayout,
+    shard_inventories: BTreeMap<String, anyhow::Result<ShardInventory>>,
+    coordinate_data: Option<BTreeMap<String, anyhow::Result<Vec<f64>>>>,
+    special: bool,
+}
+
+async fn print_metadata_json(
     metadata: &ZarrMetadata,
-    no_color: bool,
     coordinate_data: bool,
+    shards: bool,
     store: &ZarrStore,
 ) -> anyhow::Result<()> {
-    let formatter = NetCdfFormatter::new(!no_color);
-    formatter
-        .print_header(metadata, coordinate_data, store)
-        .await?;
+    let summary = cf::cf_summary(metadata);
+    let mut document = json::MetadataDocument::new(metadata, &summary);
+
+    if coordinate_data {
+        let mut values = std::collections::BTreeMap::new();
+        for var in metadata.variables.values() {
+            if var.dimensions.len() == 1
+                && metadata.dimensions.contains_key(&var.dimensions[0].name)
+            {
+                let data = store.read_coordinate_data(var).await.map(|mut data| {
+                    cf::mask_missing_values(var, &mut data);
+                    data
+                });
+                values.insert(var.path.clone(), json::CoordinateValues::from_result(data));
+            }
+        }
+        document.coordinate_data = Some(values);
+    }
+
+    if shards {
+        document.shard_inventory = Some(
+            read_shard_inventories(metadata, store)
+                .into_iter()
+                .map(|(path, inventory)| (path, json::ShardInventoryEntry::from_result(inventory)))
+                .collect(),
+        );
+    }
+
+    let out =
+        serde_json::to_string_pretty(&document).context("Failed to serialize metadata as JSON")?;
+    println!("{}", out);
     Ok(())
 }
 
@@ -262,7 +1555,7 @@ impl NetCdfFormatter {
     async fn print_header(
         &self,
         metadata: &ZarrMetadata,
-        coordinate_data: bool,
+        options: &DumpOptions,
         store: &ZarrStore,
     ) -> anyhow::Result<()> {
         let store_name = "store"; // Could be extracted from path if needed
@@ -274,49 +1567,111 @@ impl NetCdfFormatter {
             self.colorize(store_name, "1")  // Bold for store name
         );
 
-        // Dimensions section
-        self.print_dimensions(metadata);
-
-        // Variables section
-        self.print_variables(metadata);
+        let shard_inventories = if options.shards {
+            read_shard_inventories(metadata, store)
+        } else {
+            BTreeMap::new()
+        };
+        let coordinate_data = if options.coordinate_data {
+            Some(read_coordinate_values(metadata, store).await)
+        } else {
+            None
+        };
+        let context = HeaderContext {
+            metadata,
+            layout: GroupLayout::new(metadata),
+            shard_inventories,
+            coordinate_data,
+            special: options.special,
+        };
 
-        // Global attributes section
-        self.print_global_attributes(metadata);
+        // The root group, with nested `group: name { ... }` blocks for its subgroups
+        self.print_group_contents(&context, "", "");
 
         // CF summary section
         self.print_cf_summary(metadata);
 
-        // Coordinate data section (if requested)
-        if coordinate_data {
-            self.print_coordinate_data(metadata, store).await?;
-        }
-
         // Closing brace
         println!("}}");
         Ok(())
     }
 
-    fn print_dimensions(&self, metadata: &ZarrMetadata) {
-        if metadata.dimensions.is_empty() {
-            return;
+    /// Print the dimensions, variables, attributes and (with `-c`) coordinate data defined in the
+    /// group at `path`, followed by its subgroups, like ncdump does for netCDF-4 files
+    fn print_group_contents(&self, context: &HeaderContext, path: &str, indent: &str) {
+        let metadata = context.metadata;
+
+        // Dimensions section
+        self.print_dimensions(metadata, context.layout.dimensions(path), indent);
+
+        // Variables section
+        let variables = context.layout.variables(metadata, path);
+        self.print_variables(
+            &variables,
+            &context.shard_inventories,
+            context.special,
+            indent,
+        );
+
+        // Global (root) or group attributes section
+        if path.is_empty() {
+            self.print_global_attributes(metadata, context.special);
+        } else if let Some(group) = metadata.groups.get(path)
+            && !group.attributes.is_empty()
+        {
+            println!(
+                "{}{}",
+                indent,
+                self.colorize("// group attributes:", "90") // Gray for comments
+            );
+            self.print_attributes(&group.attributes, &format!("{}    ", indent));
+        }
+
+        // Coordinate data section (if requested)
+        if let Some(coordinate_data) = &context.coordinate_data {
+            self.print_coordinate_data(&variables, coordinate_data, indent);
+        }
+
+        // Subgroups
+        for child in context.layout.subgroups(path) {
+            let name = child.rsplit('/').next().unwrap_or(child);
+            println!();
+            println!(
+                "{}{} {} {{",
+                indent,
+                self.colorize("group:", "32"), // Green for section headers
+                self.colorize(name, "1")       // Bold for group name
+            );
+            self.print_group_contents(context, child, &format!("{}    ", indent));
+            println!(
+                "{}}} {}",
+                indent,
+                self.colorize(&format!("// group {}", name), "90")
+            );
         }
+    }
 
-        println!("{}", self.colorize("dimensions:", "32")); // Green for section headers
+    fn print_dimensions(&self, metadata: &ZarrMetadata, names: &[String], indent: &str) {
+        if names.is_empty() {
+            return;
+        }
 
-        let mut sorted_dims: Vec<_> = metadata.dimensions.iter().collect();
-        sorted_dims.sort_by_key(|(name, _)| name.as_str());
+        println!("{}{}", indent, self.colorize("dimensions:", "32")); // Green for section headers
 
-        for (name, dim_info) in sorted_dims {
+        for name in names {
+            let dim_info = &metadata.dimensions[name];
             if dim_info.is_unlimited {
                 println!(
-                    "    {} = {} ; {}",
+                    "{}    {} = {} ; {}",
+                    indent,
                     self.colorize(name, "36"), // Cyan for dimension names
                     self.colorize("UNLIMITED", "33"), // Yellow for UNLIMITED
                     self.colorize(&format!("// ({} currently)", dim_info.max_length), "90") // Gray for comments
                 );
             } else {
                 println!(
-                    "    {} = {} ;",
+                    "{}    {} = {} ;",
+                    indent,
                     self.colorize(name, "36"), // Cyan for dimension names
                     self.colorize(&format!("{}", dim_info.max_length), "33")  // Yellow for values
                 );
@@ -324,34 +1679,118 @@ impl NetCdfFormatter {
         }
     }
 
-    fn print_variables(&self, metadata: &ZarrMetadata) {
-        if metadata.variables.is_empty() {
+    fn print_variables(
+        &self,
+        variables: &[&metadata::Variable],
+        shard_inventories: &BTreeMap<String, anyhow::Result<ShardInventory>>,
+        special: bool,
+        indent: &str,
+    ) {
+        if variables.is_empty() {
             return;
         }
 
-        println!("{}", self.colorize("variables:", "32")); // Green for section headers
-
-        let mut sorted_vars: Vec<_> = metadata.variables.iter().collect();
-        sorted_vars.sort_by_key(|(path, _)| path.as_str());
+        println!("{}{}", indent, self.colorize("variables:", "32")); // Green for section headers
 
-        for (path, variable) in sorted_vars.iter() {
+        let attribute_indent = format!("{}        ", indent);
+        for variable in variables {
             // Variable declaration line
-            let var_name = if path.is_empty() {
-                "root"
-            } else {
-                &variable.name
-            };
+            let var_name = variable_label(variable);
             let dims_str = self.format_variable_dimensions(&variable.dimensions);
 
             println!(
-                "    {} {}({}) ;",
+                "{}    {} {}({}) ;",
+                indent,
                 self.colorize(&self.map_dtype_to_netcdf(&variable.dtype), "35"), // Magenta for data types
                 self.colorize(var_name, "36"), // Cyan for variable names
                 dims_str
             );
 
-            // Variable attributes
-            self.print_variable_attributes(var_name, &variable.attributes);
+            // Variable attributes, with the array fill value shown as `_FillValue` like netCDF
+            // (an explicit `_FillValue` attribute takes precedence)
+            let mut attributes = variable.attributes.clone();
+            if let Some(fill_value) = &variable.fill_value
+                && !matches!(fill_value, AttributeValue::Null)
+            {
+                attributes
+                    .entry("_FillValue".to_string())
+                    .or_insert_with(|| fill_value.clone());
+            }
+            // The codec pipeline, like the `_Codecs` special attribute of NCZarr
+            if !variable.codecs.is_empty() {
+                attributes.insert(
+                    "_Codecs".to_string(),
+                    AttributeValue::String(variable.codecs.to_string()),
+                );
+            }
+            self.print_variable_attributes(var_name, &attributes, &attribute_indent);
+            if special {
+                self.print_variable_attributes(
+                    var_name,
+                    &special_attributes(variable),
+                    &attribute_indent,
+                );
+            }
+
+            if let Some(sharding) = variable.codecs.sharding() {
+                self.print_sharding(
+                    sharding,
+                    shard_inventories.get(&variable.path),
+                    &attribute_indent,
+                );
+            }
+        }
+    }
+
+    /// Print the shard layout of a sharded array (and its shard inventory, if read) as comments
+    fn print_sharding(
+        &self,
+        sharding: &ShardingInfo,
+        inventory: Option<&anyhow::Result<ShardInventory>>,
+        indent: &str,
+    ) {
+        let codec_names = |codecs: &[Codec]| {
+            codecs
+                .iter()
+                .map(|c| c.name())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut lines = vec![format!(
+            "// sharding: inner chunks {:?}, codecs [{}], index codecs [{}] at {}",
+            sharding.inner_chunk_shape,
+            codec_names(&sharding.codecs),
+            codec_names(&sharding.index_codecs),
+            sharding.index_location
+        )];
+
+        match inventory {
+            Some(Ok(inventory)) => {
+                let populated: u64 = inventory
+                    .shards
+                    .iter()
+                    .map(|s| s.populated_inner_chunks)
+                    .sum();
+                lines.push(format!(
+                    "// shards: {} of {} present, {} of {} inner chunks populated",
+                    inventory.shards.len(),
+                    inventory.total_shards,
+                    populated,
+                    inventory.total_shards * inventory.inner_chunks_per_shard
+                ));
+                for shard in &inventory.shards {
+                    lines.push(format!(
+                        "//   {}: {} of {} inner chunks",
+                        shard.key, shard.populated_inner_chunks, inventory.inner_chunks_per_shard
+                    ));
+                }
+            }
+            Some(Err(e)) => lines.push(format!("// shards: <error reading shard index: {}>", e)),
+            None => {}
+        }
+
+        for line in lines {
+            println!("{}{}", indent, self.colorize(&line, "90")); // Gray for comments
         }
     }
 
@@ -359,14 +1798,25 @@ impl NetCdfFormatter {
         &self,
         var_name: &str,
         attributes: &std::collections::HashMap<String, AttributeValue>,
+        indent: &str,
     ) {
         let mut sorted_attrs: Vec<_> = attributes.iter().collect();
         sorted_attrs.sort_by_key(|(key, _)| key.as_str());
 
         for (key, value) in sorted_attrs {
-            let formatted_value = self.format_attribute_value(value);
+            let formatted_value = match value {
+                // Non-finite fill values are written unquoted, as ncdump does
+                AttributeValue::String(s)
+                    if key == "_FillValue"
+                        && matches!(s.as_str(), "NaN" | "Infinity" | "-Infinity") =>
+                {
+                    self.colorize(s, "33")
+                }
+                _ => self.format_attribute_value(value),
+            };
             println!(
-                "        {}:{} = {} ;",
+                "{}{}:{} = {} ;",
+                indent,
                 self.colorize(var_name, "36"), // Cyan for variable name
                 self.colorize(key, "33"),      // Yellow for attribute name
                 formatted_value
@@ -374,22 +1824,38 @@ impl NetCdfFormatter {
         }
     }
 
-    fn print_global_attributes(&self, metadata: &ZarrMetadata) {
-        if metadata.global_attributes.is_empty() {
+    fn print_global_attributes(&self, metadata: &ZarrMetadata, special: bool) {
+        if metadata.global_attributes.is_empty() && !special {
             return;
         }
 
         println!("{}", self.colorize("// global attributes:", "90")); // Gray for comments
+        self.print_attributes(&metadata.global_attributes, "    ");
+
+        if special {
+            let format = AttributeValue::String(format!("zarr v{}", metadata.zarr_format));
+            let consolidated = AttributeValue::Boolean(metadata.consolidated);
+            for (key, value) in [("_Format", format), ("_Consolidated", consolidated)] {
+                println!(
+                    "    :{} = {} ;",
+                    self.colorize(key, "33"),
+                    self.format_attribute_value(&value)
+                );
+            }
+        }
+    }
 
-        let mut sorted_attrs: Vec<_> = metadata.global_attributes.iter().collect();
+    /// Print group or global attributes as `:name = value ;` lines
+    fn print_attributes(&self, attributes: &HashMap<String, AttributeValue>, indent: &str) {
+        let mut sorted_attrs: Vec<_> = attributes.iter().collect();
         sorted_attrs.sort_by_key(|(key, _)| key.as_str());
 
         for (key, value) in sorted_attrs {
-            let formatted_value = self.format_attribute_value(value);
             println!(
-                "    :{} = {} ;",
+                "{}:{} = {} ;",
+                indent,
                 self.colorize(key, "33"), // Yellow for attribute name
-                formatted_value
+                self.format_attribute_value(value)
             );
         }
     }
@@ -474,6 +1940,9 @@ impl NetCdfFormatter {
             AttributeValue::Integer(i) => {
                 self.colorize(&format!("{}", i), "33") // Yellow for numbers
             }
+            AttributeValue::UnsignedInteger(i) => {
+                self.colorize(&format!("{}", i), "33") // Yellow for numbers
+            }
             AttributeValue::Boolean(b) => {
                 self.colorize(&format!("{}", b), "35") // Magenta for booleans
             }
@@ -520,52 +1989,261 @@ impl NetCdfFormatter {
         }
     }
 
-    async fn print_coordinate_data(
+    /// Print the `data:` section with the values of the coordinate variables among `variables`
+    fn print_coordinate_data(
         &self,
-        metadata: &ZarrMetadata,
-        store: &ZarrStore,
-    ) -> anyhow::Result<()> {
-        // Identify coordinate variables (1D variables that match dimension names)
-        let coordinate_vars: Vec<(&String, &metadata::Variable)> = metadata
-            .variables
+        variables: &[&metadata::Variable],
+        coordinate_data: &BTreeMap<String, anyhow::Result<Vec<f64>>>,
+        indent: &str,
+    ) {
+        let values: Vec<(&metadata::Variable, &anyhow::Result<Vec<f64>>)> = variables
             .iter()
-            .filter(|(_, var)| {
-                // A coordinate variable is typically 1D and its name matches a dimension
-                var.dimensions.len() == 1
-                    && metadata.dimensions.contains_key(&var.dimensions[0].name)
-            })
+            .filter_map(|var| coordinate_data.get(&var.path).map(|data| (*var, data)))
             .collect();
-
-        if coordinate_vars.is_empty() {
-            return Ok(());
+        if values.is_empty() {
+            return;
         }
 
-        println!("{}", self.colorize("data:", "32")); // Green for section header
         println!();
+        println!("{}{}", indent, self.colorize("data:", "32")); // Green for section header
 
-        // Read and display coordinate data
-        for (_, var) in coordinate_vars {
-            match store.read_coordinate_data(var).await {
+        for (var, data) in values {
+            println!();
+            match data {
                 Ok(data) => {
-                    let formatted_data = self.format_coordinate_values(&data);
-                    println!(" {} = {} ;", self.colorize(&var.name, "36"), formatted_data);
-                    println!(); // Add blank line between variables
+                    let missing_values = cf::collect_missing_values_f64(var);
+                    let formatted_data = self.format_coordinate_values(data, &missing_values, None);
+                    println!(
+                        "{} {} = {} ;",
+                        indent,
+                        self.colorize(&var.name, "36"),
+                        formatted_data
+                    );
                 }
                 Err(e) => {
                     // If we can't read the data, show an error message but continue
                     println!(
-                        " {} = {} ;",
+                        "{} {} = {} ;",
+                        indent,
                         self.colorize(&var.name, "36"),
                         self.colorize(&format!("<error reading data: {}>", e), "31")
                     );
                 }
             }
         }
+    }
+
+    /// Print the values of the selected variables (all variables if `variables` is `None`) as a
+    /// CDL data section, restricted to the `--isel`/`--sel` hyperslab and the row/column limits
+    async fn print_data(
+        &self,
+        metadata: &ZarrMetadata,
+        variables: Option<&str>,
+        options: &DataOptions,
+        store: &ZarrStore,
+    ) -> anyhow::Result<()> {
+        let paths = select_variable_paths(metadata, variables)?;
+
+        // Catch misspelled dimensions instead of silently printing everything
+        for dim in options.isel.keys().chain(options.sel.keys()) {
+            if options.isel.contains_key(dim) && options.sel.contains_key(dim) {
+                return Err(anyhow::anyhow!(
+                    "Dimension '{}' is selected with both --isel and --sel",
+                    dim
+                ));
+            }
+            let used = paths.iter().any(|path| {
+                metadata.variables[path]
+                    .dimensions
+                    .iter()
+                    .any(|d| &d.name == dim)
+            });
+            if !used {
+                return Err(anyhow::anyhow!(
+                    "Dimension '{}' is not used by any of the printed variables",
+                    dim
+                ));
+            }
+        }
+
+        println!(
+            "{} {} {{",
+            self.colorize("zarr", "34"),
+            self.colorize("store", "1")
+        );
+        println!("{}", self.colorize("data:", "32"));
+        println!();
+
+        let mut coordinates: HashMap<String, Vec<f64>> = HashMap::new();
+        for path in &paths {
+            let var = &metadata.variables[path];
+            let slab = self
+                .resolve_hyperslab(metadata, var, options, store, &mut coordinates)
+                .await?;
+
+            // Only read what will be printed: the first columns, and the leading indices that
+            // hold the first rows
+            let shape = slab.shape();
+            let mut read_slab = slab.clone();
+            if let Some(last) = read_slab.dims.last_mut()
+                && let Some(max_cols) = options.max_cols
+            {
+                *last = last.truncated(max_cols as u64);
+            }
+            if shape.len() >= 2
+                && let Some(max_rows) = options.max_rows
+            {
+                let rows_per_index: u64 = shape[1..shape.len() - 1].iter().product();
+                let needed = (max_rows as u64).div_ceil(rows_per_index.max(1));
+                read_slab.dims[0] = read_slab.dims[0].truncated(needed);
+            }
+
+            let data = if read_slab.shape().contains(&0) {
+                Ok(Vec::new())
+            } else {
+                read_formatted_values(store, var, &read_slab, options.precision)
+            };
+
+            if !slab.is_full(&var.shape) {
+                let dims: Vec<String> = var
+                    .dimensions
+                    .iter()
+                    .zip(&slab.dims)
+                    .map(|(dim, range)| format!("{}={}", dim.name, range))
+                    .collect();
+                println!(
+                    " {}",
+                    self.colorize(
+                        &format!("// {}({})", data_label(var), dims.join(", ")),
+                        "90"
+                    )
+                );
+            }
+
+            match data {
+                Ok(data) => {
+                    self.print_variable_data(var, &shape, &read_slab.shape(), &data, options)
+                }
+                Err(e) => println!(
+                    " {} = {} ;",
+                    self.colorize(data_label(var), "36"),
+                    self.colorize(&format!("<error reading data: {}>", e), "31")
+                ),
+            }
+            println!();
+        }
 
+        println!("}}");
         Ok(())
     }
 
-    fn format_coordinate_values(&self, data: &[f64]) -> String {
+    /// Resolve the `--isel`/`--sel` selections for the dimensions of `var`, reading (and caching)
+    /// coordinate variables for value selections
+    async fn resolve_hyperslab(
+        &self,
+        metadata: &ZarrMetadata,
+        var: &metadata::Variable,
+        options: &DataOptions,
+        store: &ZarrStore,
+        coordinates: &mut HashMap<String, Vec<f64>>,
+    ) -> anyhow::Result<hyperslab::Hyperslab> {
+        let mut selections = HashMap::new();
+        for (dim, &size) in var.dimensions.iter().zip(&var.shape) {
+            let range = if let Some(selection) = options.isel.get(&dim.name) {
+                selection.resolve(&dim.name, size)?
+            } else if let Some(selection) = options.sel.get(&dim.name) {
+                let (coord_path, coord_var) =
+                    cf::resolve_related_var(metadata, &var.path, &dim.name)
+                        .filter(|(_, c)| c.shape.len() == 1)
+                        .ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "--sel on dimension '{}' needs a 1D coordinate variable '{}' (use --isel instead)",
+                                dim.name,
+                                dim.name
+                            )
+                        })?;
+                if !coordinates.contains_key(coord_path) {
+                    let values =
+                        store
+                            .read_coordinate_data(coord_var)
+                            .await
+                            .with_context(|| {
+                                format!("Failed to read coordinate variable '{}'", coord_path)
+                            })?;
+                    coordinates.insert(coord_path.clone(), values);
+                }
+                selection.resolve(&dim.name, &coordinates[coord_path])?
+            } else {
+                continue;
+            };
+            selections.insert(dim.name.clone(), range);
+        }
+        Ok(hyperslab::build_hyperslab(var, &selections))
+    }
+
+    /// Print one variable's values in ncdump layout: scalars and 1D variables on one line, higher
+    /// dimensional variables one row (along the last dimension) per line, with `...` marking
+    /// rows and columns left out by `--max-rows`/`--max-cols`
+    fn print_variable_data(
+        &self,
+        var: &metadata::Variable,
+        shape: &[u64],
+        read_shape: &[u64],
+        data: &[String],
+        options: &DataOptions,
+    ) {
+        let total_cols = shape.last().copied().unwrap_or(1);
+        let cols = read_shape.last().copied().unwrap_or(1) as usize;
+        let total_rows: u64 = shape.iter().rev().skip(1).product();
+        let format_row = |row: &[String]| {
+            let mut line = self.format_value_list(row);
+            if (cols as u64) < total_cols {
+                line.push_str(", ...");
+            }
+            line
+        };
+
+        if shape.len() <= 1 {
+            println!(
+                " {} = {} ;",
+                self.colorize(data_label(var), "36"),
+                format_row(data)
+            );
+            return;
+        }
+
+        let mut lines: Vec<String> = data
+            .chunks(cols.max(1))
+            .take(options.max_rows.unwrap_or(usize::MAX))
+            .map(|row| format!("  {}", format_row(row)))
+            .collect();
+        if (lines.len() as u64) < total_rows {
+            lines.push("  ...".to_string());
+        }
+        if lines.is_empty() {
+            lines.push(format!("  {}", self.colorize("<no data>", "90")));
+        }
+        println!(" {} =", self.colorize(data_label(var), "36"));
+        println!("{} ;", lines.join(",\n"));
+    }
+
+    /// Format values as a comma separated list, writing fill/missing values as `_` like ncdump
+    /// and non-integer values with `precision` significant digits if given
+    fn format_coordinate_values(
+        &self,
+        data: &[f64],
+        missing_values: &[f64],
+        precision: Option<usize>,
+    ) -> String {
+        let values: Vec<String> = data
+            .iter()
+            .map(|&value| format_float_value(value, missing_values, precision))
+            .collect();
+        self.format_value_list(&values)
+    }
+
+    /// Lay out formatted values as a comma separated list, wrapped like ncdump
+    fn format_value_list(&self, data: &[String]) -> String {
         const MAX_VALUES_PER_LINE: usize = 8;
         const LINE_WIDTH: usize = 76;
 
@@ -577,18 +2255,10 @@ impl NetCdfFormatter {
         let mut current_line = String::new();
         let mut values_on_line = 0;
 
-        for (i, &value) in data.iter().enumerate() {
-            let formatted_val = if value.fract() == 0.0 && value.abs() < 1e10 {
-                format!("{}", value as i64)
-            } else if value.abs() >= 1e6 || (value.abs() < 1e-3 && value != 0.0) {
-                format!("{:e}", value)
-            } else {
-                format!("{}", value)
-            };
-
+        for (i, formatted_val) in data.iter().enumerate() {
             let val_str = if i == data.len() - 1 {
                 // Last value, no comma
-                formatted_val
+                formatted_val.clone()
             } else {
                 format!("{}, ", formatted_val)
             };
@@ -1,3 +1,4 @@
+use crate::codec::{Codec, CodecPipeline};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -16,12 +17,33 @@ pub enum AttributeValue {
     String(String),
     Number(f64),
     Integer(i64),
+    /// Integers above `i64::MAX`, e.g. the largest `uint64` fill value
+    UnsignedInteger(u64),
     Boolean(bool),
     Array(Vec<AttributeValue>),
     Object(HashMap<String, AttributeValue>),
     Null,
 }
 
+impl AttributeValue {
+    /// Numeric value, including the `"NaN"`/`"Infinity"`/`"-Infinity"` strings Zarr uses for
+    /// non-finite floats in JSON
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            AttributeValue::Number(v) => Some(*v),
+            AttributeValue::Integer(v) => Some(*v as f64),
+            AttributeValue::UnsignedInteger(v) => Some(*v as f64),
+            AttributeValue::String(s) => match s.as_str() {
+                "NaN" => Some(f64::NAN),
+                "Infinity" => Some(f64::INFINITY),
+                "-Infinity" => Some(f64::NEG_INFINITY),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
 /// Represents a single attribute
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[allow(dead_code)]
@@ -39,14 +61,124 @@ pub struct Variable {
     pub dtype: String,
     pub shape: Vec<u64>,
     pub chunks: Vec<u64>,
-    pub compressor: Option<String>,
+    pub codecs: CodecPipeline,
     pub fill_value: Option<AttributeValue>,
     pub order: String,
-    pub filters: Vec<String>,
+    /// Separator between chunk indices in chunk keys (`.` or `/`)
+    pub dimension_separator: String,
     pub attributes: HashMap<String, AttributeValue>,
     pub dimensions: Vec<Dimension>,
 }
 
+impl Variable {
+    /// Byte order of the stored data (`little` or `big`), or `None` for single-byte types
+    pub fn endianness(&self) -> Option<&str> {
+        match &self.codecs {
+            CodecPipeline::V2 { .. } => match self.dtype.chars().next() {
+                Some('<') => Some("little"),
+                Some('>') => Some("big"),
+                _ => None,
+            },
+            CodecPipeline::V3(codecs) => {
+                let inner = self.codecs.sharding().map(|s| s.codecs.as_slice());
+                codecs
+                    .iter()
+                    .chain(inner.into_iter().flatten())
+                    .find_map(|codec| match codec.resolved() {
+                        Codec::Bytes { endian } => endian.as_deref(),
+                        _ => None,
+                    })
+            }
+        }
+    }
+}
+
+/// Shards of a sharded array found in storage
+#[derive(Debug, Clone)]
+pub struct ShardInventory {
+    pub total_shards: u64,
+    pub inner_chunks_per_shard: u64,
+    /// Shards that exist in storage, in chunk grid order
+    pub shards: Vec<ShardStatus>,
+}
+
+/// A shard present in storage and the number of its inner chunks that have been written
+#[derive(Debug, Clone)]
+pub struct ShardStatus {
+    /// Chunk key relative to the array (e.g. `c/0/1`)
+    pub key: String,
+    pub populated_inner_chunks: u64,
+}
+
+/// Chunks of an array found in storage
+#[derive(Debug, Clone, Default)]
+pub struct ChunkInventory {
+    /// Chunks in the chunk grid (shards, for sharded arrays)
+    pub expected_chunks: u64,
+    /// Chunks present in storage
+    pub stored_chunks: u64,
+    /// Total size of the stored chunks in bytes
+    pub stored_bytes: u64,
+    /// Decoded size of one chunk, or `None` for variable-size data types
+    pub chunk_bytes: Option<u64>,
+    /// Decoded size of the whole array, or `None` for variable-size data types
+    pub array_bytes: Option<u64>,
+    pub smallest: Option<StoredChunk>,
+    pub largest: Option<StoredChunk>,
+}
+
+impl ChunkInventory {
+    /// Decoded size of the stored chunks divided by their stored size
+    pub fn compression_ratio(&self) -> Option<f64> {
+        let decoded = self.chunk_bytes? * self.stored_chunks;
+        (self.stored_bytes > 0).then(|| decoded as f64 / self.stored_bytes as f64)
+    }
+}
+
+/// A chunk present in storage and its stored size
+#[derive(Debug, Clone)]
+pub struct StoredChunk {
+    /// Chunk key relative to the array (e.g. `0.1` or `c/0/1`)
+    pub key: String,
+    pub size: u64,
+}
+
+/// Integrity of the chunks of an array
+#[derive(Debug, Clone, Default)]
+pub struct ArrayVerification {
+    /// Chunks in the chunk grid
+    pub expected_chunks: u64,
+    /// Chunks present in storage
+    pub stored_chunks: u64,
+    /// Keys of the chunks in the grid that are not stored (relative to the array)
+    pub missing: Vec<String>,
+    /// Keys under the array that are neither metadata nor chunks of its grid, or `None` if the
+    /// storage cannot list keys
+    pub orphans: Option<Vec<String>>,
+    /// Stored chunks that cannot be decoded
+    pub corrupt: Vec<ChunkProblem>,
+    /// Stored chunks whose decoded size does not match the chunk shape
+    pub wrong_size: Vec<ChunkProblem>,
+}
+
+impl ArrayVerification {
+    /// Whether any chunk is orphaned, corrupt or of the wrong size (missing chunks are allowed,
+    /// since Zarr does not store chunks that only hold the fill value)
+    pub fn has_errors(&self) -> bool {
+        !self.corrupt.is_empty()
+            || !self.wrong_size.is_empty()
+            || self.orphans.as_ref().is_some_and(|o| !o.is_empty())
+    }
+}
+
+/// A chunk that failed verification
+#[derive(Debug, Clone)]
+pub struct ChunkProblem {
+    /// Chunk key relative to the array
+    pub key: String,
+    pub message: String,
+}
+
 /// Represents a Zarr group
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -71,6 +203,8 @@ pub struct DimensionInfo {
 #[derive(Debug)]
 pub struct ZarrMetadata {
     pub zarr_format: u8,
+    /// Whether the metadata was read from consolidated metadata (`.zmetadata` or v3 `zarr.json`)
+    pub consolidated: bool,
     pub global_attributes: HashMap<String, AttributeValue>,
     pub groups: HashMap<String, Group>,
     pub variables: HashMap<String, Variable>,
@@ -82,6 +216,7 @@ impl Default for ZarrMetadata {
     fn default() -> Self {
         Self {
             zarr_format: 2,
+            consolidated: false,
             global_attributes: HashMap::new(),
             variables: HashMap::new(),
             groups: HashMap::new(),
@@ -101,6 +236,45 @@ impl ZarrMetadata {
         Self::default()
     }
 
+    /// Fill in the `children` of the root group and of every group from the array and group paths
+    pub fn populate_group_children(&mut self) {
+        let mut children: HashMap<String, Vec<String>> = HashMap::new();
+        for path in self.variables.keys().chain(self.groups.keys()) {
+            if path.is_empty() {
+                continue;
+            }
+            let (parent, name) = path.rsplit_once('/').unwrap_or(("", path));
+            children
+                .entry(parent.to_string())
+                .or_default()
+                .push(name.to_string());
+        }
+
+        for names in children.values_mut() {
+            names.sort();
+            names.dedup();
+        }
+
+        self.root_group.children = children.remove("").unwrap_or_default();
+        for (path, group) in self.groups.iter_mut() {
+            group.children = children.remove(path).unwrap_or_default();
+        }
+    }
+
+    /// Keep only the variables for which `keep` returns true, dropping dimensions that are no
+    /// longer used and updating group children
+    pub fn retain_variables(&mut self, mut keep: impl FnMut(&Variable) -> bool) {
+        self.variables.retain(|_, variable| keep(variable));
+
+        let variables = &self.variables;
+        self.dimensions.retain(|_, info| {
+            info.appearances
+                .retain(|(path, _)| variables.contains_key(path));
+            !info.appearances.is_empty()
+        });
+        self.populate_group_children();
+    }
+
     /// Infer dimensions and detect unlimited dimensions from all variables
     pub fn infer_dimensions(&mut self) {
         let mut dimension_map: HashMap<String, Vec<(String, u64)>> = HashMap::new();
@@ -231,6 +405,7 @@ pub struct ZArrayMetadata {
     pub fill_value: Option<serde_json::Value>,
     pub order: String,
     pub filters: Option<Vec<serde_json::Value>>,
+    pub dimension_separator: Option<String>,
 }
 
 /// Raw Zarr group metadata from .zgroup file
@@ -264,7 +439,23 @@ pub struct ZarrV3Root {
 pub struct ZarrV3ConsolidatedMetadata {
     pub kind: String,
     pub must_understand: bool,
-    pub metadata: HashMap<String, ZarrV3ArrayMetadata>,
+    pub metadata: HashMap<String, ZarrV3NodeMetadata>,
+}
+
+/// Zarr v3 node metadata from a zarr.json file, distinguished by its `node_type`
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "node_type", rename_all = "lowercase")]
+pub enum ZarrV3NodeMetadata {
+    Array(Box<ZarrV3ArrayMetadata>),
+    Group(ZarrV3GroupMetadata),
+}
+
+/// Zarr v3 group metadata from zarr.json file
+#[derive(Debug, Deserialize, Clone)]
+#[allow(dead_code)]
+pub struct ZarrV3GroupMetadata {
+    pub zarr_format: u8,
+    pub attributes: Option<HashMap<String, AttributeValue>>,
 }
 
 /// Zarr v3 array metadata from zarr.json file
@@ -272,7 +463,6 @@ pub struct ZarrV3ConsolidatedMetadata {
 #[allow(dead_code)]
 pub struct ZarrV3ArrayMetadata {
     pub zarr_format: u8,
-    pub node_type: String,
     pub shape: Vec<u64>,
     pub data_type: String,
     pub chunk_grid: ZarrV3ChunkGrid,
@@ -344,10 +534,10 @@ mod tests {
             dtype: "float64".to_string(),
             shape: vec![10, 20, 30],
             chunks: vec![5, 10, 15],
-            compressor: None,
+            codecs: CodecPipeline::default(),
             fill_value: None,
             order: "C".to_string(),
-            filters: vec![],
+            dimension_separator: ".".to_string(),
             attributes,
             dimensions: vec![],
         };
@@ -365,10 +555,10 @@ mod tests {
             dtype: "float32".to_string(),
             shape: vec![100, 200],
             chunks: vec![50, 100],
-            compressor: None,
+            codecs: CodecPipeline::default(),
             fill_value: None,
             order: "C".to_string(),
-            filters: vec![],
+            dimension_separator: ".".to_string(),
             attributes: HashMap::new(),
             dimensions: vec![],
         };
@@ -377,6 +567,49 @@ mod tests {
         assert_eq!(dim_names, vec!["dim_0", "dim_1"]);
     }
 
+    #[test]
+    fn test_populate_group_children() {
+        let mut metadata = ZarrMetadata::new();
+        for path in ["model", "model/historical"] {
+            metadata.groups.insert(
+                path.to_string(),
+                Group {
+                    name: path.rsplit('/').next().unwrap().to_string(),
+                    path: path.to_string(),
+                    attributes: HashMap::new(),
+                    children: Vec::new(),
+                },
+            );
+        }
+        for path in ["lat", "model/historical/tas", "model/historical/pr"] {
+            metadata.variables.insert(
+                path.to_string(),
+                Variable {
+                    name: path.rsplit('/').next().unwrap().to_string(),
+                    path: path.to_string(),
+                    dtype: "<f4".to_string(),
+                    shape: vec![2],
+                    chunks: vec![2],
+                    codecs: CodecPipeline::default(),
+                    fill_value: None,
+                    order: "C".to_string(),
+                    dimension_separator: ".".to_string(),
+                    attributes: HashMap::new(),
+                    dimensions: vec![],
+                },
+            );
+        }
+
+        metadata.populate_group_children();
+
+        assert_eq!(metadata.root_group.children, vec!["lat", "model"]);
+        assert_eq!(metadata.groups["model"].children, vec!["historical"]);
+        assert_eq!(
+            metadata.groups["model/historical"].children,
+            vec!["pr", "tas"]
+        );
+    }
+
     #[test]
     fn test_infer_dimensions_single_variable() {
         let mut metadata = ZarrMetadata::new();
@@ -387,10 +620,10 @@ mod tests {
             dtype: "float64".to_string(),
             shape: vec![365, 180, 360],
             chunks: vec![1, 180, 360],
-            compressor: None,
+            codecs: CodecPipeline::default(),
             fill_value: None,
             order: "C".to_string(),
-            filters: vec![],
+            dimension_separator: ".".to_string(),
             attributes: HashMap::new(),
             dimensions: vec![],
         };
@@ -444,10 +677,10 @@ mod tests {
             dtype: "float64".to_string(),
             shape: vec![100],
             chunks: vec![10],
-            compressor: None,
+            codecs: CodecPipeline::default(),
             fill_value: None,
             order: "C".to_string(),
-            filters: vec![],
+            dimension_separator: ".".to_string(),
             attributes: attrs1,
             dimensions: vec![],
         };
@@ -458,10 +691,10 @@ mod tests {
             dtype: "float64".to_string(),
             shape: vec![200], // Different size for same dimension
             chunks: vec![20],
-            compressor: None,
+            codecs: CodecPipeline::default(),
             fill_value: None,
             order: "C".to_string(),
-            filters: vec![],
+            dimension_separator: ".".to_string(),
             attributes: attrs2,
             dimensions: vec![],
         };
@@ -495,10 +728,10 @@ mod tests {
             dtype: "float64".to_string(),
             shape: vec![0, 100], // Zero size in first dimension
             chunks: vec![1, 100],
-            compressor: None,
+            codecs: CodecPipeline::default(),
             fill_value: None,
             order: "C".to_string(),
-            filters: vec![],
+            dimension_separator: ".".to_string(),
             attributes: HashMap::new(),
             dimensions: vec![],
         };
@@ -518,6 +751,25 @@ mod tests {
     }
 
     #[test]
+    fn test_attribute_value_as_f64() {
+        assert_eq!(AttributeValue::Number(2.5).as_f64(), Some(2.5));
+        assert_eq!(AttributeValue::Integer(-3).as_f64(), Some(-3.0));
+        assert!(
+            AttributeValue::String("NaN".to_string())
+                .as_f64()
+                .unwrap()
+                .is_nan()
+        );
+        assert_eq!(
+            AttributeValue::String("-Infinity".to_string()).as_f64(),
+            Some(f64::NEG_INFINITY)
+        );
+        assert_eq!(AttributeValue::String("1.0".to_string()).as_f64(), None);
+        assert_eq!(AttributeValue::Boolean(true).as_f64(), None);
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
     fn test_attribute_value_serialization() {
         // Test different attribute value types
         let string_val = AttributeValue::String("test".to_string());
@@ -633,10 +885,10 @@ mod tests {
             dtype: "float32".to_string(),
             shape: vec![365, 180, 360],
             chunks: vec![1, 180, 360],
-            compressor: None,
+            codecs: CodecPipeline::default(),
             fill_value: None,
             order: "C".to_string(),
-            filters: vec![],
+            dimension_separator: ".".to_string(),
             attributes: attrs1,
             dimensions: vec![],
         };
@@ -647,10 +899,10 @@ mod tests {
             dtype: "float32".to_string(),
             shape: vec![365, 50], // Same time dimension, different level
             chunks: vec![1, 50],
-            compressor: None,
+            codecs: CodecPipeline::default(),
             fill_value: None,
             order: "C".to_string(),
-            filters: vec![],
+            dimension_separator: ".".to_string(),
             attributes: attrs2,
             dimensions: vec![],
         };
@@ -0,0 +1,368 @@
+//! A small pure-Rust writer for the netCDF classic file formats.
+//!
+//! Files use the 64-bit offset format (CDF-2), or the 64-bit data format (CDF-5) when unsigned or
+//! 64-bit integer types are needed. Both are read by every netCDF-4 library and by xarray. Writing
+//! HDF5-based netCDF-4 files would need the netCDF C library, so groups and `string` variables
+//! are not supported.
+
+use crate::cdl::{CdlType, Number, TypedAttribute};
+use anyhow::{Result, bail};
+use std::io::Write;
+
+const NC_DIMENSION: u32 = 0x0A;
+const NC_VARIABLE: u32 = 0x0B;
+const NC_ATTRIBUTE: u32 = 0x0C;
+
+/// A dimension; a length of 0 makes it the record (unlimited) dimension
+#[derive(Debug, Clone)]
+pub struct NcDimension {
+    pub name: String,
+    pub length: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct NcAttribute {
+    pub name: String,
+    pub value: TypedAttribute,
+}
+
+#[derive(Debug, Clone)]
+pub struct NcVariable {
+    pub name: String,
+    /// Indices into [`NcFile::dimensions`]
+    pub dimensions: Vec<usize>,
+    pub nc_type: CdlType,
+    pub attributes: Vec<NcAttribute>,
+}
+
+/// The header of a netCDF classic file: dimensions, global attributes and variables
+#[derive(Debug, Clone, Default)]
+pub struct NcFile {
+    pub dimensions: Vec<NcDimension>,
+    pub attributes: Vec<NcAttribute>,
+    pub variables: Vec<NcVariable>,
+}
+
+/// `nc_type` code and element size of a type, or `None` for `string`
+fn type_info(nc_type: CdlType) -> Option<(u32, u64)> {
+    Some(match nc_type {
+        CdlType::Byte => (1, 1),
+        CdlType::Short => (3, 2),
+        CdlType::Int => (4, 4),
+        CdlType::Float => (5, 4),
+        CdlType::Double => (6, 8),
+        CdlType::UByte => (7, 1),
+        CdlType::UShort => (8, 2),
+        CdlType::UInt => (9, 4),
+        CdlType::Int64 => (10, 8),
+        CdlType::UInt64 => (11, 8),
+        CdlType::String => return None,
+    })
+}
+
+/// Whether a type needs the CDF-5 format
+fn is_extended(nc_type: CdlType) -> bool {
+    matches!(
+        nc_type,
+        CdlType::UByte | CdlType::UShort | CdlType::UInt | CdlType::Int64 | CdlType::UInt64
+    )
+}
+
+fn padding(len: u64) -> u64 {
+    (4 - len % 4) % 4
+}
+
+impl NcFile {
+    /// Format version byte: 5 if any variable or attribute needs the extended types, else 2
+    pub fn version(&self) -> u8 {
+        let attribute_types = self
+            .attributes
+            .iter()
+            .chain(self.variables.iter().flat_map(|v| &v.attributes))
+            .filter_map(|a| match &a.value {
+                TypedAttribute::Numbers(t, _) => Some(*t),
+                _ => None,
+            });
+        let extended = self
+            .variables
+            .iter()
+            .map(|v| v.nc_type)
+            .chain(attribute_types)
+            .any(is_extended);
+        if extended { 5 } else { 2 }
+    }
+
+    fn is_record_variable(&self, variable: &NcVariable) -> bool {
+        variable
+            .dimensions
+            .first()
+            .is_some_and(|&d| self.dimensions[d].length == 0)
+    }
+
+    /// Bytes of data of a variable (of one record, for record variables), before padding
+    pub fn data_size(&self, variable: &NcVariable) -> u64 {
+        let (_, size) = type_info(variable.nc_type).unwrap_or((0, 0));
+        variable
+            .dimensions
+            .iter()
+            .skip(usize::from(self.is_record_variable(variable)))
+            .map(|&d| self.dimensions[d].length)
+            .product::<u64>()
+            * size
+    }
+
+    fn validate(&self) -> Result<()> {
+        let records = self.dimensions.iter().filter(|d| d.length == 0).count();
+        if records > 1 {
+            bail!("netCDF classic files can only have one zero-length (record) dimension");
+        }
+        for variable in &self.variables {
+            if type_info(variable.nc_type).is_none() {
+                bail!(
+                    "Variable '{}' has type {}, which netCDF classic files cannot hold",
+                    variable.name,
+                    variable.nc_type.name()
+                );
+            }
+            for (i, &d) in variable.dimensions.iter().enumerate() {
+                if i > 0 && self.dimensions[d].length == 0 {
+                    bail!(
+                        "Variable '{}' uses the record dimension '{}' after its first dimension",
+                        variable.name,
+                        self.dimensions[d].name
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Serialize the header with the given data offsets
+    fn encode_header(&self, begins: &[u64]) -> Vec<u8> {
+        let version = self.version();
+        let mut out = Vec::new();
+        let put_u32 = |out: &mut Vec<u8>, v: u32| out.extend_from_slice(&v.to_be_bytes());
+        // Counts and lengths are 8 bytes wide in CDF-5
+        let put_count = |out: &mut Vec<u8>, v: u64| {
+            if version == 5 {
+                out.extend_from_slice(&v.to_be_bytes());
+            } else {
+                out.extend_from_slice(&(v as u32).to_be_bytes());
+            }
+        };
+        let put_name = |out: &mut Vec<u8>, name: &str| {
+            put_count(out, name.len() as u64);
+            out.extend_from_slice(name.as_bytes());
+            out.resize(out.len() + padding(name.len() as u64) as usize, 0);
+        };
+        let put_list_tag = |out: &mut Vec<u8>, tag: u32, len: usize| {
+            if len == 0 {
+                // ABSENT
+                put_u32(out, 0);
+                put_count(out, 0);
+            } else {
+                put_u32(out, tag);
+                put_count(out, len as u64);
+            }
+        };
+        let put_attributes = |out: &mut Vec<u8>, attributes: &[NcAttribute]| {
+            put_list_tag(out, NC_ATTRIBUTE, attributes.len());
+            for attribute in attributes {
+                put_name(out, &attribute.name);
+                let start = out.len();
+                match &attribute.value {
+                    TypedAttribute::Numbers(nc_type, numbers) => {
+                        let (code, _) = type_info(*nc_type).unwrap_or((6, 8));
+                        put_u32(out, code);
+                        put_count(out, numbers.len() as u64);
+                        let values: Vec<f64> = numbers.iter().map(|n| n.as_f64()).collect();
+                        let value_start = out.len();
+                        match (nc_type, numbers.as_slice()) {
+                            // Keep 64-bit integers exact instead of going through f64
+                            (CdlType::Int64 | CdlType::UInt64, _)
+                                if numbers.iter().all(|n| matches!(n, Number::Integer(_))) =>
+                            {
+                                for n in numbers {
+                                    if let Number::Integer(i) = n {
+                                        // Two's complement, so also right for uint64
+                                        out.extend_from_slice(&(*i as u64).to_be_bytes());
+                                    }
+                                }
+                            }
+                            _ => encode_values(*nc_type, &values, out),
+                        }
+                        let written = (out.len() - value_start) as u64;
+                        out.resize(out.len() + padding(written) as usize, 0);
+                    }
+                    TypedAttribute::Text(text) => {
+                        put_u32(out, 2); // NC_CHAR
+                        put_count(out, text.len() as u64);
+                        out.extend_from_slice(text.as_bytes());
+                        out.resize(out.len() + padding(text.len() as u64) as usize, 0);
+                    }
+                    TypedAttribute::Strings(strings) => {
+                        // No string type in classic files: keep the list as JSON text
+                        let text = serde_json::to_string(strings).unwrap_or_default();
+                        put_u32(out, 2);
+                        put_count(out, text.len() as u64);
+                        out.extend_from_slice(text.as_bytes());
+                        out.resize(out.len() + padding(text.len() as u64) as usize, 0);
+                    }
+                }
+                debug_assert_eq!((out.len() - start) % 4, 0);
+            }
+        };
+
+        out.extend_from_slice(b"CDF");
+        out.push(version);
+        put_count(&mut out, 0); // numrecs: record dimensions are always empty
+
+        put_list_tag(&mut out, NC_DIMENSION, self.dimensions.len());
+        for dimension in &self.dimensions {
+            put_name(&mut out, &dimension.name);
+            put_count(&mut out, dimension.length);
+        }
+
+        put_attributes(&mut out, &self.attributes);
+
+        put_list_tag(&mut out, NC_VARIABLE, self.variables.len());
+        for (variable, begin) in self.variables.iter().zip(begins) {
+            put_name(&mut out, &variable.name);
+            put_count(&mut out, variable.dimensions.len() as u64);
+            for &d in &variable.dimensions {
+                put_count(&mut out, d as u64);
+            }
+            put_attributes(&mut out, &variable.attributes);
+            let (code, _) = type_info(variable.nc_type).unwrap_or((6, 8));
+            put_u32(&mut out, code);
+            let size = self.data_size(variable);
+            let vsize = size + padding(size);
+            if version == 5 {
+                out.extend_from_slice(&vsize.to_be_bytes());
+            } else {
+                // Variables over 4 GiB store the maximum value, as the format specifies
+                put_u32(&mut out, u32::try_from(vsize).unwrap_or(u32::MAX));
+            }
+            out.extend_from_slice(&begin.to_be_bytes());
+        }
+        out
+    }
+
+    /// The encoded header and the offset where each variable's data starts. Data of fixed-size
+    /// variables follows the header in variable order, each padded to 4 bytes; record variables
+    /// have no data since the record dimension is empty.
+    pub fn header(&self) -> Result<(Vec<u8>, Vec<u64>)> {
+        self.validate()?;
+
+        let header_len = self.encode_header(&vec![0; self.variables.len()]).len() as u64;
+        let mut offset = header_len;
+        let mut begins = Vec::with_capacity(self.variables.len());
+        for variable in &self.variables {
+            begins.push(offset);
+            if !self.is_record_variable(variable) {
+                let size = self.data_size(variable);
+                offset += size + padding(size);
+            }
+        }
+        Ok((self.encode_header(&begins), begins))
+    }
+}
+
+/// Append values converted to `nc_type` as big-endian bytes (NaN becomes 0 for integer types)
+pub fn encode_values(nc_type: CdlType, values: &[f64], out: &mut Vec<u8>) {
+    for &v in values {
+        match nc_type {
+            CdlType::Byte => out.extend_from_slice(&(v as i8).to_be_bytes()),
+            CdlType::UByte => out.extend_from_slice(&(v as u8).to_be_bytes()),
+            CdlType::Short => out.extend_from_slice(&(v as i16).to_be_bytes()),
+            CdlType::UShort => out.extend_from_slice(&(v as u16).to_be_bytes()),
+            CdlType::Int => out.extend_from_slice(&(v as i32).to_be_bytes()),
+            CdlType::UInt => out.extend_from_slice(&(v as u32).to_be_bytes()),
+            CdlType::Int64 => out.extend_from_slice(&(v as i64).to_be_bytes()),
+            CdlType::UInt64 => out.extend_from_slice(&(v as u64).to_be_bytes()),
+            CdlType::Float => out.extend_from_slice(&(v as f32).to_be_bytes()),
+            CdlType::Double | CdlType::String => out.extend_from_slice(&v.to_be_bytes()),
+        }
+    }
+}
+
+/// Write the padding that follows `size` bytes of variable data
+pub fn write_padding(writer: &mut impl Write, size: u64) -> std::io::Result<()> {
+    writer.write_all(&[0u8; 4][..padding(size) as usize])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_file(nc_type: CdlType) -> NcFile {
+        NcFile {
+            dimensions: vec![NcDimension {
+                name: "x".to_string(),
+                length: 3,
+            }],
+            attributes: vec![NcAttribute {
+                name: "title".to_string(),
+                value: TypedAttribute::Text("t".to_string()),
+            }],
+            variables: vec![NcVariable {
+                name: "v".to_string(),
+                dimensions: vec![0],
+                nc_type,
+                attributes: vec![NcAttribute {
+                    name: "_FillValue".to_string(),
+                    value: TypedAttribute::Numbers(nc_type, vec![Number::Integer(-1)]),
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_classic_header_layout() {
+        let file = sample_file(CdlType::Short);
+        assert_eq!(file.version(), 2);
+        let (header, begins) = file.header().unwrap();
+
+        let mut expected = b"CDF\x02".to_vec();
+        expected.extend_from_slice(&0u32.to_be_bytes()); // numrecs
+        expected.extend_from_slice(&[0, 0, 0, 0x0A, 0, 0, 0, 1]);
+        expected.extend_from_slice(&[0, 0, 0, 1, b'x', 0, 0, 0, 0, 0, 0, 3]);
+        expected.extend_from_slice(&[0, 0, 0, 0x0C, 0, 0, 0, 1]);
+        expected.extend_from_slice(&[0, 0, 0, 5]);
+        expected.extend_from_slice(b"title\0\0\0");
+        expected.extend_from_slice(&[0, 0, 0, 2, 0, 0, 0, 1, b't', 0, 0, 0]);
+        expected.extend_from_slice(&[0, 0, 0, 0x0B, 0, 0, 0, 1]);
+        expected.extend_from_slice(&[0, 0, 0, 1, b'v', 0, 0, 0]);
+        expected.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 0]); // one dimension, id 0
+        expected.extend_from_slice(&[0, 0, 0, 0x0C, 0, 0, 0, 1]);
+        expected.extend_from_slice(&[0, 0, 0, 10]);
+        expected.extend_from_slice(b"_FillValue\0\0");
+        expected.extend_from_slice(&[0, 0, 0, 3, 0, 0, 0, 1, 0xFF, 0xFF, 0, 0]);
+        expected.extend_from_slice(&[0, 0, 0, 3]); // NC_SHORT
+        expected.extend_from_slice(&8u32.to_be_bytes()); // vsize: 6 bytes padded to 8
+        let begin = expected.len() as u64 + 8;
+        expected.extend_from_slice(&begin.to_be_bytes());
+
+        assert_eq!(header, expected);
+        assert_eq!(begins, [begin]);
+    }
+
+    #[test]
+    fn test_extended_types_use_cdf5() {
+        let file = sample_file(CdlType::UInt64);
+        assert_eq!(file.version(), 5);
+        let (header, _) = file.header().unwrap();
+        assert_eq!(&header[..4], b"CDF\x05");
+        // numrecs is 8 bytes wide
+        assert_eq!(&header[4..12], &[0; 8]);
+
+        let mut values = Vec::new();
+        encode_values(CdlType::Float, &[1.5], &mut values);
+        assert_eq!(values, 1.5f32.to_be_bytes());
+    }
+
+    #[test]
+    fn test_rejects_string_variables() {
+        assert!(sample_file(CdlType::String).header().is_err());
+    }
+}
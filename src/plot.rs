@@ -223,6 +223,7 @@ fn compute_c_strides(shape: &[usize]) -> Result<Vec<usize>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::codec::CodecPipeline;
     use crate::metadata::{Dimension, Variable};
     use std::collections::HashMap;
 
@@ -243,10 +244,10 @@ mod tests {
             dtype: "<f4".to_string(),
             shape: shape.to_vec(),
             chunks: vec![],
-            compressor: None,
+            codecs: CodecPipeline::default(),
             fill_value: None,
             order: "C".to_string(),
-            filters: vec![],
+            dimension_separator: ".".to_string(),
             attributes: HashMap::new(),
             dimensions,
         }
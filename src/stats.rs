@@ -0,0 +1,228 @@
+//! Streaming per-variable statistics for the `stats` subcommand
+
+use crate::cf;
+use crate::format::{format_significant, format_table};
+use crate::hyperslab::Hyperslab;
+use crate::metadata::{AttributeValue, Variable};
+use crate::store::ZarrStore;
+use anyhow::{Context, Result};
+use serde_json::{Value, json};
+
+/// How stored values map to data values, following the CF conventions: fill and missing values
+/// and values outside the valid range (compared in stored units) are masked, and the others are
+/// unpacked with `scale_factor` and `add_offset`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueMask {
+    missing_values: Vec<f64>,
+    valid_min: Option<f64>,
+    valid_max: Option<f64>,
+    scale_factor: f64,
+    add_offset: f64,
+}
+
+fn attribute_f64(var: &Variable, name: &str) -> Option<f64> {
+    var.attributes.get(name).and_then(AttributeValue::as_f64)
+}
+
+impl ValueMask {
+    pub fn new(var: &Variable) -> Self {
+        let mut valid_min = attribute_f64(var, "valid_min");
+        let mut valid_max = attribute_f64(var, "valid_max");
+        if let Some(AttributeValue::Array(range)) = var.attributes.get("valid_range")
+            && let [min, max] = range.as_slice()
+        {
+            valid_min = min.as_f64().or(valid_min);
+            valid_max = max.as_f64().or(valid_max);
+        }
+        Self {
+            missing_values: cf::collect_missing_values_f64(var),
+            valid_min,
+            valid_max,
+            scale_factor: attribute_f64(var, "scale_factor").unwrap_or(1.0),
+            add_offset: attribute_f64(var, "add_offset").unwrap_or(0.0),
+        }
+    }
+
+    fn is_missing(&self, value: f64) -> bool {
+        cf::is_missing_value(value, &self.missing_values)
+            || self.valid_min.is_some_and(|min| value < min)
+            || self.valid_max.is_some_and(|max| value > max)
+    }
+
+    fn unpack(&self, value: f64) -> f64 {
+        value * self.scale_factor + self.add_offset
+    }
+}
+
+/// Running statistics of the data values of a variable
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VariableStats {
+    /// Values that are neither fill/missing values nor NaN
+    pub valid: u64,
+    /// Fill and missing values, including values outside the valid range
+    pub missing: u64,
+    /// NaN values that are not fill values
+    pub nan: u64,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    mean: f64,
+    /// Sum of squared differences from the mean (Welford's algorithm)
+    m2: f64,
+}
+
+impl VariableStats {
+    /// Add a batch of stored values
+    pub fn update(&mut self, mask: &ValueMask, data: &[f64]) {
+        for &raw in data {
+            if mask.is_missing(raw) {
+                self.missing += 1;
+                continue;
+            }
+            if raw.is_nan() {
+                self.nan += 1;
+                continue;
+            }
+
+            let value = mask.unpack(raw);
+            self.valid += 1;
+            self.min = Some(self.min.map_or(value, |min| min.min(value)));
+            self.max = Some(self.max.map_or(value, |max| max.max(value)));
+            let delta = value - self.mean;
+            self.mean += delta / self.valid as f64;
+            self.m2 += delta * (value - self.mean);
+        }
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.valid > 0).then_some(self.mean)
+    }
+
+    /// Population standard deviation (like numpy's default `ddof=0`)
+    pub fn std(&self) -> Option<f64> {
+        (self.valid > 0).then(|| (self.m2 / self.valid as f64).sqrt())
+    }
+
+    pub fn to_json(&self) -> Value {
+        // JSON has no NaN or infinity
+        let number = |v: Option<f64>| v.filter(|v| v.is_finite()).map_or(Value::Null, Value::from);
+        json!({
+            "valid": self.valid,
+            "missing": self.missing,
+            "nan": self.nan,
+            "min": number(self.min),
+            "max": number(self.max),
+            "mean": number(self.mean()),
+            "std": number(self.std()),
+        })
+    }
+}
+
+/// Compute the statistics of a hyperslab of a variable, reading it a batch at a time
+pub fn compute_stats(store: &ZarrStore, var: &Variable, slab: &Hyperslab) -> Result<VariableStats> {
+    let mask = ValueMask::new(var);
+    let mut stats = VariableStats::default();
+    store
+        .read_hyperslab_batches(var, slab, |data| {
+            stats.update(&mask, data);
+            Ok(())
+        })
+        .with_context(|| format!("Failed to read variable '{}'", var.path))?;
+    Ok(stats)
+}
+
+/// Aligned text table with one row per variable
+pub fn render_table(rows: &[(String, VariableStats)]) -> String {
+    let number = |v: Option<f64>| v.map_or_else(|| "-".to_string(), |v| format_significant(v, 6));
+    let mut table = vec![
+        [
+            "variable", "valid", "missing", "nan", "min", "max", "mean", "std",
+        ]
+        .map(String::from)
+        .to_vec(),
+    ];
+    for (name, stats) in rows {
+        table.push(vec![
+            name.clone(),
+            stats.valid.to_string(),
+            stats.missing.to_string(),
+            stats.nan.to_string(),
+            number(stats.min),
+            number(stats.max),
+            number(stats.mean()),
+            number(stats.std()),
+        ]);
+    }
+    format_table(&table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::CodecPipeline;
+
+    fn variable(attributes: &[(&str, AttributeValue)]) -> Variable {
+        Variable {
+            name: "t".to_string(),
+            path: "t".to_string(),
+            dtype: "<i2".to_string(),
+            shape: vec![8],
+            chunks: vec![8],
+            codecs: CodecPipeline::default(),
+            fill_value: Some(AttributeValue::Number(-1.0)),
+            order: "C".to_string(),
+            dimension_separator: ".".to_string(),
+            attributes: attributes
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect(),
+            dimensions: vec![],
+        }
+    }
+
+    #[test]
+    fn test_stats_mask_and_unpack() {
+        let var = variable(&[
+            ("missing_value", AttributeValue::Number(-2.0)),
+            (
+                "valid_range",
+                AttributeValue::Array(vec![
+                    AttributeValue::Number(0.0),
+                    AttributeValue::Number(100.0),
+                ]),
+            ),
+            ("scale_factor", AttributeValue::Number(0.5)),
+            ("add_offset", AttributeValue::Number(10.0)),
+        ]);
+        let mask = ValueMask::new(&var);
+        let mut stats = VariableStats::default();
+        stats.update(&mask, &[-1.0, -2.0, 2.0, 4.0]);
+        stats.update(&mask, &[101.0, f64::NAN, 6.0, 0.0]);
+
+        assert_eq!((stats.valid, stats.missing, stats.nan), (4, 3, 1));
+        // 0, 2, 4, 6 unpacked to 10, 11, 12, 13
+        assert_eq!(stats.min, Some(10.0));
+        assert_eq!(stats.max, Some(13.0));
+        assert_eq!(stats.mean(), Some(11.5));
+        assert!((stats.std().unwrap() - 1.25f64.sqrt()).abs() < 1e-12);
+        assert_eq!(stats.to_json()["nan"], 1);
+
+        let table = render_table(&[("t".to_string(), stats)]);
+        assert_eq!(
+            table,
+            "variable  valid  missing  nan  min  max  mean      std\n\
+             t             4        3    1   10   13  11.5  1.11803\n"
+        );
+    }
+
+    #[test]
+    fn test_stats_without_valid_values() {
+        let var = variable(&[("_FillValue", AttributeValue::String("NaN".to_string()))]);
+        let mut stats = VariableStats::default();
+        stats.update(&ValueMask::new(&var), &[f64::NAN, -1.0]);
+
+        // NaN is the fill value here, so it is counted as missing
+        assert_eq!((stats.valid, stats.missing, stats.nan), (0, 2, 0));
+        assert_eq!(stats.mean(), None);
+        assert_eq!(stats.to_json()["min"], Value::Null);
+    }
+}
@@ -0,0 +1,183 @@
+use super::Storage;
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Read-only storage backed by a zip archive (e.g. written by zarr-python's `ZipStore`)
+pub struct ZipStorage {
+    archive: Mutex<zip::ZipArchive<File>>,
+    /// Directory inside the archive that holds the store root (empty if at the top level)
+    prefix: String,
+}
+
+impl ZipStorage {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open zip archive '{}'", path.display()))?;
+        let archive = zip::ZipArchive::new(file)
+            .with_context(|| format!("'{}' is not a valid zip archive", path.display()))?;
+
+        let prefix = Self::detect_root_prefix(&archive);
+
+        Ok(Self {
+            archive: Mutex::new(archive),
+            prefix,
+        })
+    }
+
+    /// Archives created by zipping a store directory (`zip -r store.zip store.zarr`) put
+    /// everything under a single top-level folder; treat that folder as the store root.
+    fn detect_root_prefix(archive: &zip::ZipArchive<File>) -> String {
+        const ROOT_KEYS: [&str; 4] = [".zgroup", ".zarray", ".zmetadata", "zarr.json"];
+
+        let names: Vec<&str> = archive.file_names().collect();
+        if names.iter().any(|n| ROOT_KEYS.contains(n)) {
+            return String::new();
+        }
+
+        let mut top_level = names
+            .iter()
+            .filter_map(|n| n.split_once('/').map(|(first, _)| first));
+        let Some(first) = top_level.next() else {
+            return String::new();
+        };
+        if !top_level.all(|d| d == first) {
+            return String::new();
+        }
+
+        let prefix = format!("{}/", first);
+        if ROOT_KEYS
+            .iter()
+            .any(|k| names.contains(&format!("{}{}", prefix, k).as_str()))
+        {
+            prefix
+        } else {
+            String::new()
+        }
+    }
+}
+
+impl Storage for ZipStorage {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let mut archive = self
+            .archive
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Zip archive lock poisoned"))?;
+
+        let name = format!("{}{}", self.prefix, key);
+        let mut entry = match archive.by_name(&name) {
+            Ok(entry) => entry,
+            Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to read '{}' from zip archive", key));
+            }
+        };
+
+        if entry.is_dir() {
+            return Ok(None);
+        }
+
+        let mut data = Vec::with_capacity(entry.size() as usize);
+        entry
+            .read_to_end(&mut data)
+            .with_context(|| format!("Failed to decompress '{}' from zip archive", key))?;
+        Ok(Some(data))
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let archive = self
+            .archive
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Zip archive lock poisoned"))?;
+
+        let full_prefix = format!("{}{}", self.prefix, prefix);
+        let mut keys: Vec<String> = archive
+            .file_names()
+            .filter(|n| !n.ends_with('/') && n.starts_with(&full_prefix))
+            .map(|n| n[self.prefix.len()..].to_string())
+            .collect();
+        keys.sort();
+        Ok(keys)
+    }
+
+    fn exists(&self, key: &str) -> Result<bool> {
+        let archive = self
+            .archive
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Zip archive lock poisoned"))?;
+        Ok(archive
+            .index_for_name(&format!("{}{}", self.prefix, key))
+            .is_some())
+    }
+
+    fn size(&self, key: &str) -> Result<Option<u64>> {
+        let mut archive = self
+            .archive
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Zip archive lock poisoned"))?;
+        match archive.by_name(&format!("{}{}", self.prefix, key)) {
+            Ok(entry) if !entry.is_dir() => Ok(Some(entry.size())),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+        let file = File::create(path).unwrap();
+        let mut writer = zip::ZipWriter::new(file);
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        for (name, data) in entries {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn test_zip_storage_get_and_list() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let zip_path = dir.path().join("store.zip");
+        write_zip(
+            &zip_path,
+            &[
+                (".zgroup", br#"{"zarr_format": 2}"#),
+                ("a/.zarray", b"{}"),
+                ("a/0", &[1, 2, 3]),
+            ],
+        );
+
+        let storage = ZipStorage::open(&zip_path).unwrap();
+        assert_eq!(storage.get("a/0").unwrap(), Some(vec![1, 2, 3]));
+        assert_eq!(storage.get("missing").unwrap(), None);
+        assert!(storage.exists(".zgroup").unwrap());
+        assert_eq!(storage.size("a/0").unwrap(), Some(3));
+        assert_eq!(storage.list("a/").unwrap(), vec!["a/.zarray", "a/0"]);
+    }
+
+    #[test]
+    fn test_zip_storage_nested_root() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let zip_path = dir.path().join("store.zip");
+        write_zip(
+            &zip_path,
+            &[
+                ("store.zarr/.zgroup", br#"{"zarr_format": 2}"#),
+                ("store.zarr/a/.zarray", b"{}"),
+            ],
+        );
+
+        let storage = ZipStorage::open(&zip_path).unwrap();
+        assert!(storage.exists(".zgroup").unwrap());
+        assert_eq!(storage.list("").unwrap(), vec![".zgroup", "a/.zarray"]);
+    }
+}
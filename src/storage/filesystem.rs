@@ -0,0 +1,151 @@
+use super::Storage;
+use anyhow::{Context, Result};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Storage backed by a directory on the local filesystem
+pub struct FilesystemStorage {
+    root: PathBuf,
+}
+
+impl FilesystemStorage {
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Collect the keys below `dir` (whose key is `prefix`), skipping entries of `dir` itself
+    /// whose name does not start with `name_prefix`
+    fn collect_keys(
+        &self,
+        dir: &Path,
+        prefix: &str,
+        name_prefix: &str,
+        keys: &mut Vec<String>,
+    ) -> Result<()> {
+        let entries = fs::read_dir(dir)
+            .with_context(|| format!("Failed to read directory: {}", dir.display()))?;
+
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.starts_with(name_prefix) {
+                continue;
+            }
+            let key = if prefix.is_empty() {
+                name
+            } else {
+                format!("{}/{}", prefix, name)
+            };
+
+            if entry.path().is_dir() {
+                self.collect_keys(&entry.path(), &key, "", keys)?;
+            } else {
+                keys.push(key);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Storage for FilesystemStorage {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let path = self.root.join(key);
+        match fs::read(&path) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(_) if path.is_dir() => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to read '{}'", path.display())),
+        }
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        // Only walk the directory the prefix points into, e.g. `tas` for `tas/0.` or `tas/`
+        let (dir, name_prefix) = prefix.rsplit_once('/').unwrap_or(("", prefix));
+        let dir_path = self.root.join(dir);
+        let mut keys = Vec::new();
+        if dir.is_empty() || dir_path.is_dir() {
+            self.collect_keys(&dir_path, dir, name_prefix, &mut keys)?;
+        }
+        keys.sort();
+        Ok(keys)
+    }
+
+    fn get_range(&self, key: &str, offset: u64, length: Option<u64>) -> Result<Option<Vec<u8>>> {
+        read_file_range(&self.root.join(key), offset, length)
+    }
+
+    fn exists(&self, key: &str) -> Result<bool> {
+        Ok(self.root.join(key).is_file())
+    }
+
+    fn size(&self, key: &str) -> Result<Option<u64>> {
+        match fs::metadata(self.root.join(key)) {
+            Ok(m) if m.is_file() => Ok(Some(m.len())),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Read `length` bytes (or the rest of the file) at `offset` of a local file
+pub(super) fn read_file_range(
+    path: &Path,
+    offset: u64,
+    length: Option<u64>,
+) -> Result<Option<Vec<u8>>> {
+    let mut file = match File::open(path) {
+        Ok(file) if path.is_file() => file,
+        Ok(_) => return Ok(None),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to open '{}'", path.display()));
+        }
+    };
+
+    file.seek(SeekFrom::Start(offset))
+        .with_context(|| format!("Failed to seek in '{}'", path.display()))?;
+    let mut data = Vec::new();
+    match length {
+        Some(len) => {
+            data.resize(len as usize, 0);
+            file.read_exact(&mut data)
+        }
+        None => file.read_to_end(&mut data).map(|_| ()),
+    }
+    .with_context(|| format!("Failed to read byte range from '{}'", path.display()))?;
+    Ok(Some(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        for key in [
+            "tas/.zarray",
+            "tas/0.0",
+            "tas/0.1",
+            "tas/1.0",
+            "time/0",
+            ".zgroup",
+        ] {
+            let path = dir.path().join(key);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, b"x").unwrap();
+        }
+        let storage = FilesystemStorage::new(dir.path());
+
+        assert_eq!(storage.list("").unwrap().len(), 6);
+        assert_eq!(
+            storage.list("tas/").unwrap(),
+            ["tas/.zarray", "tas/0.0", "tas/0.1", "tas/1.0"]
+        );
+        assert_eq!(storage.list("tas/0.").unwrap(), ["tas/0.0", "tas/0.1"]);
+        assert_eq!(storage.list("t").unwrap().len(), 5);
+        assert!(storage.list("pr/").unwrap().is_empty());
+    }
+}
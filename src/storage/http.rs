@@ -0,0 +1,178 @@
+use super::{ListingUnsupported, Storage, slice_range};
+use anyhow::{Context, Result};
+use std::io::Read;
+
+/// Read-only storage served by a plain HTTP(S) file server.
+///
+/// HTTP has no standard way to list a directory, so stores opened this way need consolidated
+/// metadata. Partial reads (e.g. of sharded chunks) use `Range` requests.
+pub struct HttpStorage {
+    base_url: String,
+    agent: ureq::Agent,
+}
+
+impl HttpStorage {
+    pub fn new(url: &str) -> Self {
+        Self {
+            base_url: url.trim_end_matches('/').to_string(),
+            agent: ureq::AgentBuilder::new().build(),
+        }
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("{}/{}", self.base_url, key)
+    }
+}
+
+impl Storage for HttpStorage {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        http_get(self.agent.get(&self.url(key)))
+    }
+
+    fn list(&self, _prefix: &str) -> Result<Vec<String>> {
+        Err(ListingUnsupported(format!(
+            "HTTP stores cannot be listed; '{}' must provide consolidated metadata (.zmetadata, or consolidated_metadata in zarr.json)",
+            self.base_url
+        ))
+        .into())
+    }
+
+    fn get_range(&self, key: &str, offset: u64, length: Option<u64>) -> Result<Option<Vec<u8>>> {
+        http_get_range(self.agent.get(&self.url(key)), key, offset, length)
+    }
+
+    fn exists(&self, key: &str) -> Result<bool> {
+        Ok(http_call(self.agent.head(&self.url(key)))?.is_some())
+    }
+
+    fn size(&self, key: &str) -> Result<Option<u64>> {
+        let Some(response) = http_call(self.agent.head(&self.url(key)))? else {
+            return Ok(None);
+        };
+        match content_length(&response) {
+            Some(size) => Ok(Some(size)),
+            // No length advertised: fall back to downloading the value
+            None => Ok(self.get(key)?.map(|v| v.len() as u64)),
+        }
+    }
+}
+
+/// Send a request, mapping `404 Not Found` to `None`
+pub(super) fn http_call(request: ureq::Request) -> Result<Option<ureq::Response>> {
+    let url = request.url().to_string();
+    match request.call() {
+        Ok(response) => Ok(Some(response)),
+        Err(ureq::Error::Status(404, _)) => Ok(None),
+        Err(ureq::Error::Status(code, response)) => Err(anyhow::anyhow!(
+            "HTTP {} {} for '{}'",
+            code,
+            response.status_text(),
+            url
+        )),
+        Err(e) => Err(e).with_context(|| format!("HTTP request to '{}' failed", url)),
+    }
+}
+
+pub(super) fn http_get(request: ureq::Request) -> Result<Option<Vec<u8>>> {
+    match http_call(request)? {
+        Some(response) => read_body(response).map(Some),
+        None => Ok(None),
+    }
+}
+
+pub(super) fn http_get_range(
+    request: ureq::Request,
+    key: &str,
+    offset: u64,
+    length: Option<u64>,
+) -> Result<Option<Vec<u8>>> {
+    let range = match length {
+        Some(0) => return Ok(Some(Vec::new())),
+        Some(len) => format!("bytes={}-{}", offset, offset + len - 1),
+        None => format!("bytes={}-", offset),
+    };
+
+    let Some(response) = http_call(request.set("Range", &range))? else {
+        return Ok(None);
+    };
+
+    // Servers that ignore the Range header send the whole value with 200 OK
+    let partial = response.status() == 206;
+    let data = read_body(response)?;
+    if partial {
+        Ok(Some(data))
+    } else {
+        slice_range(&data, offset, length, key).map(|bytes| Some(bytes.to_vec()))
+    }
+}
+
+fn read_body(response: ureq::Response) -> Result<Vec<u8>> {
+    let url = response.get_url().to_string();
+    let mut data = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut data)
+        .with_context(|| format!("Failed to read response body from '{}'", url))?;
+    Ok(data)
+}
+
+pub(super) fn content_length(response: &ureq::Response) -> Option<u64> {
+    response
+        .header("Content-Length")
+        .and_then(|v| v.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serve `files` over HTTP, honouring `Range: bytes=a-b` requests
+    fn serve(files: Vec<(&'static str, Vec<u8>)>) -> String {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+        std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let key = request.url().trim_start_matches('/').to_string();
+                let Some((_, data)) = files.iter().find(|(name, _)| *name == key) else {
+                    request.respond(tiny_http::Response::empty(404)).unwrap();
+                    continue;
+                };
+                let range = request
+                    .headers()
+                    .iter()
+                    .find(|h| h.field.equiv("Range"))
+                    .map(|h| h.value.to_string());
+                let response = match range.as_deref().and_then(|r| r.strip_prefix("bytes=")) {
+                    Some(spec) => {
+                        let (start, end) = spec.split_once('-').unwrap();
+                        let start: usize = start.parse().unwrap();
+                        let end = end.parse::<usize>().map_or(data.len(), |e| e + 1);
+                        tiny_http::Response::from_data(data[start..end].to_vec())
+                            .with_status_code(206)
+                    }
+                    None => tiny_http::Response::from_data(data.clone()),
+                };
+                request.respond(response).unwrap();
+            }
+        });
+        format!("http://127.0.0.1:{}/store/", port)
+    }
+
+    #[test]
+    fn test_http_storage_get_and_ranges() {
+        let url = serve(vec![("store/a/0", (0u8..10).collect())]);
+        let storage = HttpStorage::new(&url);
+
+        assert_eq!(storage.get("a/0").unwrap(), Some((0u8..10).collect()));
+        assert_eq!(storage.get("missing").unwrap(), None);
+        assert!(storage.exists("a/0").unwrap());
+        assert!(!storage.exists("missing").unwrap());
+        assert_eq!(storage.size("a/0").unwrap(), Some(10));
+        assert_eq!(
+            storage.get_range("a/0", 2, Some(3)).unwrap(),
+            Some(vec![2, 3, 4])
+        );
+        assert_eq!(storage.get_range("a/0", 8, None).unwrap(), Some(vec![8, 9]));
+        assert!(storage.list("").is_err());
+    }
+}
@@ -0,0 +1,294 @@
+use super::filesystem::read_file_range;
+use super::http::http_get_range;
+use super::{Storage, is_http_url, slice_range};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A single entry of a kerchunk reference set
+enum Reference {
+    /// Value stored in the reference file itself (plain text or `base64:`-prefixed bytes)
+    Inline(Vec<u8>),
+    /// Bytes `offset..offset + length` of another file (the whole file if `length` is `None`)
+    Range {
+        url: String,
+        offset: u64,
+        length: Option<u64>,
+    },
+}
+
+/// Read-only virtual Zarr v2 store described by a kerchunk reference JSON file (v0 or v1).
+///
+/// Chunk references are resolved to byte ranges of local files (absolute, `file://` or relative
+/// to the reference file) or of `http(s)://` URLs.
+pub struct KerchunkStorage {
+    refs: HashMap<String, Reference>,
+    base_dir: PathBuf,
+    agent: ureq::Agent,
+}
+
+impl KerchunkStorage {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let data = fs::read(path)
+            .with_context(|| format!("Failed to read reference file '{}'", path.display()))?;
+        let document: serde_json::Value = serde_json::from_slice(&data)
+            .with_context(|| format!("Invalid kerchunk reference JSON in '{}'", path.display()))?;
+
+        let base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Self::from_json(&document, base_dir)
+            .with_context(|| format!("Invalid kerchunk references in '{}'", path.display()))
+    }
+
+    fn from_json(document: &serde_json::Value, base_dir: PathBuf) -> Result<Self> {
+        let object = document
+            .as_object()
+            .ok_or_else(|| anyhow::anyhow!("Reference file must contain a JSON object"))?;
+
+        // Version 1 nests the references under "refs" and may define URL templates
+        let (entries, templates) = match object.get("version").and_then(|v| v.as_u64()) {
+            Some(1) => {
+                if object
+                    .get("gen")
+                    .and_then(|g| g.as_array())
+                    .is_some_and(|g| !g.is_empty())
+                {
+                    return Err(anyhow::anyhow!(
+                        "Generated references (\"gen\") are not supported; expand them with kerchunk first"
+                    ));
+                }
+                let entries = object
+                    .get("refs")
+                    .and_then(|r| r.as_object())
+                    .ok_or_else(|| anyhow::anyhow!("Version 1 references are missing \"refs\""))?;
+                let templates: HashMap<String, String> = object
+                    .get("templates")
+                    .and_then(|t| t.as_object())
+                    .map(|t| {
+                        t.iter()
+                            .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                (entries, templates)
+            }
+            Some(version) => {
+                return Err(anyhow::anyhow!(
+                    "Unsupported kerchunk reference version {}",
+                    version
+                ));
+            }
+            None => (object, HashMap::new()),
+        };
+
+        let mut refs = HashMap::new();
+        for (key, value) in entries {
+            let reference = Self::parse_reference(value, &templates)
+                .with_context(|| format!("Invalid reference for key '{}'", key))?;
+            refs.insert(key.clone(), reference);
+        }
+
+        Ok(Self {
+            refs,
+            base_dir,
+            agent: ureq::AgentBuilder::new().build(),
+        })
+    }
+
+    fn parse_reference(
+        value: &serde_json::Value,
+        templates: &HashMap<String, String>,
+    ) -> Result<Reference> {
+        use base64::Engine;
+        use serde_json::Value;
+
+        match value {
+            Value::String(text) => match text.strip_prefix("base64:") {
+                Some(encoded) => base64::engine::general_purpose::STANDARD
+                    .decode(encoded)
+                    .map(Reference::Inline)
+                    .context("Invalid base64 data"),
+                None => Ok(Reference::Inline(text.as_bytes().to_vec())),
+            },
+            Value::Array(parts) => {
+                let url = parts
+                    .first()
+                    .and_then(|u| u.as_str())
+                    .ok_or_else(|| anyhow::anyhow!("Reference must start with a URL"))?;
+                let url = templates
+                    .iter()
+                    .fold(url.to_string(), |url, (name, value)| {
+                        url.replace(&format!("{{{{{}}}}}", name), value)
+                            .replace(&format!("{{{{ {} }}}}", name), value)
+                    });
+
+                match parts.as_slice() {
+                    [_] => Ok(Reference::Range {
+                        url,
+                        offset: 0,
+                        length: None,
+                    }),
+                    [_, offset, length] => Ok(Reference::Range {
+                        url,
+                        offset: offset
+                            .as_u64()
+                            .ok_or_else(|| anyhow::anyhow!("Invalid offset {}", offset))?,
+                        length: Some(
+                            length
+                                .as_u64()
+                                .ok_or_else(|| anyhow::anyhow!("Invalid length {}", length))?,
+                        ),
+                    }),
+                    _ => Err(anyhow::anyhow!(
+                        "Expected [url] or [url, offset, length], got {} elements",
+                        parts.len()
+                    )),
+                }
+            }
+            // Some writers embed metadata documents as JSON rather than strings
+            Value::Object(_) => Ok(Reference::Inline(serde_json::to_vec(value)?)),
+            other => Err(anyhow::anyhow!("Unsupported reference value {}", other)),
+        }
+    }
+
+    /// Read `length` bytes at `offset` of the file or URL a reference points to
+    fn read_target(&self, url: &str, offset: u64, length: Option<u64>) -> Result<Vec<u8>> {
+        if is_http_url(url) {
+            return http_get_range(self.agent.get(url), url, offset, length)?
+                .ok_or_else(|| anyhow::anyhow!("Referenced URL '{}' not found", url));
+        }
+
+        let path = match url.strip_prefix("file://") {
+            Some(path) => PathBuf::from(path),
+            None if url.contains("://") => {
+                return Err(anyhow::anyhow!(
+                    "Unsupported reference target '{}' (only local files and http(s) URLs can be read)",
+                    url
+                ));
+            }
+            None => self.base_dir.join(url),
+        };
+
+        read_file_range(&path, offset, length)?
+            .ok_or_else(|| anyhow::anyhow!("Referenced file '{}' not found", path.display()))
+    }
+}
+
+impl Storage for KerchunkStorage {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        self.get_range(key, 0, None)
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut keys: Vec<String> = self
+            .refs
+            .keys()
+            .filter(|k| k.starts_with(prefix))
+            .cloned()
+            .collect();
+        keys.sort();
+        Ok(keys)
+    }
+
+    fn get_range(&self, key: &str, offset: u64, length: Option<u64>) -> Result<Option<Vec<u8>>> {
+        match self.refs.get(key) {
+            None => Ok(None),
+            Some(Reference::Inline(data)) => {
+                slice_range(data, offset, length, key).map(|bytes| Some(bytes.to_vec()))
+            }
+            Some(Reference::Range {
+                url,
+                offset: start,
+                length: total,
+            }) => {
+                // Bound the range by the referenced length, like `slice_range` does for values
+                let length = match *total {
+                    Some(total) => {
+                        let end = length.map_or(total, |length| offset.saturating_add(length));
+                        if offset > total || end > total {
+                            return Err(anyhow::anyhow!(
+                                "Byte range {}..{} is out of bounds for '{}' ({} bytes)",
+                                offset,
+                                end,
+                                key,
+                                total
+                            ));
+                        }
+                        Some(end - offset)
+                    }
+                    None => length,
+                };
+                self.read_target(url, start + offset, length)
+                    .with_context(|| format!("Failed to resolve reference for '{}'", key))
+                    .map(Some)
+            }
+        }
+    }
+
+    fn exists(&self, key: &str) -> Result<bool> {
+        Ok(self.refs.contains_key(key))
+    }
+
+    fn size(&self, key: &str) -> Result<Option<u64>> {
+        match self.refs.get(key) {
+            None => Ok(None),
+            Some(Reference::Inline(data)) => Ok(Some(data.len() as u64)),
+            Some(Reference::Range {
+                length: Some(length),
+                ..
+            }) => Ok(Some(*length)),
+            Some(Reference::Range { .. }) => Ok(self.get(key)?.map(|v| v.len() as u64)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kerchunk_references() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(dir.path().join("data.nc"), b"HEADERabcdefTRAILER").unwrap();
+
+        // Version 1 with a URL template, inline text and inline base64 data
+        let v1 = serde_json::json!({
+            "version": 1,
+            "templates": {"u": "data.nc"},
+            "refs": {
+                ".zgroup": "{\"zarr_format\": 2}",
+                "a/0": ["{{u}}", 6, 6],
+                "a/1": "base64:AQID",
+                "whole": ["{{ u }}"]
+            }
+        });
+        let storage = KerchunkStorage::from_json(&v1, dir.path().to_path_buf()).unwrap();
+        assert_eq!(
+            storage.get(".zgroup").unwrap().unwrap(),
+            br#"{"zarr_format": 2}"#
+        );
+        assert_eq!(storage.get("a/0").unwrap().unwrap(), b"abcdef");
+        assert_eq!(
+            storage.get_range("a/0", 2, Some(2)).unwrap().unwrap(),
+            b"cd"
+        );
+        assert_eq!(storage.get_range("a/0", 4, None).unwrap().unwrap(), b"ef");
+        // Ranges beyond the referenced bytes are errors, not reads of the next bytes of the file
+        assert!(storage.get_range("a/0", 4, Some(4)).is_err());
+        assert!(storage.get_range("a/0", 7, None).is_err());
+        assert_eq!(storage.get("a/1").unwrap().unwrap(), vec![1, 2, 3]);
+        assert_eq!(storage.size("a/0").unwrap(), Some(6));
+        assert_eq!(storage.get("whole").unwrap().unwrap().len(), 19);
+        assert_eq!(storage.list("a/").unwrap(), vec!["a/0", "a/1"]);
+        assert_eq!(storage.get("missing").unwrap(), None);
+
+        // Version 0 is a flat key -> reference mapping
+        let v0 = serde_json::json!({".zgroup": "{}", "b/0": ["data.nc", 0, 6]});
+        let storage = KerchunkStorage::from_json(&v0, dir.path().to_path_buf()).unwrap();
+        assert_eq!(storage.get("b/0").unwrap().unwrap(), b"HEADER");
+
+        let bad = serde_json::json!({"version": 1, "refs": {"k": ["data.nc", 1]}});
+        assert!(KerchunkStorage::from_json(&bad, dir.path().to_path_buf()).is_err());
+    }
+}
@@ -0,0 +1,56 @@
+use super::Storage;
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+/// Storage held entirely in memory, e.g. for building stores in tests without touching disk
+#[derive(Debug, Default, Clone)]
+#[allow(dead_code)]
+pub struct MemoryStorage {
+    values: BTreeMap<String, Vec<u8>>,
+}
+
+#[allow(dead_code)]
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store `value` at `key`, replacing any existing value
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<Vec<u8>>) {
+        self.values.insert(key.into(), value.into());
+    }
+}
+
+impl<K: Into<String>, V: Into<Vec<u8>>> FromIterator<(K, V)> for MemoryStorage {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self {
+            values: iter
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        }
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.values.get(key).cloned())
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        Ok(self
+            .values
+            .keys()
+            .filter(|k| k.starts_with(prefix))
+            .cloned()
+            .collect())
+    }
+
+    fn exists(&self, key: &str) -> Result<bool> {
+        Ok(self.values.contains_key(key))
+    }
+
+    fn size(&self, key: &str) -> Result<Option<u64>> {
+        Ok(self.values.get(key).map(|v| v.len() as u64))
+    }
+}
@@ -0,0 +1,224 @@
+//! Key/value storage backends that Zarr stores are read through.
+//!
+//! [`ZarrStore`](crate::store::ZarrStore) performs all metadata and chunk reads through the
+//! [`Storage`] trait, so library users can plug in their own backends with
+//! [`ZarrStore::from_storage`](crate::store::ZarrStore::from_storage).
+
+mod archive;
+mod filesystem;
+mod http;
+mod kerchunk;
+mod memory;
+mod s3;
+
+pub use archive::ZipStorage;
+pub use filesystem::FilesystemStorage;
+pub use http::HttpStorage;
+pub use kerchunk::KerchunkStorage;
+// Only used by library consumers and tests, not by the binary
+#[allow(unused_imports)]
+pub use memory::MemoryStorage;
+pub use s3::S3Storage;
+
+use anyhow::Result;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Error returned by [`Storage::list`] for backends that cannot enumerate their keys (HTTP).
+///
+/// Callers that can do without a listing check for it with `error.downcast_ref()`; any other
+/// listing error is a real failure.
+#[derive(Debug)]
+pub struct ListingUnsupported(pub String);
+
+impl fmt::Display for ListingUnsupported {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ListingUnsupported {}
+
+/// Key/value access to the bytes of a Zarr store.
+///
+/// Keys are `/`-separated paths relative to the store root (e.g. `temperature/.zarray`).
+/// Only [`get`](Storage::get) and [`list`](Storage::list) are required; backends that can read
+/// partial values or query sizes cheaply should override the other methods.
+pub trait Storage: Send + Sync {
+    /// Read the value stored at `key`, or `None` if the key does not exist.
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    /// List all keys that start with `prefix` (use `""` to list the whole store).
+    ///
+    /// Backends that cannot list keys return a [`ListingUnsupported`] error.
+    fn list(&self, prefix: &str) -> Result<Vec<String>>;
+
+    /// Read `length` bytes (or everything, if `None`) starting at `offset` of the value at `key`.
+    fn get_range(&self, key: &str, offset: u64, length: Option<u64>) -> Result<Option<Vec<u8>>> {
+        let Some(value) = self.get(key)? else {
+            return Ok(None);
+        };
+        slice_range(&value, offset, length, key).map(|bytes| Some(bytes.to_vec()))
+    }
+
+    /// Whether a value is stored at `key`.
+    fn exists(&self, key: &str) -> Result<bool> {
+        Ok(self.get(key)?.is_some())
+    }
+
+    /// Size in bytes of the value at `key`, or `None` if the key does not exist.
+    fn size(&self, key: &str) -> Result<Option<u64>> {
+        Ok(self.get(key)?.map(|v| v.len() as u64))
+    }
+}
+
+fn slice_range<'a>(
+    value: &'a [u8],
+    offset: u64,
+    length: Option<u64>,
+    key: &str,
+) -> Result<&'a [u8]> {
+    let start = offset as usize;
+    let end = length.map_or(value.len(), |len| start + len as usize);
+    value.get(start..end).ok_or_else(|| {
+        anyhow::anyhow!(
+            "Byte range {}..{} is out of bounds for '{}' ({} bytes)",
+            start,
+            end,
+            key,
+            value.len()
+        )
+    })
+}
+
+/// Exposes a [`Storage`] to the zarrs crate so arrays can be decoded from any backend.
+pub(crate) struct ZarrsStorageAdapter {
+    inner: Arc<dyn Storage>,
+}
+
+impl ZarrsStorageAdapter {
+    pub fn new(inner: Arc<dyn Storage>) -> Self {
+        Self { inner }
+    }
+}
+
+impl zarrs::storage::ReadableStorageTraits for ZarrsStorageAdapter {
+    fn get_partial_values_key(
+        &self,
+        key: &zarrs::storage::StoreKey,
+        byte_ranges: &[zarrs::byte_range::ByteRange],
+    ) -> Result<Option<Vec<zarrs::storage::Bytes>>, zarrs::storage::StorageError> {
+        use zarrs::byte_range::ByteRange;
+
+        let key = key.as_str();
+        let to_storage_error =
+            |e: anyhow::Error| zarrs::storage::StorageError::Other(format!("{:#}", e));
+
+        // Whole-value reads (the common case for unsharded chunks and metadata)
+        if let [ByteRange::FromStart(0, None)] = byte_ranges {
+            return Ok(self
+                .inner
+                .get(key)
+                .map_err(to_storage_error)?
+                .map(|value| vec![value.into()]));
+        }
+
+        let mut size = None;
+        let mut parts = Vec::with_capacity(byte_ranges.len());
+        for byte_range in byte_ranges {
+            let (offset, length) = match byte_range {
+                ByteRange::FromStart(offset, length) => (*offset, *length),
+                ByteRange::FromEnd(..) => {
+                    // Ranges relative to the end (e.g. a shard index) need the value size
+                    if size.is_none() {
+                        size = self.inner.size(key).map_err(to_storage_error)?;
+                    }
+                    let Some(size) = size else {
+                        return Ok(None);
+                    };
+                    (byte_range.start(size), Some(byte_range.length(size)))
+                }
+            };
+
+            match self
+                .inner
+                .get_range(key, offset, length)
+                .map_err(to_storage_error)?
+            {
+                Some(bytes) => parts.push(bytes.into()),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(parts))
+    }
+
+    fn size_key(
+        &self,
+        key: &zarrs::storage::StoreKey,
+    ) -> Result<Option<u64>, zarrs::storage::StorageError> {
+        self.inner
+            .size(key.as_str())
+            .map_err(|e| zarrs::storage::StorageError::Other(format!("{:#}", e)))
+    }
+}
+
+pub(crate) fn is_http_url(location: &str) -> bool {
+    location.starts_with("http://") || location.starts_with("https://")
+}
+
+pub(crate) fn is_s3_url(location: &str) -> bool {
+    location.starts_with("s3://")
+}
+
+/// Whether `path` is a kerchunk reference JSON file rather than a store directory
+pub(crate) fn is_kerchunk_path(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+/// Whether `location` names a remote store rather than a local path
+pub(crate) fn is_remote_location(location: &str) -> bool {
+    is_http_url(location) || is_s3_url(location)
+}
+
+pub(crate) fn is_zip_path(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zarrs::byte_range::ByteRange;
+    use zarrs::storage::{ReadableStorageTraits, StoreKey};
+
+    #[test]
+    fn test_zarrs_adapter_byte_ranges() {
+        let mut storage = MemoryStorage::new();
+        storage.insert("a/0", (0u8..10).collect::<Vec<_>>());
+        let adapter = ZarrsStorageAdapter::new(Arc::new(storage));
+
+        let key = StoreKey::new("a/0").unwrap();
+        let parts = adapter
+            .get_partial_values_key(
+                &key,
+                &[
+                    ByteRange::FromStart(1, Some(2)),
+                    ByteRange::FromEnd(0, Some(3)),
+                ],
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(parts[0].as_ref(), &[1, 2]);
+        assert_eq!(parts[1].as_ref(), &[7, 8, 9]);
+        assert_eq!(adapter.size_key(&key).unwrap(), Some(10));
+
+        let missing = StoreKey::new("a/1").unwrap();
+        assert!(adapter.get(&missing).unwrap().is_none());
+    }
+}
@@ -0,0 +1,262 @@
+use super::Storage;
+use super::http::{content_length, http_call, http_get, http_get_range};
+use anyhow::{Context, Result};
+use rusty_s3::S3Action;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How long presigned S3 request URLs stay valid
+const S3_SIGNATURE_TTL: Duration = Duration::from_secs(300);
+
+/// Read-only storage in an S3-compatible object store, addressed as `s3://bucket/prefix`.
+///
+/// Credentials are taken from `AWS_ACCESS_KEY_ID`/`AWS_SECRET_ACCESS_KEY` (plus optional
+/// `AWS_SESSION_TOKEN`) or from the shared credentials file for `AWS_PROFILE`; without any,
+/// requests are sent unsigned, which is enough for public buckets. `AWS_ENDPOINT_URL` selects a
+/// custom (e.g. MinIO) endpoint, addressed path-style.
+pub struct S3Storage {
+    bucket: rusty_s3::Bucket,
+    credentials: Option<rusty_s3::Credentials>,
+    /// Key prefix of the store root inside the bucket (empty, or ending in `/`)
+    prefix: String,
+    agent: ureq::Agent,
+}
+
+impl S3Storage {
+    pub fn open(location: &str) -> Result<Self> {
+        let (bucket_name, prefix) = parse_s3_url(location)?;
+
+        let profile = env_var("AWS_PROFILE").unwrap_or_else(|| "default".to_string());
+        let config_section = if profile == "default" {
+            profile.clone()
+        } else {
+            format!("profile {}", profile)
+        };
+
+        let region = env_var("AWS_REGION")
+            .or_else(|| env_var("AWS_DEFAULT_REGION"))
+            .or_else(|| {
+                aws_file_section(
+                    &aws_config_path("AWS_CONFIG_FILE", "config")?,
+                    &config_section,
+                )
+                .remove("region")
+            })
+            .unwrap_or_else(|| "us-east-1".to_string());
+
+        let (endpoint, url_style) =
+            match env_var("AWS_ENDPOINT_URL_S3").or_else(|| env_var("AWS_ENDPOINT_URL")) {
+                Some(endpoint) => (endpoint, rusty_s3::UrlStyle::Path),
+                None => (
+                    format!("https://s3.{}.amazonaws.com", region),
+                    rusty_s3::UrlStyle::VirtualHost,
+                ),
+            };
+        let endpoint: url::Url = endpoint
+            .parse()
+            .with_context(|| format!("Invalid S3 endpoint URL '{}'", endpoint))?;
+
+        let bucket = rusty_s3::Bucket::new(endpoint, url_style, bucket_name.clone(), region)
+            .map_err(|e| anyhow::anyhow!("Invalid S3 bucket '{}': {}", bucket_name, e))?;
+
+        let credentials = rusty_s3::Credentials::from_env().or_else(|| {
+            let mut section = aws_file_section(
+                &aws_config_path("AWS_SHARED_CREDENTIALS_FILE", "credentials")?,
+                &profile,
+            );
+            let key = section.remove("aws_access_key_id")?;
+            let secret = section.remove("aws_secret_access_key")?;
+            Some(match section.remove("aws_session_token") {
+                Some(token) => rusty_s3::Credentials::new_with_token(key, secret, token),
+                None => rusty_s3::Credentials::new(key, secret),
+            })
+        });
+
+        Ok(Self {
+            bucket,
+            credentials,
+            prefix,
+            agent: ureq::AgentBuilder::new().build(),
+        })
+    }
+
+    fn object_key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+
+    fn get_request(&self, key: &str) -> ureq::Request {
+        let object = self.object_key(key);
+        let url = self
+            .bucket
+            .get_object(self.credentials.as_ref(), &object)
+            .sign(S3_SIGNATURE_TTL);
+        self.agent.get(url.as_str())
+    }
+
+    fn head_request(&self, key: &str) -> ureq::Request {
+        let object = self.object_key(key);
+        let url = self
+            .bucket
+            .head_object(self.credentials.as_ref(), &object)
+            .sign(S3_SIGNATURE_TTL);
+        self.agent.head(url.as_str())
+    }
+}
+
+impl Storage for S3Storage {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        http_get(self.get_request(key))
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let full_prefix = self.object_key(prefix);
+        let mut keys = Vec::new();
+        let mut continuation_token: Option<String> = None;
+
+        loop {
+            let mut action = self.bucket.list_objects_v2(self.credentials.as_ref());
+            action.with_prefix(full_prefix.as_str());
+            if let Some(token) = &continuation_token {
+                action.with_continuation_token(token.as_str());
+            }
+
+            let url = action.sign(S3_SIGNATURE_TTL);
+            let body = http_get(self.agent.get(url.as_str()))?.ok_or_else(|| {
+                anyhow::anyhow!("S3 bucket '{}' does not exist", self.bucket.name())
+            })?;
+            let page =
+                rusty_s3::actions::ListObjectsV2::parse_response(&String::from_utf8_lossy(&body))
+                    .map_err(|e| anyhow::anyhow!("Invalid S3 ListObjectsV2 response: {}", e))?;
+
+            keys.extend(
+                page.contents
+                    .into_iter()
+                    .filter_map(|object| object.key.strip_prefix(&self.prefix).map(str::to_string)),
+            );
+
+            match page.next_continuation_token {
+                Some(token) => continuation_token = Some(token),
+                None => break,
+            }
+        }
+
+        keys.sort();
+        Ok(keys)
+    }
+
+    fn get_range(&self, key: &str, offset: u64, length: Option<u64>) -> Result<Option<Vec<u8>>> {
+        http_get_range(self.get_request(key), key, offset, length)
+    }
+
+    fn exists(&self, key: &str) -> Result<bool> {
+        Ok(http_call(self.head_request(key))?.is_some())
+    }
+
+    fn size(&self, key: &str) -> Result<Option<u64>> {
+        let Some(response) = http_call(self.head_request(key))? else {
+            return Ok(None);
+        };
+        match content_length(&response) {
+            Some(size) => Ok(Some(size)),
+            None => Ok(self.get(key)?.map(|v| v.len() as u64)),
+        }
+    }
+}
+
+/// Split `s3://bucket/prefix` into the bucket name and a key prefix (empty or ending in `/`)
+fn parse_s3_url(location: &str) -> Result<(String, String)> {
+    let rest = location
+        .strip_prefix("s3://")
+        .ok_or_else(|| anyhow::anyhow!("Not an S3 URL: '{}'", location))?;
+    let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+    if bucket.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Missing bucket name in S3 URL '{}'",
+            location
+        ));
+    }
+
+    let prefix = prefix.trim_matches('/');
+    let prefix = if prefix.is_empty() {
+        String::new()
+    } else {
+        format!("{}/", prefix)
+    };
+    Ok((bucket.to_string(), prefix))
+}
+
+fn env_var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+/// Location of an AWS shared config file, honouring its override variable
+fn aws_config_path(override_var: &str, file_name: &str) -> Option<PathBuf> {
+    if let Some(path) = env_var(override_var) {
+        return Some(PathBuf::from(path));
+    }
+    let home = env_var("HOME").or_else(|| env_var("USERPROFILE"))?;
+    Some(Path::new(&home).join(".aws").join(file_name))
+}
+
+/// Read the `key = value` pairs of one `[section]` of an AWS INI-style config file
+fn aws_file_section(path: &Path, section: &str) -> HashMap<String, String> {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return HashMap::new();
+    };
+
+    let mut values = HashMap::new();
+    let mut in_section = false;
+    for line in contents.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_section = name.trim() == section;
+        } else if in_section && let Some((key, value)) = line.split_once('=') {
+            values.insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_s3_url() {
+        assert_eq!(
+            parse_s3_url("s3://bucket/path/to/store.zarr/").unwrap(),
+            ("bucket".to_string(), "path/to/store.zarr/".to_string())
+        );
+        assert_eq!(
+            parse_s3_url("s3://bucket").unwrap(),
+            ("bucket".to_string(), String::new())
+        );
+        assert!(parse_s3_url("s3:///prefix").is_err());
+    }
+
+    #[test]
+    fn test_aws_file_section() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("credentials");
+        fs::write(
+            &path,
+            "[default]\naws_access_key_id = AKIDEFAULT\n\n# comment\n[profile other]\nregion=eu-west-1\n",
+        )
+        .unwrap();
+
+        let default = aws_file_section(&path, "default");
+        assert_eq!(default.get("aws_access_key_id").unwrap(), "AKIDEFAULT");
+        assert_eq!(
+            aws_file_section(&path, "profile other")
+                .get("region")
+                .unwrap(),
+            "eu-west-1"
+        );
+        assert!(aws_file_section(&path, "missing").is_empty());
+    }
+}
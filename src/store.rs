@@ -1,51 +1,130 @@
+use crate::codec::{Codec, CodecPipeline};
+use crate::hyperslab::Hyperslab;
 use crate::metadata::*;
+use crate::storage::{
+    FilesystemStorage, HttpStorage, KerchunkStorage, ListingUnsupported, S3Storage, Storage,
+    ZarrsStorageAdapter, ZipStorage, is_kerchunk_path, is_remote_location, is_s3_url, is_zip_path,
+};
 use anyhow::{Context, Result};
 use byteorder::{LittleEndian, ReadBytesExt};
-use std::collections::HashMap;
-use std::fs::{self, File};
-use std::io::Read;
-use std::path::{Path, PathBuf};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Names of the metadata documents of Zarr nodes, which are never chunks
+const METADATA_KEYS: &[&str] = &[".zarray", ".zattrs", ".zgroup", ".zmetadata", "zarr.json"];
 
 pub struct ZarrStore {
-    path: PathBuf,
+    storage: Arc<dyn Storage>,
+    /// The same storage wrapped for the zarrs crate, created once and shared by all array reads
+    zarrs_storage: Arc<ZarrsStorageAdapter>,
+    location: String,
+    quiet: bool,
 }
 
 impl ZarrStore {
-    /// Create a new ZarrStore from a directory path
+    /// Create a new ZarrStore from a directory path, a `.zip` archive, a kerchunk reference
+    /// `.json` file, an `http(s)://` URL or an `s3://bucket/prefix` URL
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
         let path = path.as_ref().to_path_buf();
 
+        if let Some(url) = path.to_str().filter(|p| is_remote_location(p)) {
+            let storage: Arc<dyn Storage> = if is_s3_url(url) {
+                Arc::new(S3Storage::open(url)?)
+            } else {
+                Arc::new(HttpStorage::new(url))
+            };
+            return Ok(Self::from_storage(storage, url.trim_end_matches('/')));
+        }
+
         if !path.exists() {
             return Err(anyhow::anyhow!("Path does not exist: {}", path.display()));
         }
 
-        if !path.is_dir() {
+        let storage: Arc<dyn Storage> = if path.is_dir() {
+            Arc::new(FilesystemStorage::new(&path))
+        } else if is_zip_path(&path) {
+            Arc::new(ZipStorage::open(&path)?)
+        } else if is_kerchunk_path(&path) {
+            Arc::new(KerchunkStorage::open(&path)?)
+        } else {
             return Err(anyhow::anyhow!(
-                "Path is not a directory: {}",
+                "Path is not a directory, .zip archive or kerchunk reference .json file: {}",
                 path.display()
             ));
+        };
+
+        Ok(Self::from_storage(storage, path.display().to_string()))
+    }
+
+    /// Create a ZarrStore over any [`Storage`] backend.
+    ///
+    /// `location` is only used to describe the store in messages and errors.
+    pub fn from_storage(storage: Arc<dyn Storage>, location: impl Into<String>) -> Self {
+        Self {
+            zarrs_storage: Arc::new(ZarrsStorageAdapter::new(storage.clone())),
+            storage,
+            location: location.into(),
+            quiet: false,
         }
+    }
 
-        Ok(Self { path })
+    /// Suppress progress messages (e.g. when stdout carries machine-readable output)
+    pub fn quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    fn log(&self, message: impl std::fmt::Display) {
+        if !self.quiet {
+            println!("{}", message);
+        }
+    }
+
+    /// Human-readable location of a key, for error messages
+    fn key_location(&self, key: &str) -> String {
+        Path::new(&self.location).join(key).display().to_string()
+    }
+
+    /// Read a key that must exist
+    fn read_key(&self, key: &str) -> Result<Vec<u8>> {
+        self.storage
+            .get(key)?
+            .ok_or_else(|| anyhow::anyhow!("Key '{}' not found", key))
+    }
+
+    /// Read an optional attributes document (.zattrs), treating missing or invalid JSON as empty
+    fn read_attributes(&self, key: &str) -> HashMap<String, AttributeValue> {
+        match self.storage.get(key) {
+            Ok(Some(attrs_data)) => {
+                serde_json::from_slice::<HashMap<String, AttributeValue>>(&attrs_data)
+                    .unwrap_or_default()
+            }
+            _ => HashMap::new(),
+        }
+    }
+
+    fn zarrs_storage(&self) -> Arc<ZarrsStorageAdapter> {
+        self.zarrs_storage.clone()
     }
 
     /// Load metadata from the Zarr store, attempting consolidated read first
     pub async fn load_metadata(&self) -> Result<ZarrMetadata> {
         // First check if this is a Zarr v3 store by looking for zarr.json
-        let zarr_json_path = self.path.join("zarr.json");
-        if zarr_json_path.exists() {
+        if self.storage.exists("zarr.json")? {
             return self.load_v3_metadata().await;
         }
 
         // Try consolidated metadata first (v2)
         match self.load_consolidated_metadata().await {
-            Ok(metadata) => {
-                println!("Loaded consolidated metadata from .zmetadata");
+            Ok(mut metadata) => {
+                self.log("Loaded consolidated metadata from .zmetadata");
+                metadata.consolidated = true;
                 Ok(metadata)
             }
             Err(e) => {
-                println!("Consolidated metadata not found: {}", e);
-                println!("Falling back to hierarchical scanning...");
+                self.log(format!("Consolidated metadata not found: {}", e));
+                self.log("Falling back to hierarchical scanning...");
                 self.load_hierarchical_metadata().await
             }
         }
@@ -53,19 +132,19 @@ impl ZarrStore {
 
     /// Attempt to load consolidated metadata from .zmetadata file
     async fn load_consolidated_metadata(&self) -> Result<ZarrMetadata> {
-        let zmetadata_path = self.path.join(".zmetadata");
+        let zmetadata_path = self.key_location(".zmetadata");
 
         // Read .zmetadata file
-        let data = fs::read(&zmetadata_path)
+        let data = self.read_key(".zmetadata")
             .with_context(|| format!(
-                "No consolidated metadata found at '{}'. This file is created when using zarr.convenience.consolidate_metadata().",
-                zmetadata_path.display()
+                "No consolidated metadata found at '{}'. This file is created when using zarr.convenience.consolidate_metadata() or `zarr-dump consolidate`.",
+                zmetadata_path
             ))?;
 
         let consolidated: ConsolidatedMetadata = serde_json::from_slice(&data)
             .with_context(|| format!(
                 "Invalid consolidated metadata JSON format at '{}'. The file exists but contains malformed JSON.",
-                zmetadata_path.display()
+                zmetadata_path
             ))?;
 
         // Parse consolidated metadata
@@ -78,8 +157,8 @@ impl ZarrStore {
     async fn load_hierarchical_metadata(&self) -> Result<ZarrMetadata> {
         let mut metadata = ZarrMetadata::new();
 
-        // Start from root and recursively scan
-        Self::scan_directory(&mut metadata, "", &self.path)?;
+        // Discover arrays and groups from the store's keys
+        self.scan_keys(&mut metadata)?;
 
         // Load async metadata for discovered items
         for (path, _) in metadata.variables.clone() {
@@ -99,87 +178,71 @@ impl ZarrStore {
         if metadata.variables.is_empty() && metadata.groups.is_empty() {
             return Err(anyhow::anyhow!(
                 "No Zarr arrays or groups found in '{}'. The directory must contain .zarray, .zgroup, or .zmetadata files to be a valid Zarr store.",
-                self.path.display()
+                self.location
             ));
         }
 
         // Step 4: Infer dimensions and detect unlimited dimensions
         metadata.infer_dimensions();
+        metadata.populate_group_children();
 
         Ok(metadata)
     }
 
-    /// Recursively scan directory for Zarr metadata files
-    fn scan_directory(
-        metadata: &mut ZarrMetadata,
-        current_path: &str,
-        fs_path: &Path,
-    ) -> Result<()> {
-        let entries = std::fs::read_dir(fs_path)
-            .context(format!("Failed to read directory: {}", fs_path.display()))?;
-
-        let mut has_zgroup = false;
-        let mut has_zarray = false;
-        let mut children = Vec::new();
-
-        // First pass: check what files exist in this directory
-        for entry in entries.flatten() {
-            let filename = entry.file_name().to_string_lossy().to_string();
-            let entry_path = entry.path();
-
-            match filename.as_str() {
-                ".zgroup" => has_zgroup = true,
-                ".zarray" => has_zarray = true,
-                name if !name.starts_with('.') && entry_path.is_dir() => {
-                    children.push(name.to_string());
+    /// Find arrays (.zarray) and groups (.zgroup) by listing all keys in the store
+    fn scan_keys(&self, metadata: &mut ZarrMetadata) -> Result<()> {
+        let keys = self
+            .storage
+            .list("")
+            .with_context(|| format!("Failed to list keys in '{}'", self.location))?;
+
+        let mut array_paths = BTreeSet::new();
+        let mut group_paths = BTreeSet::new();
+
+        for key in &keys {
+            let (node_path, filename) = split_key(key);
+
+            // Skip hidden directories (e.g. .git) below the root
+            if !node_path.is_empty() && node_path.split('/').any(|c| c.starts_with('.')) {
+                continue;
+            }
+
+            match filename {
+                ".zarray" => {
+                    array_paths.insert(node_path.to_string());
+                }
+                ".zgroup" => {
+                    group_paths.insert(node_path.to_string());
                 }
                 _ => {}
             }
         }
 
         // Mark items for later processing
-        if has_zarray {
+        for current_path in &array_paths {
             // This is an array - add a placeholder
-            let variable_name = if current_path.is_empty() {
-                "root".to_string()
-            } else {
-                current_path
-                    .split('/')
-                    .next_back()
-                    .unwrap_or(current_path)
-                    .to_string()
-            };
-
             let placeholder = Variable {
-                name: variable_name,
+                name: node_name(current_path, "root"),
                 path: current_path.to_string(),
                 dtype: "unknown".to_string(),
                 shape: vec![],
                 chunks: vec![],
-                compressor: None,
+                codecs: CodecPipeline::default(),
                 fill_value: None,
                 order: "C".to_string(),
-                filters: vec![],
+                dimension_separator: ".".to_string(),
                 attributes: HashMap::new(),
                 dimensions: vec![],
             };
             metadata
                 .variables
                 .insert(current_path.to_string(), placeholder);
-        } else if has_zgroup {
-            // This is a group - add a placeholder
-            let group_name = if current_path.is_empty() {
-                "/".to_string()
-            } else {
-                current_path
-                    .split('/')
-                    .next_back()
-                    .unwrap_or(current_path)
-                    .to_string()
-            };
+        }
 
+        for current_path in group_paths.difference(&array_paths) {
+            // This is a group - add a placeholder
             let placeholder = Group {
-                name: group_name,
+                name: node_name(current_path, "/"),
                 path: current_path.to_string(),
                 attributes: HashMap::new(),
                 children: Vec::new(),
@@ -194,62 +257,112 @@ impl ZarrStore {
             }
         }
 
-        // Recursively scan subdirectories
-        for child in children {
-            let child_path = if current_path.is_empty() {
-                child.clone()
-            } else {
-                format!("{}/{}", current_path, child)
-            };
-            let child_fs_path = fs_path.join(&child);
+        Ok(())
+    }
 
-            Self::scan_directory(metadata, &child_path, &child_fs_path)?;
-        }
+    /// Zarr format of the store: 3 if it has a root `zarr.json`, 2 otherwise
+    pub fn zarr_format(&self) -> Result<u8> {
+        Ok(if self.storage.exists("zarr.json")? {
+            3
+        } else {
+            2
+        })
+    }
 
-        Ok(())
+    /// Read the metadata document of every node from the per-node files, keyed as in consolidated
+    /// metadata: store keys such as `tas/.zarray` for v2, and node paths such as `tas` for v3
+    /// (whose root `zarr.json` holds the consolidated metadata and is left out)
+    pub fn scan_metadata_documents(&self) -> Result<BTreeMap<String, serde_json::Value>> {
+        let zarr_format = self.zarr_format()?;
+        let keys = self
+            .storage
+            .list("")
+            .with_context(|| format!("Failed to list keys in '{}'", self.location))?;
+
+        let mut documents = BTreeMap::new();
+        for key in &keys {
+            let (node_path, filename) = split_key(key);
+
+            // Skip hidden directories, and v3 chunk directories
+            if !node_path.is_empty()
+                && node_path
+                    .split('/')
+                    .any(|c| c.starts_with('.') || (zarr_format == 3 && c == "c"))
+            {
+                continue;
+            }
+
+            let entry = match (zarr_format, filename) {
+                (2, ".zarray" | ".zattrs" | ".zgroup") => key.clone(),
+                (3, "zarr.json") if !node_path.is_empty() => node_path.to_string(),
+                _ => continue,
+            };
+            let document: serde_json::Value = serde_json::from_slice(&self.read_key(key)?)
+                .with_context(|| format!("Failed to parse '{}'", self.key_location(key)))?;
+            documents.insert(entry, without_consolidated_metadata(document));
+        }
+        Ok(documents)
     }
 
-    /// Load array metadata from .zarray and .zattrs files
-    async fn load_array_metadata(&self, metadata: &mut ZarrMetadata, path: &str) -> Result<()> {
-        let zarray_path = if path.is_empty() {
-            self.path.join(".zarray")
+    /// Read the consolidated metadata entries from `.zmetadata` (v2) or the root `zarr.json` (v3),
+    /// keyed as by [`scan_metadata_documents`](Self::scan_metadata_documents), or `None` if the
+    /// store has no consolidated metadata
+    pub fn read_consolidated_documents(
+        &self,
+    ) -> Result<Option<BTreeMap<String, serde_json::Value>>> {
+        let key = if self.zarr_format()? == 3 {
+            "zarr.json"
         } else {
-            self.path.join(path).join(".zarray")
+            ".zmetadata"
         };
+        let Some(data) = self.storage.get(key)? else {
+            return Ok(None);
+        };
+        let document: serde_json::Value = serde_json::from_slice(&data)
+            .with_context(|| format!("Failed to parse '{}'", self.key_location(key)))?;
 
-        let zattrs_path = if path.is_empty() {
-            self.path.join(".zattrs")
+        let metadata = if key == "zarr.json" {
+            &document["consolidated_metadata"]["metadata"]
         } else {
-            self.path.join(path).join(".zattrs")
+            &document["metadata"]
         };
+        Ok(metadata.as_object().map(|entries| {
+            entries
+                .iter()
+                .map(|(name, value)| {
+                    let name = name.trim_matches('/').to_string();
+                    (name, without_consolidated_metadata(value.clone()))
+                })
+                .collect()
+        }))
+    }
+
+    /// Load array metadata from .zarray and .zattrs files
+    async fn load_array_metadata(&self, metadata: &mut ZarrMetadata, path: &str) -> Result<()> {
+        let zarray_key = node_key(path, ".zarray");
+        let zarray_path = self.key_location(&zarray_key);
 
         // Load .zarray
-        let array_data = fs::read(&zarray_path)
+        let array_data = self.read_key(&zarray_key)
             .with_context(|| {
                 if path.is_empty() {
-                    format!("Missing .zarray file for root variable at '{}'. This file is required to define array metadata (shape, dtype, chunks).", zarray_path.display())
+                    format!("Missing .zarray file for root variable at '{}'. This file is required to define array metadata (shape, dtype, chunks).", zarray_path)
                 } else {
-                    format!("Missing .zarray file for variable '{}' at '{}'. This file is required to define array metadata (shape, dtype, chunks).", path, zarray_path.display())
+                    format!("Missing .zarray file for variable '{}' at '{}'. This file is required to define array metadata (shape, dtype, chunks).", path, zarray_path)
                 }
             })?;
 
         let zarray: ZArrayMetadata = serde_json::from_slice(&array_data)
             .with_context(|| {
                 if path.is_empty() {
-                    format!("Invalid .zarray JSON format for root variable at '{}'. The file exists but contains malformed JSON.", zarray_path.display())
+                    format!("Invalid .zarray JSON format for root variable at '{}'. The file exists but contains malformed JSON.", zarray_path)
                 } else {
-                    format!("Invalid .zarray JSON format for variable '{}' at '{}'. The file exists but contains malformed JSON.", path, zarray_path.display())
+                    format!("Invalid .zarray JSON format for variable '{}' at '{}'. The file exists but contains malformed JSON.", path, zarray_path)
                 }
             })?;
 
         // Load .zattrs (optional)
-        let attributes = match fs::read(&zattrs_path) {
-            Ok(attrs_data) => {
-                serde_json::from_slice::<HashMap<String, AttributeValue>>(&attrs_data)
-                    .unwrap_or_default()
-            }
-            Err(_) => HashMap::new(),
-        };
+        let attributes = self.read_attributes(&node_key(path, ".zattrs"));
 
         // Create Variable struct
         let variable_name = if path.is_empty() {
@@ -269,31 +382,17 @@ impl ZarrStore {
             })
             .collect();
 
-        let compressor = zarray
-            .compressor
-            .as_ref()
-            .and_then(|c| c.get("id"))
-            .and_then(|id| id.as_str())
-            .map(|s| s.to_string());
-
-        let filters = zarray
-            .filters
-            .as_ref()
-            .map(|f| {
-                f.iter()
-                    .filter_map(|filter| {
-                        filter
-                            .get("id")
-                            .and_then(|id| id.as_str().map(|s| s.to_string()))
-                    })
-                    .collect()
-            })
-            .unwrap_or_default();
+        let codecs = CodecPipeline::V2 {
+            filters: zarray
+                .filters
+                .iter()
+                .flatten()
+                .map(Codec::from_v2)
+                .collect(),
+            compressor: zarray.compressor.as_ref().map(Codec::from_v2),
+        };
 
-        let fill_value = zarray
-            .fill_value
-            .as_ref()
-            .map(|fv| serde_json::from_value(fv.clone()).unwrap_or(AttributeValue::Null));
+        let fill_value = zarray.fill_value.as_ref().map(decode_v2_fill_value);
 
         let variable = Variable {
             name: variable_name.clone(),
@@ -301,10 +400,12 @@ impl ZarrStore {
             dtype: zarray.dtype,
             shape: zarray.shape,
             chunks: zarray.chunks,
-            compressor,
+            codecs,
             fill_value,
             order: zarray.order,
-            filters,
+            dimension_separator: zarray
+                .dimension_separator
+                .unwrap_or_else(|| ".".to_string()),
             attributes,
             dimensions,
         };
@@ -315,45 +416,30 @@ impl ZarrStore {
 
     /// Load group metadata from .zgroup and .zattrs files
     async fn load_group_metadata(&self, metadata: &mut ZarrMetadata, path: &str) -> Result<()> {
-        let zgroup_path = if path.is_empty() {
-            self.path.join(".zgroup")
-        } else {
-            self.path.join(path).join(".zgroup")
-        };
-
-        let zattrs_path = if path.is_empty() {
-            self.path.join(".zattrs")
-        } else {
-            self.path.join(path).join(".zattrs")
-        };
+        let zgroup_key = node_key(path, ".zgroup");
+        let zgroup_path = self.key_location(&zgroup_key);
 
         // Load .zgroup
-        let group_data = fs::read(&zgroup_path)
+        let group_data = self.read_key(&zgroup_key)
             .with_context(|| {
                 if path.is_empty() {
-                    format!("Missing .zgroup file for root group at '{}'. This file is required to define group metadata.", zgroup_path.display())
+                    format!("Missing .zgroup file for root group at '{}'. This file is required to define group metadata.", zgroup_path)
                 } else {
-                    format!("Missing .zgroup file for group '{}' at '{}'. This file is required to define group metadata.", path, zgroup_path.display())
+                    format!("Missing .zgroup file for group '{}' at '{}'. This file is required to define group metadata.", path, zgroup_path)
                 }
             })?;
 
         let _zgroup: ZGroupMetadata = serde_json::from_slice(&group_data)
             .with_context(|| {
                 if path.is_empty() {
-                    format!("Invalid .zgroup JSON format for root group at '{}'. The file exists but contains malformed JSON.", zgroup_path.display())
+                    format!("Invalid .zgroup JSON format for root group at '{}'. The file exists but contains malformed JSON.", zgroup_path)
                 } else {
-                    format!("Invalid .zgroup JSON format for group '{}' at '{}'. The file exists but contains malformed JSON.", path, zgroup_path.display())
+                    format!("Invalid .zgroup JSON format for group '{}' at '{}'. The file exists but contains malformed JSON.", path, zgroup_path)
                 }
             })?;
 
         // Load .zattrs (optional)
-        let attributes = match fs::read(&zattrs_path) {
-            Ok(attrs_data) => {
-                serde_json::from_slice::<HashMap<String, AttributeValue>>(&attrs_data)
-                    .unwrap_or_default()
-            }
-            Err(_) => HashMap::new(),
-        };
+        let attributes = self.read_attributes(&node_key(path, ".zattrs"));
 
         // Create Group struct
         let group_name = if path.is_empty() {
@@ -381,19 +467,11 @@ impl ZarrStore {
 
     /// Load global attributes from root .zattrs
     async fn load_global_attributes(&self, metadata: &mut ZarrMetadata) -> Result<()> {
-        let zattrs_path = self.path.join(".zattrs");
-
-        match fs::read(&zattrs_path) {
-            Ok(attrs_data) => {
-                let attributes: HashMap<String, AttributeValue> =
-                    serde_json::from_slice(&attrs_data).unwrap_or_default();
-
-                metadata.global_attributes = attributes.clone();
-                metadata.root_group.attributes = attributes;
-            }
-            Err(_) => {
-                // .zattrs is optional
-            }
+        // .zattrs is optional
+        if self.storage.exists(".zattrs")? {
+            let attributes = self.read_attributes(".zattrs");
+            metadata.global_attributes = attributes.clone();
+            metadata.root_group.attributes = attributes;
         }
         Ok(())
     }
@@ -457,6 +535,7 @@ impl ZarrStore {
 
         // Step 4: Infer dimensions and detect unlimited dimensions
         metadata.infer_dimensions();
+        metadata.populate_group_children();
 
         Ok(metadata)
     }
@@ -482,31 +561,17 @@ impl ZarrStore {
             })
             .collect();
 
-        let compressor = zarray
-            .compressor
-            .as_ref()
-            .and_then(|c| c.get("id"))
-            .and_then(|id| id.as_str())
-            .map(|s| s.to_string());
-
-        let filters = zarray
-            .filters
-            .as_ref()
-            .map(|f| {
-                f.iter()
-                    .filter_map(|filter| {
-                        filter
-                            .get("id")
-                            .and_then(|id| id.as_str().map(|s| s.to_string()))
-                    })
-                    .collect()
-            })
-            .unwrap_or_default();
+        let codecs = CodecPipeline::V2 {
+            filters: zarray
+                .filters
+                .iter()
+                .flatten()
+                .map(Codec::from_v2)
+                .collect(),
+            compressor: zarray.compressor.as_ref().map(Codec::from_v2),
+        };
 
-        let fill_value = zarray
-            .fill_value
-            .as_ref()
-            .map(|fv| serde_json::from_value(fv.clone()).unwrap_or(AttributeValue::Null));
+        let fill_value = zarray.fill_value.as_ref().map(decode_v2_fill_value);
 
         let variable = Variable {
             name: variable_name,
@@ -514,10 +579,12 @@ impl ZarrStore {
             dtype: zarray.dtype,
             shape: zarray.shape,
             chunks: zarray.chunks,
-            compressor,
+            codecs,
             fill_value,
             order: zarray.order,
-            filters,
+            dimension_separator: zarray
+                .dimension_separator
+                .unwrap_or_else(|| ".".to_string()),
             attributes,
             dimensions,
         };
@@ -526,6 +593,226 @@ impl ZarrStore {
         Ok(())
     }
 
+    /// Open an array with the zarrs crate
+    fn open_array(&self, variable: &Variable) -> Result<zarrs::array::Array<ZarrsStorageAdapter>> {
+        let array_path = if variable.path.is_empty() {
+            "/".to_string()
+        } else {
+            format!("/{}", variable.path)
+        };
+        zarrs::array::Array::open(self.zarrs_storage(), &array_path)
+            .map_err(|e| anyhow::anyhow!("Failed to open array '{}': {}", array_path, e))
+    }
+
+    /// Count the chunks of an array present in storage and sum up their sizes.
+    ///
+    /// Every chunk of the chunk grid is probed (for sharded arrays, every shard), and only the
+    /// sizes of the stored objects are read.
+    pub fn read_chunk_inventory(&self, variable: &Variable) -> Result<ChunkInventory> {
+        let array = self.open_array(variable)?;
+        let chunk_bytes = array
+            .data_type()
+            .fixed_size()
+            .map(|size| variable.chunks.iter().product::<u64>() * size as u64);
+        let array_bytes = array
+            .data_type()
+            .fixed_size()
+            .map(|size| variable.shape.iter().product::<u64>() * size as u64);
+
+        let mut inventory = ChunkInventory {
+            chunk_bytes,
+            array_bytes,
+            ..ChunkInventory::default()
+        };
+        inventory.expected_chunks = visit_chunk_keys(&array, |_, key| {
+            let Some(size) = self.storage.size(key)? else {
+                return Ok(());
+            };
+            inventory.stored_chunks += 1;
+            inventory.stored_bytes += size;
+
+            let relative_key = key
+                .strip_prefix(&format!("{}/", variable.path))
+                .unwrap_or(key);
+            let chunk = StoredChunk {
+                key: relative_key.to_string(),
+                size,
+            };
+            if inventory.smallest.as_ref().is_none_or(|c| size < c.size) {
+                inventory.smallest = Some(chunk.clone());
+            }
+            if inventory.largest.as_ref().is_none_or(|c| size > c.size) {
+                inventory.largest = Some(chunk);
+            }
+            Ok(())
+        })?;
+        Ok(inventory)
+    }
+
+    /// Check every chunk of an array: whether it is stored, decodes, and decodes to the size of
+    /// the chunk shape, and list the keys under the array that are not in its chunk grid.
+    ///
+    /// `other_nodes` are the paths of the other arrays and groups, whose keys are not orphans of
+    /// this array (this matters for an array at the root of the store).
+    pub fn verify_array(
+        &self,
+        variable: &Variable,
+        other_nodes: &[&str],
+    ) -> Result<ArrayVerification> {
+        use zarrs::array::ArrayError;
+        use zarrs::array::codec::CodecError;
+
+        let array = self.open_array(variable)?;
+        let relative = |key: &str| {
+            key.strip_prefix(&format!("{}/", variable.path))
+                .unwrap_or(key)
+                .to_string()
+        };
+
+        let mut verification = ArrayVerification::default();
+        let mut expected_keys = HashSet::new();
+        verification.expected_chunks = visit_chunk_keys(&array, |indices, key| {
+            expected_keys.insert(key.to_string());
+            if self.storage.size(key)?.is_none() {
+                verification.missing.push(relative(key));
+                return Ok(());
+            }
+            verification.stored_chunks += 1;
+            match array.retrieve_chunk(indices) {
+                Ok(_) => {}
+                Err(ArrayError::CodecError(e @ CodecError::UnexpectedChunkDecodedSize(..))) => {
+                    verification.wrong_size.push(ChunkProblem {
+                        key: relative(key),
+                        message: e.to_string(),
+                    });
+                }
+                Err(e) => verification.corrupt.push(ChunkProblem {
+                    key: relative(key),
+                    message: e.to_string(),
+                }),
+            }
+            Ok(())
+        })?;
+
+        // Stores that cannot list keys (HTTP) cannot be checked for orphans
+        let prefix = if variable.path.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", variable.path)
+        };
+        let keys = match self.storage.list(&prefix) {
+            Ok(keys) => keys,
+            Err(e) if e.downcast_ref::<ListingUnsupported>().is_some() => return Ok(verification),
+            Err(e) => {
+                return Err(e.context(format!("Failed to list the chunks of '{}'", variable.path)));
+            }
+        };
+        let orphans = keys
+            .into_iter()
+            .filter(|key| !expected_keys.contains(key))
+            .filter(|key| {
+                let name = key.rsplit('/').next().unwrap_or(key);
+                !METADATA_KEYS.contains(&name)
+            })
+            .filter(|key| {
+                !other_nodes
+                    .iter()
+                    .any(|node| key.starts_with(&format!("{}/", node)))
+            })
+            .map(|key| relative(&key))
+            .collect();
+        verification.orphans = Some(orphans);
+        Ok(verification)
+    }
+
+    /// List the shards of a sharded v3 array and count the inner chunks written to each one.
+    ///
+    /// Every shard of the chunk grid is probed, and only the index of each shard is read.
+    pub fn read_shard_inventory(&self, variable: &Variable) -> Result<ShardInventory> {
+        let sharding = variable
+            .codecs
+            .sharding()
+            .ok_or_else(|| anyhow::anyhow!("Array '{}' is not sharded", variable.path))?;
+
+        if sharding.inner_chunk_shape.len() != variable.chunks.len()
+            || sharding
+                .inner_chunk_shape
+                .iter()
+                .zip(&variable.chunks)
+                .any(|(&inner, &outer)| inner == 0 || outer % inner != 0)
+        {
+            return Err(anyhow::anyhow!(
+                "Inner chunk shape {:?} does not evenly divide the shard shape {:?} of '{}'",
+                sharding.inner_chunk_shape,
+                variable.chunks,
+                variable.path
+            ));
+        }
+        let inner_chunks_per_shard: u64 = variable
+            .chunks
+            .iter()
+            .zip(&sharding.inner_chunk_shape)
+            .map(|(outer, inner)| outer / inner)
+            .product();
+
+        let (big_endian, checksum) = shard_index_encoding(&sharding.index_codecs)?;
+        let index_size = inner_chunks_per_shard * 16 + if checksum { 4 } else { 0 };
+
+        let array = self.open_array(variable)?;
+        let mut shards = Vec::new();
+        let total_shards = visit_chunk_keys(&array, |_, key| {
+            if let Some(size) = self.storage.size(key)? {
+                if size < index_size {
+                    return Err(anyhow::anyhow!(
+                        "Shard '{}' is smaller than its {} byte index",
+                        key,
+                        index_size
+                    ));
+                }
+                let offset = if sharding.index_location == "start" {
+                    0
+                } else {
+                    size - index_size
+                };
+                let index = self
+                    .storage
+                    .get_range(key, offset, Some(index_size))?
+                    .ok_or_else(|| anyhow::anyhow!("Shard '{}' disappeared while reading", key))?;
+
+                // Each inner chunk has an (offset, length) pair; both are all ones if it is empty
+                let populated_inner_chunks = index[..inner_chunks_per_shard as usize * 16]
+                    .chunks_exact(16)
+                    .filter(|entry| {
+                        let read = |bytes: &[u8]| {
+                            let bytes: [u8; 8] = bytes.try_into().unwrap();
+                            if big_endian {
+                                u64::from_be_bytes(bytes)
+                            } else {
+                                u64::from_le_bytes(bytes)
+                            }
+                        };
+                        read(&entry[..8]) != u64::MAX || read(&entry[8..]) != u64::MAX
+                    })
+                    .count() as u64;
+
+                let relative_key = key
+                    .strip_prefix(&format!("{}/", variable.path))
+                    .unwrap_or(key);
+                shards.push(ShardStatus {
+                    key: relative_key.to_string(),
+                    populated_inner_chunks,
+                });
+            }
+            Ok(())
+        })?;
+
+        Ok(ShardInventory {
+            total_shards,
+            inner_chunks_per_shard,
+            shards,
+        })
+    }
+
     /// Read coordinate data for a variable using zarrs crate for full Zarr compliance
     pub async fn read_coordinate_data(&self, variable: &Variable) -> Result<Vec<f64>> {
         // For simplicity, we'll only handle 1D coordinate variables
@@ -547,13 +834,8 @@ impl ZarrStore {
         variable: &Variable,
         ranges: &[std::ops::Range<u64>],
     ) -> Result<Vec<f64>> {
-        use std::sync::Arc;
         use zarrs::array::{Array, DataType};
         use zarrs::array_subset::ArraySubset;
-        use zarrs::storage::store::FilesystemStore;
-
-        let store = FilesystemStore::new(&self.path)
-            .map_err(|e| anyhow::anyhow!("Failed to create zarrs FilesystemStore: {}", e))?;
 
         let array_path = if variable.path.is_empty() {
             "/".to_string()
@@ -561,7 +843,7 @@ impl ZarrStore {
             format!("/{}", variable.path)
         };
 
-        let array = Array::open(Arc::new(store), &array_path)
+        let array = Array::open(self.zarrs_storage(), &array_path)
             .map_err(|e| anyhow::anyhow!("Failed to open array '{}': {}", array_path, e))?;
 
         let subset = ArraySubset::new_with_ranges(ranges);
@@ -651,6 +933,128 @@ impl ZarrStore {
         Ok(values)
     }
 
+    /// Read the values of a hyperslab in C order, a batch of leading indices at a time, and pass
+    /// each batch to `consume`. Batches hold about a million values, rounded to whole chunks along
+    /// the first dimension when they fit, so arrays larger than memory can be streamed.
+    pub fn read_hyperslab_batches(
+        &self,
+        variable: &Variable,
+        slab: &Hyperslab,
+        consume: impl FnMut(&[f64]) -> Result<()>,
+    ) -> Result<()> {
+        self.read_batches(
+            variable,
+            slab,
+            |ranges| self.read_array_subset_f64(variable, ranges),
+            consume,
+        )
+    }
+
+    /// Read the values of a hyperslab in C order. Dimensions strided by at least their chunk
+    /// length are read one selected index at a time, so the chunks in between are skipped.
+    pub fn read_hyperslab_f64(&self, variable: &Variable, slab: &Hyperslab) -> Result<Vec<f64>> {
+        Self::read_hyperslab(variable, slab, |ranges| {
+            self.read_array_subset_f64(variable, ranges)
+        })
+    }
+
+    /// Like [`read_hyperslab_batches`](Self::read_hyperslab_batches), but reads the values as
+    /// their native element type `T`, which must match the array's data type. Use it for int64
+    /// and uint64 arrays, whose values an `f64` cannot all hold.
+    pub fn read_hyperslab_batches_as<T: zarrs::array::ElementOwned + Copy + Default>(
+        &self,
+        variable: &Variable,
+        slab: &Hyperslab,
+        consume: impl FnMut(&[T]) -> Result<()>,
+    ) -> Result<()> {
+        self.read_batches(
+            variable,
+            slab,
+            |ranges| self.read_array_subset_elements(variable, ranges),
+            consume,
+        )
+    }
+
+    /// Read a subset of an array as its native element type `T`
+    fn read_array_subset_elements<T: zarrs::array::ElementOwned>(
+        &self,
+        variable: &Variable,
+        ranges: &[std::ops::Range<u64>],
+    ) -> Result<Vec<T>> {
+        let subset = zarrs::array_subset::ArraySubset::new_with_ranges(ranges);
+        self.open_array(variable)?
+            .retrieve_array_subset_elements::<T>(&subset)
+            .with_context(|| {
+                format!(
+                    "Failed to read subset for '{}' as {}",
+                    variable.path,
+                    std::any::type_name::<T>()
+                )
+            })
+    }
+
+    /// Read a hyperslab with `read`, which reads the contiguous index ranges of a subset
+    fn read_hyperslab<T: Copy + Default>(
+        variable: &Variable,
+        slab: &Hyperslab,
+        read: impl Fn(&[std::ops::Range<u64>]) -> Result<Vec<T>>,
+    ) -> Result<Vec<T>> {
+        let parts = slab.split_sparse(read_chunk_shape(variable));
+        if let [part] = parts.as_slice() {
+            return Ok(part.subsample(read(&part.read_ranges())?));
+        }
+        let mut values = vec![T::default(); slab.shape().iter().product::<u64>() as usize];
+        for part in &parts {
+            let data = part.subsample(read(&part.read_ranges())?);
+            slab.place(&mut values, part, &data);
+        }
+        Ok(values)
+    }
+
+    fn read_batches<T: Copy + Default>(
+        &self,
+        variable: &Variable,
+        slab: &Hyperslab,
+        read: impl Fn(&[std::ops::Range<u64>]) -> Result<Vec<T>>,
+        mut consume: impl FnMut(&[T]) -> Result<()>,
+    ) -> Result<()> {
+        const BATCH_VALUES: u64 = 1 << 20;
+
+        let shape = slab.shape();
+        if shape.contains(&0) {
+            return Ok(());
+        }
+        let Some((first, rest)) = slab.dims.split_first() else {
+            let data = read(&[])?;
+            return consume(&data);
+        };
+
+        // Batches are sized by the values read, which include the indices a strided selection skips
+        let read_shape = slab.read_shape(read_chunk_shape(variable));
+        // (along the first dimension, `step` indices per selected index unless read one at a time)
+        let per_index: u64 =
+            read_shape[1..].iter().product::<u64>() * read_shape[0].min(first.step);
+        let mut batch = (BATCH_VALUES / per_index.max(1)).max(1);
+        if let Some(&chunk) = variable.chunks.first()
+            && first.step == 1
+            && chunk > 0
+            && batch >= chunk
+        {
+            batch -= batch % chunk;
+        }
+
+        let mut offset = 0;
+        while offset < first.count() {
+            let mut part = Hyperslab {
+                dims: vec![first.slice(offset, batch)],
+            };
+            part.dims.extend_from_slice(rest);
+            consume(&Self::read_hyperslab(variable, &part, &read)?)?;
+            offset += batch;
+        }
+        Ok(())
+    }
+
     /// Read array data using the zarrs crate with proper compression support
     async fn read_zarr_array_data(&self, variable: &Variable) -> Result<Vec<f64>> {
         // Try different zarrs API approaches
@@ -660,14 +1064,10 @@ impl ZarrStore {
             .or_else(|_| self.fallback_to_manual_read(variable))
     }
 
-    /// Try zarrs API approach 1: Using filesystem store
+    /// Try zarrs API approach 1: Using the store's storage backend
     async fn try_zarrs_api_v1(&self, variable: &Variable) -> Result<Vec<f64>> {
         use zarrs::array::Array;
         use zarrs::array_subset::ArraySubset;
-        use zarrs::storage::store::FilesystemStore;
-
-        let store = FilesystemStore::new(&self.path)
-            .map_err(|e| anyhow::anyhow!("Failed to create zarrs FilesystemStore: {}", e))?;
 
         let array_path = if variable.path.is_empty() {
             "/".to_string()
@@ -675,7 +1075,7 @@ impl ZarrStore {
             format!("/{}", variable.path)
         };
 
-        let array = Array::open(std::sync::Arc::new(store), &array_path)
+        let array = Array::open(self.zarrs_storage(), &array_path)
             .map_err(|e| anyhow::anyhow!("Failed to open array '{}': {}", array_path, e))?;
 
         // Create array subset for the entire array
@@ -769,37 +1169,25 @@ impl ZarrStore {
     /// Fallback to manual reading for uncompressed data
     fn fallback_to_manual_read(&self, variable: &Variable) -> Result<Vec<f64>> {
         // Check if the variable has compression - if so, we can't handle it with this simple implementation
-        if variable.compressor.is_some() {
+        if let Some(compressor) = variable.codecs.compressor() {
             return Err(anyhow::anyhow!(
                 "Variable '{}' uses compression ('{}'), which could not be handled by the zarrs crate. \
                 This may be due to API version incompatibility or missing compression support.",
                 variable.name,
-                variable.compressor.as_ref().unwrap()
+                compressor
             ));
         }
 
-        // Build the path to the first chunk (0)
-        let chunk_path = if variable.path.is_empty() {
-            self.path.join("0")
-        } else {
-            self.path.join(&variable.path).join("0")
-        };
-
-        // Check if the chunk file exists
-        if !chunk_path.exists() {
-            return Err(anyhow::anyhow!(
-                "Chunk file not found: {}",
-                chunk_path.display()
-            ));
-        }
+        // Build the key of the first chunk (0)
+        let chunk_key = node_key(&variable.path, "0");
+        let chunk_path = self.key_location(&chunk_key);
 
         // Read the raw chunk data
-        let mut file = File::open(&chunk_path)
-            .with_context(|| format!("Failed to open chunk file: {}", chunk_path.display()))?;
-
-        let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer)
-            .with_context(|| format!("Failed to read chunk file: {}", chunk_path.display()))?;
+        let buffer = self
+            .storage
+            .get(&chunk_key)
+            .with_context(|| format!("Failed to read chunk file: {}", chunk_path))?
+            .ok_or_else(|| anyhow::anyhow!("Chunk file not found: {}", chunk_path))?;
 
         // Parse the data based on dtype
         self.parse_coordinate_data(&buffer, &variable.dtype, variable.shape[0] as usize)
@@ -862,9 +1250,11 @@ impl ZarrStore {
     async fn load_v3_metadata(&self) -> Result<ZarrMetadata> {
         use crate::metadata::ZarrV3Root;
 
-        let zarr_json_path = self.path.join("zarr.json");
-        let data = fs::read(&zarr_json_path).with_context(|| {
-            format!("Failed to read zarr.json at '{}'", zarr_json_path.display())
+        let data = self.read_key("zarr.json").with_context(|| {
+            format!(
+                "Failed to read zarr.json at '{}'",
+                self.key_location("zarr.json")
+            )
         })?;
 
         let v3_root: ZarrV3Root =
@@ -881,69 +1271,76 @@ impl ZarrStore {
 
         // Check for consolidated metadata first
         if let Some(consolidated) = v3_root.consolidated_metadata {
-            println!("Loaded consolidated metadata from zarr.json (Zarr v3)");
-            for (name, v3_array) in consolidated.metadata {
-                self.convert_v3_array_to_variable(&mut metadata, &name, v3_array)?;
+            self.log("Loaded consolidated metadata from zarr.json (Zarr v3)");
+            metadata.consolidated = true;
+            for (name, node) in consolidated.metadata {
+                self.add_v3_node(&mut metadata, name.trim_matches('/'), node)?;
             }
         } else {
             // Fall back to hierarchical scanning for v3
-            println!("Scanning for individual zarr.json files (Zarr v3)...");
-            self.scan_v3_directory(&mut metadata, "", &self.path)?;
+            self.log("Scanning for individual zarr.json files (Zarr v3)...");
+            self.scan_v3_keys(&mut metadata)?;
         }
 
         // Infer dimensions
         metadata.infer_dimensions();
+        metadata.populate_group_children();
 
         Ok(metadata)
     }
 
-    /// Recursively scan directory for Zarr v3 zarr.json files
-    fn scan_v3_directory(
+    /// Record a Zarr v3 array or group found at `path`
+    fn add_v3_node(
         &self,
         metadata: &mut ZarrMetadata,
-        current_path: &str,
-        fs_path: &Path,
+        path: &str,
+        node: ZarrV3NodeMetadata,
     ) -> Result<()> {
-        use crate::metadata::ZarrV3ArrayMetadata;
+        match node {
+            ZarrV3NodeMetadata::Array(v3_array) => {
+                self.convert_v3_array_to_variable(metadata, path, *v3_array)
+            }
+            ZarrV3NodeMetadata::Group(v3_group) => {
+                let group = Group {
+                    name: node_name(path, "/"),
+                    path: path.to_string(),
+                    attributes: v3_group.attributes.unwrap_or_default(),
+                    children: Vec::new(),
+                };
+                metadata.groups.insert(path.to_string(), group);
+                Ok(())
+            }
+        }
+    }
 
-        let entries = std::fs::read_dir(fs_path)
-            .context(format!("Failed to read directory: {}", fs_path.display()))?;
+    /// Find nested Zarr v3 zarr.json documents by listing all keys in the store
+    fn scan_v3_keys(&self, metadata: &mut ZarrMetadata) -> Result<()> {
+        let keys = self
+            .storage
+            .list("")
+            .with_context(|| format!("Failed to list keys in '{}'", self.location))?;
 
-        for entry in entries.flatten() {
-            let filename = entry.file_name().to_string_lossy().to_string();
-            let entry_path = entry.path();
+        for key in &keys {
+            let (var_path, filename) = split_key(key);
 
-            // Skip hidden files and chunk data directories
-            if filename.starts_with('.') || filename == "c" {
+            // The root zarr.json has already been handled by the caller
+            if filename != "zarr.json" || var_path.is_empty() {
                 continue;
             }
 
-            if entry_path.is_dir() {
-                let zarr_json = entry_path.join("zarr.json");
-                if zarr_json.exists() {
-                    // Read and parse the zarr.json
-                    let data = fs::read(&zarr_json)?;
-                    let v3_array: ZarrV3ArrayMetadata = serde_json::from_slice(&data).context(
-                        format!("Failed to parse zarr.json at {}", zarr_json.display()),
-                    )?;
-
-                    let var_path = if current_path.is_empty() {
-                        filename.clone()
-                    } else {
-                        format!("{}/{}", current_path, filename)
-                    };
+            // Skip hidden directories and chunk data directories
+            if var_path.split('/').any(|c| c.starts_with('.') || c == "c") {
+                continue;
+            }
 
-                    self.convert_v3_array_to_variable(metadata, &var_path, v3_array)?;
-                }
+            // Read and parse the zarr.json
+            let data = self.read_key(key)?;
+            let node: ZarrV3NodeMetadata = serde_json::from_slice(&data).context(format!(
+                "Failed to parse zarr.json at {}",
+                self.key_location(key)
+            ))?;
 
-                // Recursively scan subdirectories
-                let child_path = if current_path.is_empty() {
-                    filename
-                } else {
-                    format!("{}/{}", current_path, filename)
-                };
-                self.scan_v3_directory(metadata, &child_path, &entry_path)?;
-            }
+            self.add_v3_node(metadata, var_path, node)?;
         }
 
         Ok(())
@@ -964,11 +1361,24 @@ impl ZarrStore {
         // Extract chunks from chunk_grid
         let chunks = v3_array.chunk_grid.configuration.chunk_shape.clone();
 
-        // Extract codec names
-        let codecs: Vec<String> = v3_array.codecs.iter().map(|c| c.name.clone()).collect();
+        // The `default` encoding separates chunk indices with `/` unless configured otherwise
+        let key_encoding = &v3_array.chunk_key_encoding;
+        let dimension_separator = key_encoding
+            .get("configuration")
+            .and_then(|c| c.get("separator"))
+            .and_then(|s| s.as_str())
+            .unwrap_or(match key_encoding.get("name").and_then(|n| n.as_str()) {
+                Some("v2") => ".",
+                _ => "/",
+            })
+            .to_string();
 
-        // Determine primary compressor (skip 'bytes' codec which is just endianness)
-        let compressor = codecs.iter().find(|c| *c != "bytes").cloned();
+        let codecs = v3_array
+            .codecs
+            .iter()
+            .map(Codec::from_v3)
+            .collect::<Result<Vec<_>>>()
+            .with_context(|| format!("Invalid codecs for array '{}'", path))?;
 
         // Prepare attributes - merge v3 attributes with dimension_names
         let mut attributes = v3_array.attributes.clone().unwrap_or_default();
@@ -1002,10 +1412,13 @@ impl ZarrStore {
             dtype,
             shape: v3_array.shape,
             chunks,
-            compressor,
-            fill_value: Some(AttributeValue::String("NaN".to_string())), // Simplified
+            codecs: CodecPipeline::V3(codecs),
+            fill_value: Some(decode_v3_fill_value(
+                &v3_array.data_type,
+                &v3_array.fill_value,
+            )),
             order: "C".to_string(),
-            filters: vec![],
+            dimension_separator,
             attributes,
             dimensions,
         };
@@ -1017,8 +1430,11 @@ impl ZarrStore {
     /// Convert Zarr v3 data_type to v2 dtype format
     fn convert_v3_datatype_to_v2(&self, data_type: &str) -> String {
         match data_type {
+            "float16" => "<f2".to_string(),
             "float32" => "<f4".to_string(),
             "float64" => "<f8".to_string(),
+            "complex64" => "<c8".to_string(),
+            "complex128" => "<c16".to_string(),
             "int8" => "<i1".to_string(),
             "int16" => "<i2".to_string(),
             "int32" => "<i4".to_string(),
@@ -1032,3 +1448,318 @@ impl ZarrStore {
         }
     }
 }
+
+/// Shape of the chunks zarrs decodes when reading: the inner chunks of a sharded array
+fn read_chunk_shape(variable: &Variable) -> &[u64] {
+    match variable.codecs.sharding() {
+        Some(sharding) => &sharding.inner_chunk_shape,
+        None => &variable.chunks,
+    }
+}
+
+/// Decode a v2 `fill_value`, keeping integers too large for an `f64` exact
+fn decode_v2_fill_value(value: &serde_json::Value) -> AttributeValue {
+    match (value.as_i64(), value.as_u64()) {
+        (Some(v), _) if v.unsigned_abs() > 1 << 53 => AttributeValue::Integer(v),
+        (None, Some(v)) => AttributeValue::UnsignedInteger(v),
+        _ => serde_json::from_value(value.clone()).unwrap_or(AttributeValue::Null),
+    }
+}
+
+/// Decode a v3 `fill_value` according to the array's `data_type`.
+///
+/// Non-finite floats are kept as the `"NaN"`/`"Infinity"`/`"-Infinity"` strings used by v2
+/// metadata, and hex-encoded bit patterns (e.g. `"0x7fc00000"`) are decoded to their value.
+/// Values that do not match the data type are kept as they appear in the metadata.
+fn decode_v3_fill_value(data_type: &str, value: &serde_json::Value) -> AttributeValue {
+    let decoded = match data_type {
+        "bool" => value.as_bool().map(AttributeValue::Boolean),
+        "int8" | "int16" | "int32" | "int64" | "uint8" | "uint16" | "uint32" | "uint64" => value
+            .as_i64()
+            .map(AttributeValue::Integer)
+            .or_else(|| value.as_u64().map(AttributeValue::UnsignedInteger)),
+        "float16" | "bfloat16" | "float32" | "float64" => decode_v3_float(data_type, value),
+        "complex64" | "complex128" => {
+            let part_type = if data_type == "complex64" {
+                "float32"
+            } else {
+                "float64"
+            };
+            match value.as_array().map(Vec::as_slice) {
+                Some([re, im]) => decode_v3_float(part_type, re)
+                    .zip(decode_v3_float(part_type, im))
+                    .map(|(re, im)| AttributeValue::Array(vec![re, im])),
+                _ => None,
+            }
+        }
+        // Raw bits types (r8, r16, ...) use one integer per byte
+        raw if raw
+            .strip_prefix('r')
+            .is_some_and(|bits| bits.parse::<u32>().is_ok()) =>
+        {
+            value
+                .as_array()
+                .and_then(|bytes| {
+                    bytes
+                        .iter()
+                        .map(|b| b.as_u64().filter(|b| *b <= 255))
+                        .map(|b| b.map(|b| AttributeValue::Integer(b as i64)))
+                        .collect::<Option<Vec<_>>>()
+                })
+                .map(AttributeValue::Array)
+        }
+        _ => None,
+    };
+
+    decoded.unwrap_or_else(|| serde_json::from_value(value.clone()).unwrap_or(AttributeValue::Null))
+}
+
+fn decode_v3_float(data_type: &str, value: &serde_json::Value) -> Option<AttributeValue> {
+    if let Some(v) = value.as_f64() {
+        return Some(AttributeValue::Number(v));
+    }
+
+    let s = value.as_str()?;
+    if matches!(s, "NaN" | "Infinity" | "-Infinity") {
+        return Some(AttributeValue::String(s.to_string()));
+    }
+
+    let bits = u64::from_str_radix(s.strip_prefix("0x")?, 16).ok()?;
+    let v = match data_type {
+        "float16" => f16_bits_to_f64(u16::try_from(bits).ok()?),
+        "bfloat16" => f32::from_bits(u32::from(u16::try_from(bits).ok()?) << 16) as f64,
+        "float32" => f32::from_bits(u32::try_from(bits).ok()?) as f64,
+        _ => f64::from_bits(bits),
+    };
+
+    Some(if v.is_nan() {
+        AttributeValue::String("NaN".to_string())
+    } else if v.is_infinite() {
+        let s = if v > 0.0 { "Infinity" } else { "-Infinity" };
+        AttributeValue::String(s.to_string())
+    } else {
+        AttributeValue::Number(v)
+    })
+}
+
+/// Value of an IEEE 754 half-precision float given its bit pattern
+fn f16_bits_to_f64(bits: u16) -> f64 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = i32::from((bits >> 10) & 0x1f);
+    let fraction = f64::from(bits & 0x3ff);
+    match exponent {
+        0 => sign * fraction * 2f64.powi(-24),
+        0x1f if fraction == 0.0 => sign * f64::INFINITY,
+        0x1f => f64::NAN,
+        _ => sign * (1.0 + fraction / 1024.0) * 2f64.powi(exponent - 15),
+    }
+}
+
+/// Call `visit` with the indices and store key of every chunk in the chunk grid of `array`, in C
+/// order, and return the number of chunks
+fn visit_chunk_keys(
+    array: &zarrs::array::Array<ZarrsStorageAdapter>,
+    mut visit: impl FnMut(&[u64], &str) -> Result<()>,
+) -> Result<u64> {
+    let grid_shape = array.chunk_grid_shape().ok_or_else(|| {
+        anyhow::anyhow!("Could not determine the chunk grid of '{}'", array.path())
+    })?;
+
+    let total: u64 = grid_shape.iter().product();
+    let mut indices = vec![0u64; grid_shape.len()];
+    for _ in 0..total {
+        visit(&indices, array.chunk_key(&indices).as_str())?;
+
+        // Advance to the next chunk in C order
+        for (index, &len) in indices.iter_mut().zip(&grid_shape).rev() {
+            *index += 1;
+            if *index < len {
+                break;
+            }
+            *index = 0;
+        }
+    }
+    Ok(total)
+}
+
+/// Byte order and whether a trailing crc32c checksum is present, for a shard index encoded
+/// with `index_codecs`
+fn shard_index_encoding(index_codecs: &[Codec]) -> Result<(bool, bool)> {
+    let mut big_endian = false;
+    let mut checksum = false;
+    for codec in index_codecs {
+        match codec.resolved() {
+            Codec::Bytes { endian } => big_endian = endian.as_deref() == Some("big"),
+            Codec::Crc32c => checksum = true,
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Unsupported shard index codec '{}' (supported: bytes, crc32c)",
+                    other.name()
+                ));
+            }
+        }
+    }
+    Ok((big_endian, checksum))
+}
+
+/// A node metadata document without the consolidated metadata that v3 groups may carry, which is
+/// not part of the node's own metadata
+fn without_consolidated_metadata(mut document: serde_json::Value) -> serde_json::Value {
+    if let Some(object) = document.as_object_mut() {
+        object.remove("consolidated_metadata");
+    }
+    document
+}
+
+/// Key of a metadata document (or chunk) belonging to the node at `path`
+fn node_key(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", path, name)
+    }
+}
+
+/// Split a store key into its parent node path and final component
+fn split_key(key: &str) -> (&str, &str) {
+    key.rsplit_once('/').unwrap_or(("", key))
+}
+
+/// Last path component of a node, or `root_name` for the store root
+fn node_name(path: &str, root_name: &str) -> String {
+    if path.is_empty() {
+        root_name.to_string()
+    } else {
+        path.split('/').next_back().unwrap_or(path).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+    use serde_json::json;
+
+    #[test]
+    fn test_decode_v3_fill_value() {
+        let decode = |data_type: &str, value: serde_json::Value| {
+            serde_json::to_value(decode_v3_fill_value(data_type, &value)).unwrap()
+        };
+
+        assert_eq!(decode("int16", json!(-999)), json!(-999));
+        assert_eq!(
+            decode("uint64", json!(18446744073709551615u64)).to_string(),
+            "18446744073709551615"
+        );
+        assert_eq!(
+            decode("int64", json!(-9007199254740993i64)).to_string(),
+            "-9007199254740993"
+        );
+        assert_eq!(decode("bool", json!(true)), json!(true));
+        assert_eq!(decode("float64", json!(-9999.5)), json!(-9999.5));
+        assert_eq!(decode("float32", json!("NaN")), json!("NaN"));
+        assert_eq!(decode("float64", json!("-Infinity")), json!("-Infinity"));
+
+        // Hex-encoded bit patterns
+        assert_eq!(decode("float32", json!("0x7fc00000")), json!("NaN"));
+        assert_eq!(decode("float32", json!("0xc2c80000")), json!(-100.0));
+        assert_eq!(decode("float64", json!("0x3ff0000000000000")), json!(1.0));
+        assert_eq!(decode("float16", json!("0x3c00")), json!(1.0));
+        assert_eq!(decode("float16", json!("0xfc00")), json!("-Infinity"));
+        assert_eq!(decode("bfloat16", json!("0x4040")), json!(3.0));
+
+        assert_eq!(
+            decode("complex64", json!([1.5, "NaN"])),
+            json!([1.5, "NaN"])
+        );
+        assert_eq!(decode("r16", json!([0, 255])), json!([0, 255]));
+
+        // Values that do not match the data type are kept as-is
+        assert_eq!(decode("int32", json!("oops")), json!("oops"));
+        assert_eq!(decode("float32", json!("0xzz")), json!("0xzz"));
+
+        // v2 fill values are only typed when an f64 cannot hold them
+        let decode_v2 =
+            |value: serde_json::Value| serde_json::to_value(decode_v2_fill_value(&value)).unwrap();
+        assert_eq!(decode_v2(json!(-1)), json!(-1.0));
+        assert_eq!(
+            decode_v2(json!(18446744073709551615u64)).to_string(),
+            "18446744073709551615"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_store_over_memory_storage() {
+        let values: Vec<u8> = [1.0f64, 2.0, 3.0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let storage: MemoryStorage = [
+            (".zgroup", br#"{"zarr_format": 2}"#.to_vec()),
+            (".zattrs", br#"{"title": "in memory"}"#.to_vec()),
+            (
+                "x/.zarray",
+                br#"{"zarr_format": 2, "shape": [3], "chunks": [3], "dtype": "<f8",
+                     "compressor": null, "fill_value": "NaN", "order": "C", "filters": null}"#
+                    .to_vec(),
+            ),
+            ("x/.zattrs", br#"{"_ARRAY_DIMENSIONS": ["x"]}"#.to_vec()),
+            ("x/0", values),
+        ]
+        .into_iter()
+        .collect();
+
+        let store = ZarrStore::from_storage(Arc::new(storage), "memory").quiet(true);
+        let metadata = store.load_metadata().await.unwrap();
+        assert!(matches!(
+            metadata.global_attributes.get("title"),
+            Some(AttributeValue::String(title)) if title == "in memory"
+        ));
+
+        let variable = &metadata.variables["x"];
+        assert_eq!(variable.dimensions[0].name, "x");
+        assert_eq!(
+            store.read_coordinate_data(variable).await.unwrap(),
+            vec![1.0, 2.0, 3.0]
+        );
+        assert_eq!(
+            store
+                .read_array_subset_f64(variable, &[std::ops::Range { start: 1, end: 3 }])
+                .unwrap(),
+            vec![2.0, 3.0]
+        );
+    }
+
+    #[test]
+    fn test_v3_metadata_documents() {
+        let storage: MemoryStorage = [
+            (
+                "zarr.json",
+                br#"{"zarr_format": 3, "node_type": "group", "attributes": {},
+                     "consolidated_metadata": {"kind": "inline", "must_understand": false,
+                         "metadata": {"/g": {"zarr_format": 3, "node_type": "group",
+                             "attributes": {"a": 1}, "consolidated_metadata": null}}}}"#
+                    .to_vec(),
+            ),
+            (
+                "g/zarr.json",
+                br#"{"zarr_format": 3, "node_type": "group", "attributes": {"a": 2}}"#.to_vec(),
+            ),
+            ("g/c/zarr.json", b"not a node".to_vec()),
+        ]
+        .into_iter()
+        .collect();
+        let store = ZarrStore::from_storage(Arc::new(storage), "memory");
+
+        let scanned = store.scan_metadata_documents().unwrap();
+        assert_eq!(scanned.keys().collect::<Vec<_>>(), ["g"]);
+        assert_eq!(scanned["g"]["attributes"]["a"], 2);
+
+        // Leading slashes and nested consolidated_metadata are normalized away
+        let stored = store.read_consolidated_documents().unwrap().unwrap();
+        assert_eq!(
+            stored["g"],
+            json!({"zarr_format": 3, "node_type": "group", "attributes": {"a": 1}})
+        );
+    }
+}
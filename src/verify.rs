@@ -0,0 +1,144 @@
+//! Integrity report of the `verify` subcommand
+
+use crate::metadata::{ArrayVerification, ChunkProblem};
+use serde_json::{Value, json};
+use std::fmt::Write;
+
+/// Chunk keys listed per problem in the text report
+const MAX_LISTED: usize = 10;
+
+fn list_keys<'a>(keys: impl ExactSizeIterator<Item = &'a str>) -> String {
+    let total = keys.len();
+    let mut listed: Vec<&str> = keys.take(MAX_LISTED).collect();
+    let more = format!("... ({} more)", total.saturating_sub(MAX_LISTED));
+    if total > MAX_LISTED {
+        listed.push(&more);
+    }
+    listed.join(", ")
+}
+
+/// Text report: one line per array, followed by its problems
+pub fn render_text(rows: &[(String, ArrayVerification)], require_all_chunks: bool) -> String {
+    let mut out = String::new();
+    let (mut missing, mut orphans, mut corrupt, mut wrong_size) = (0, 0, 0, 0);
+    for (name, verification) in rows {
+        let orphan_keys = verification.orphans.as_deref().unwrap_or_default();
+        let status = if verification.has_errors()
+            || (require_all_chunks && !verification.missing.is_empty())
+        {
+            "FAILED"
+        } else {
+            "ok"
+        };
+        let _ = writeln!(
+            out,
+            "{}: {}/{} chunks stored, {}",
+            name, verification.stored_chunks, verification.expected_chunks, status
+        );
+
+        if !verification.missing.is_empty() {
+            let _ = writeln!(
+                out,
+                "  missing ({}): {}",
+                verification.missing.len(),
+                list_keys(verification.missing.iter().map(String::as_str))
+            );
+        }
+        match &verification.orphans {
+            None => {
+                let _ = writeln!(out, "  orphans: not checked (the store cannot list keys)");
+            }
+            Some(keys) if !keys.is_empty() => {
+                let _ = writeln!(
+                    out,
+                    "  orphaned ({}): {}",
+                    keys.len(),
+                    list_keys(keys.iter().map(String::as_str))
+                );
+            }
+            Some(_) => {}
+        }
+        for (label, problems) in [
+            ("corrupt", &verification.corrupt),
+            ("wrong size", &verification.wrong_size),
+        ] {
+            for problem in problems.iter().take(MAX_LISTED) {
+                let _ = writeln!(out, "  {}: {}: {}", label, problem.key, problem.message);
+            }
+            if problems.len() > MAX_LISTED {
+                let _ = writeln!(
+                    out,
+                    "  {}: ... ({} more)",
+                    label,
+                    problems.len() - MAX_LISTED
+                );
+            }
+        }
+
+        missing += verification.missing.len();
+        orphans += orphan_keys.len();
+        corrupt += verification.corrupt.len();
+        wrong_size += verification.wrong_size.len();
+    }
+
+    let _ = writeln!(
+        out,
+        "\nVerified {} array(s): {} missing, {} orphaned, {} corrupt and {} wrong-size chunk(s)",
+        rows.len(),
+        missing,
+        orphans,
+        corrupt,
+        wrong_size
+    );
+    out
+}
+
+pub fn to_json(verification: &ArrayVerification) -> Value {
+    let problems = |problems: &[ChunkProblem]| {
+        problems
+            .iter()
+            .map(|p| json!({"key": p.key, "message": p.message}))
+            .collect::<Vec<_>>()
+    };
+    json!({
+        "expected_chunks": verification.expected_chunks,
+        "stored_chunks": verification.stored_chunks,
+        "missing": verification.missing,
+        "orphans": verification.orphans,
+        "corrupt": problems(&verification.corrupt),
+        "wrong_size": problems(&verification.wrong_size),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_report() {
+        let verification = ArrayVerification {
+            expected_chunks: 20,
+            stored_chunks: 7,
+            missing: (0..13).map(|i| i.to_string()).collect(),
+            orphans: Some(vec!["99".to_string()]),
+            corrupt: vec![ChunkProblem {
+                key: "3".to_string(),
+                message: "bad header".to_string(),
+            }],
+            wrong_size: Vec::new(),
+        };
+        let text = render_text(&[("tas".to_string(), verification.clone())], false);
+        assert_eq!(
+            text,
+            "tas: 7/20 chunks stored, FAILED\n  \
+             missing (13): 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, ... (3 more)\n  \
+             orphaned (1): 99\n  \
+             corrupt: 3: bad header\n\
+             \nVerified 1 array(s): 13 missing, 1 orphaned, 1 corrupt and 0 wrong-size chunk(s)\n"
+        );
+
+        let json = to_json(&verification);
+        assert_eq!(json["orphans"][0], "99");
+        assert_eq!(json["corrupt"][0]["message"], "bad header");
+    }
+}
@@ -234,6 +234,20 @@ fn test_cli_with_hierarchical_store() {
         lines.iter().any(|line| line.contains("title")),
         "Missing title attribute"
     );
+
+    // Codec configuration is shown as a special attribute
+    assert!(
+        lines.iter().any(|line| line.contains(
+            "pressure:_Codecs = \"blosc(cname=zstd, clevel=3, shuffle=shuffle, blocksize=0)\" ;"
+        )),
+        "Missing pressure codecs"
+    );
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.contains("temperature:_Codecs = \"zlib(level=1)\" ;")),
+        "Missing temperature codecs"
+    );
 }
 
 #[test]
@@ -509,3 +523,2261 @@ fn test_dimension_inference_integration() {
         "x dimension should not be unlimited"
     );
 }
+
+#[test]
+fn test_cli_json_output() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let store_path = temp_dir.path();
+
+    create_sample_store(store_path).expect("Failed to create sample store");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .arg(store_path.to_str().unwrap())
+        .arg("--format")
+        .arg("json")
+        .output()
+        .expect("Failed to execute zarr-dump");
+
+    assert!(
+        output.status.success(),
+        "Command failed with status: {:?}\nStderr: {}",
+        output.status,
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // stdout must be a single JSON document with no progress messages mixed in
+    let doc: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout should be valid JSON");
+
+    assert_eq!(doc["schema_version"], 1);
+    assert_eq!(doc["zarr_format"], 2);
+    assert_eq!(doc["global_attributes"]["title"], "Sample Climate Dataset");
+
+    let dims = doc["dimensions"].as_array().unwrap();
+    let record = dims.iter().find(|d| d["name"] == "record").unwrap();
+    assert_eq!(record["unlimited"], true);
+
+    let vars = doc["variables"].as_array().unwrap();
+    let paths: Vec<&str> = vars.iter().map(|v| v["path"].as_str().unwrap()).collect();
+    assert_eq!(paths, vec!["pressure", "temperature", "unlimited_var"]);
+
+    let temperature = &vars[1];
+    assert_eq!(temperature["dtype"], "<f4");
+    assert_eq!(temperature["shape"], serde_json::json!([365, 180, 360]));
+    assert_eq!(temperature["chunks"], serde_json::json!([1, 180, 360]));
+    assert_eq!(temperature["compressor"], "zlib");
+    assert_eq!(temperature["fill_value"], -9999.0);
+    assert_eq!(
+        temperature["dimensions"],
+        serde_json::json!(["time", "lat", "lon"])
+    );
+    assert_eq!(temperature["attributes"]["units"], "degrees_C");
+
+    assert!(doc["cf"]["candidate_data_vars"].is_array());
+}
+
+#[test]
+fn test_cli_cf_check_json_report() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let store_path = temp_dir.path();
+
+    create_sample_store(store_path).expect("Failed to create sample store");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .arg("cf-check")
+        .arg(store_path.to_str().unwrap())
+        .arg("--format")
+        .arg("json")
+        .output()
+        .expect("Failed to execute zarr-dump cf-check");
+
+    assert!(output.status.success());
+
+    let report: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout should be valid JSON");
+    let issues = report["issues"].as_array().unwrap();
+
+    // The sample store has no Conventions attribute and no coordinate variables
+    let conventions = issues
+        .iter()
+        .find(|i| i["code"] == "CF-CONVENTIONS-MISSING")
+        .expect("missing Conventions should be reported");
+    assert_eq!(conventions["level"], "warning");
+    assert_eq!(conventions["cf_section"], "2.6.1");
+    assert!(conventions["variable"].is_null());
+    assert!(conventions["suggestion"].is_string());
+
+    assert!(issues.iter().any(|i| i["code"] == "CF-DIM-NO-COORD"));
+    assert_eq!(
+        report["summary"]["warnings"].as_u64().unwrap() as usize,
+        issues.iter().filter(|i| i["level"] == "warning").count()
+    );
+}
+
+#[test]
+fn test_cli_cf_check_sarif_and_junit_reports() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let store_path = temp_dir.path();
+
+    create_sample_store(store_path).expect("Failed to create sample store");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .args([
+            "cf-check",
+            store_path.to_str().unwrap(),
+            "--format",
+            "sarif",
+        ])
+        .output()
+        .expect("Failed to execute zarr-dump cf-check");
+    assert!(output.status.success());
+
+    let sarif: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout should be valid SARIF JSON");
+    assert_eq!(sarif["version"], "2.1.0");
+    assert!(
+        sarif["runs"][0]["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|r| r["ruleId"] == "CF-CONVENTIONS-MISSING")
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .args([
+            "cf-check",
+            store_path.to_str().unwrap(),
+            "--format",
+            "junit",
+        ])
+        .output()
+        .expect("Failed to execute zarr-dump cf-check");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("<?xml"));
+    assert!(stdout.contains("<testcase classname=\"cf-check\" name=\"temperature\">"));
+    assert!(stdout.contains("type=\"CF-CONVENTIONS-MISSING\""));
+}
+
+/// Helper function to pack a store directory into a zip archive, optionally under a top-level folder
+fn zip_store(store_dir: &Path, zip_path: &Path, root: &str) -> std::io::Result<()> {
+    use std::io::Write;
+
+    fn add_dir(
+        writer: &mut zip::ZipWriter<fs::File>,
+        dir: &Path,
+        prefix: &str,
+    ) -> std::io::Result<()> {
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+            if entry.path().is_dir() {
+                add_dir(writer, &entry.path(), &format!("{}/", name))?;
+            } else {
+                writer.start_file(name, options)?;
+                writer.write_all(&fs::read(entry.path())?)?;
+            }
+        }
+        Ok(())
+    }
+
+    let mut writer = zip::ZipWriter::new(fs::File::create(zip_path)?);
+    add_dir(&mut writer, store_dir, root)?;
+    writer.finish()?;
+    Ok(())
+}
+
+#[test]
+fn test_cli_zip_store() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let store_path = temp_dir.path().join("sample.zarr");
+    fs::create_dir_all(&store_path).unwrap();
+    create_sample_store(&store_path).expect("Failed to create sample store");
+
+    // Add a small uncompressed coordinate variable so data can be read from the archive
+    fs::create_dir_all(store_path.join("x")).unwrap();
+    fs::write(
+        store_path.join("x").join(".zarray"),
+        r#"{"zarr_format": 2, "shape": [3], "chunks": [3], "dtype": "<f8",
+            "compressor": null, "fill_value": null, "order": "C", "filters": null}"#,
+    )
+    .unwrap();
+    fs::write(
+        store_path.join("x").join(".zattrs"),
+        r#"{"_ARRAY_DIMENSIONS": ["x"], "units": "m"}"#,
+    )
+    .unwrap();
+    let values: Vec<u8> = [10.0f64, 20.0, 30.0]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    fs::write(store_path.join("x").join("0"), values).unwrap();
+
+    // Archive with the store nested under a top-level folder, as `zip -r` would produce
+    let zip_path = temp_dir.path().join("sample.zarr.zip");
+    zip_store(&store_path, &zip_path, "sample.zarr/").expect("Failed to create zip archive");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .arg(zip_path.to_str().unwrap())
+        .arg("--no-color")
+        .arg("-c")
+        .output()
+        .expect("Failed to execute zarr-dump");
+
+    assert!(
+        output.status.success(),
+        "Command failed with status: {:?}\nStderr: {}",
+        output.status,
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("float temperature(time, lat, lon)"));
+    assert!(stdout.contains("double x(x)"));
+    assert!(stdout.contains(":title = \"Sample Climate Dataset\""));
+    assert!(stdout.contains("10, 20, 30"), "stdout: {}", stdout);
+}
+
+/// Helper function to serve a store directory over HTTP from a background thread.
+///
+/// Honours `Range` requests like a static file server would; every `Range` header received is
+/// recorded so tests can check that partial reads were used.
+fn serve_directory(root: &Path) -> (String, std::sync::Arc<std::sync::Mutex<Vec<String>>>) {
+    let server = tiny_http::Server::http("127.0.0.1:0").expect("Failed to start HTTP server");
+    let port = server.server_addr().to_ip().unwrap().port();
+    let root = root.to_path_buf();
+    let ranges = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let seen = ranges.clone();
+
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let path = root.join(request.url().trim_start_matches('/'));
+            let Ok(data) = fs::read(&path) else {
+                let _ = request.respond(tiny_http::Response::empty(404));
+                continue;
+            };
+
+            let range = request
+                .headers()
+                .iter()
+                .find(|h| h.field.equiv("Range"))
+                .map(|h| h.value.to_string());
+            let response = match range {
+                Some(range) => {
+                    seen.lock().unwrap().push(range.clone());
+                    let spec = range.trim_start_matches("bytes=");
+                    let (start, end) = spec.split_once('-').unwrap();
+                    let (start, end) = if start.is_empty() {
+                        (data.len() - end.parse::<usize>().unwrap(), data.len())
+                    } else {
+                        let start: usize = start.parse().unwrap();
+                        (start, end.parse::<usize>().map_or(data.len(), |e| e + 1))
+                    };
+                    tiny_http::Response::from_data(data[start..end].to_vec()).with_status_code(206)
+                }
+                None => tiny_http::Response::from_data(data),
+            };
+            let _ = request.respond(response);
+        }
+    });
+
+    (format!("http://127.0.0.1:{}", port), ranges)
+}
+
+#[test]
+fn test_cli_http_store() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let store_path = temp_dir.path().join("remote.zarr");
+    fs::create_dir_all(store_path.join("x")).unwrap();
+    create_consolidated_store(&store_path).expect("Failed to create consolidated store");
+
+    // Coordinate data referenced by the consolidated metadata, fetched on demand with -c
+    let zmetadata = fs::read_to_string(store_path.join(".zmetadata")).unwrap();
+    let mut consolidated: serde_json::Value = serde_json::from_str(&zmetadata).unwrap();
+    consolidated["metadata"]["x/.zarray"] = serde_json::json!({
+        "zarr_format": 2, "shape": [3], "chunks": [3], "dtype": "<f8",
+        "compressor": null, "fill_value": null, "order": "C", "filters": null
+    });
+    consolidated["metadata"]["x/.zattrs"] = serde_json::json!({"_ARRAY_DIMENSIONS": ["x"]});
+    fs::write(store_path.join(".zmetadata"), consolidated.to_string()).unwrap();
+    let values: Vec<u8> = [1.5f64, 2.5, 3.5]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    fs::write(store_path.join("x").join("0"), values).unwrap();
+
+    let (base_url, _) = serve_directory(temp_dir.path());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .arg(format!("{}/remote.zarr", base_url))
+        .arg("--no-color")
+        .arg("-c")
+        .output()
+        .expect("Failed to execute zarr-dump");
+
+    assert!(
+        output.status.success(),
+        "Command failed with status: {:?}\nStderr: {}",
+        output.status,
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Loaded consolidated metadata"));
+    assert!(stdout.contains("double x(x)"));
+    assert!(stdout.contains("1.5, 2.5, 3.5"), "stdout: {}", stdout);
+
+    // Without consolidated metadata there is no way to discover arrays over HTTP
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .arg(format!("{}/missing.zarr", base_url))
+        .output()
+        .expect("Failed to execute zarr-dump");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("HTTP stores cannot be listed"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_http_store_reads_sharded_chunks_with_range_requests() {
+    use std::sync::Arc;
+    use zarrs::array::codec::array_to_bytes::sharding::ShardingCodecBuilder;
+    use zarrs::array::{ArrayBuilder, DataType, FillValue};
+
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let store_path = temp_dir.path().join("sharded.zarr");
+    fs::create_dir_all(&store_path).unwrap();
+
+    // One shard of 8 elements made of 4 inner chunks
+    let storage = Arc::new(zarrs::storage::store::FilesystemStore::new(&store_path).unwrap());
+    let array = ArrayBuilder::new(
+        vec![8],
+        DataType::Float64,
+        vec![8].try_into().unwrap(),
+        FillValue::from(f64::NAN),
+    )
+    .array_to_bytes_codec(Box::new(
+        ShardingCodecBuilder::new(vec![2].try_into().unwrap()).build(),
+    ))
+    .dimension_names(Some(["x"]))
+    .build(storage, "/x")
+    .unwrap();
+    array.store_metadata().unwrap();
+    array
+        .store_chunk_elements(&[0], &[0.0f64, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0])
+        .unwrap();
+
+    // Root group with consolidated metadata, so the store can be opened without listing
+    let array_json: serde_json::Value =
+        serde_json::from_slice(&fs::read(store_path.join("x").join("zarr.json")).unwrap()).unwrap();
+    let root = serde_json::json!({
+        "zarr_format": 3,
+        "node_type": "group",
+        "attributes": {},
+        "consolidated_metadata": {
+            "kind": "inline",
+            "must_understand": false,
+            "metadata": {"x": array_json}
+        }
+    });
+    fs::write(store_path.join("zarr.json"), root.to_string()).unwrap();
+
+    let (base_url, ranges) = serve_directory(temp_dir.path());
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let store = zarr_dump::ZarrStore::new(format!("{}/sharded.zarr", base_url))
+        .unwrap()
+        .quiet(true);
+    let metadata = runtime.block_on(store.load_metadata()).unwrap();
+    let variable = &metadata.variables["x"];
+
+    let values = store
+        .read_array_subset_f64(variable, &[std::ops::Range { start: 2, end: 4 }])
+        .unwrap();
+    assert_eq!(values, vec![2.0, 3.0]);
+
+    // Only the shard index (at the end of the shard) and the requested inner chunk are fetched
+    let ranges = ranges.lock().unwrap();
+    assert_eq!(ranges.len(), 2, "ranges: {:?}", ranges);
+    assert_eq!(ranges[1], "bytes=16-31");
+}
+
+/// Helper function to emulate an S3-compatible object store (path-style addressing) over a
+/// directory whose subdirectories are buckets.
+///
+/// Supports GET/HEAD of objects (with `Range`) and paginated `ListObjectsV2`. Returns the endpoint
+/// URL and the number of requests that carried a SigV4 signature.
+fn serve_s3(root: &Path) -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>) {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn collect_keys(dir: &Path, prefix: &str, keys: &mut Vec<String>) {
+        for entry in fs::read_dir(dir).unwrap().flatten() {
+            let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+            if entry.path().is_dir() {
+                collect_keys(&entry.path(), &format!("{}/", name), keys);
+            } else {
+                keys.push(name);
+            }
+        }
+    }
+
+    let server = tiny_http::Server::http("127.0.0.1:0").expect("Failed to start S3 server");
+    let port = server.server_addr().to_ip().unwrap().port();
+    let root = root.to_path_buf();
+    let signed = std::sync::Arc::new(AtomicUsize::new(0));
+    let signed_requests = signed.clone();
+
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let url = url::Url::parse(&format!("http://localhost{}", request.url())).unwrap();
+            let query: std::collections::HashMap<String, String> =
+                url.query_pairs().into_owned().collect();
+            if query.contains_key("X-Amz-Signature") {
+                signed_requests.fetch_add(1, Ordering::SeqCst);
+            }
+
+            let path = url.path().trim_start_matches('/').to_string();
+            let (bucket, key) = path.split_once('/').unwrap_or((&path, ""));
+
+            if query.get("list-type").map(String::as_str) == Some("2") {
+                // Two keys per page to exercise continuation tokens
+                let mut keys = Vec::new();
+                collect_keys(&root.join(bucket), "", &mut keys);
+                keys.sort();
+                let prefix = query.get("prefix").cloned().unwrap_or_default();
+                keys.retain(|k| k.starts_with(&prefix));
+                let start: usize = query
+                    .get("continuation-token")
+                    .map_or(0, |t| t.parse().unwrap());
+                let end = (start + 2).min(keys.len());
+
+                let mut xml = String::from(
+                    r#"<?xml version="1.0" encoding="UTF-8"?><ListBucketResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">"#,
+                );
+                for key in &keys[start..end] {
+                    let size = fs::metadata(root.join(bucket).join(key)).unwrap().len();
+                    xml.push_str(&format!(
+                        "<Contents><Key>{}</Key><LastModified>2024-01-01T00:00:00.000Z</LastModified><ETag>\"0\"</ETag><Size>{}</Size></Contents>",
+                        key, size
+                    ));
+                }
+                if end < keys.len() {
+                    xml.push_str(&format!(
+                        "<NextContinuationToken>{}</NextContinuationToken>",
+                        end
+                    ));
+                }
+                xml.push_str("</ListBucketResult>");
+                let _ = request.respond(tiny_http::Response::from_string(xml));
+                continue;
+            }
+
+            let Ok(data) = fs::read(root.join(bucket).join(key)) else {
+                let _ = request.respond(tiny_http::Response::empty(404));
+                continue;
+            };
+            let range = request
+                .headers()
+                .iter()
+                .find(|h| h.field.equiv("Range"))
+                .map(|h| h.value.to_string());
+            let response = match range {
+                Some(range) => {
+                    let (start, end) = range.trim_start_matches("bytes=").split_once('-').unwrap();
+                    let start: usize = start.parse().unwrap();
+                    let end = end.parse::<usize>().map_or(data.len(), |e| e + 1);
+                    tiny_http::Response::from_data(data[start..end].to_vec()).with_status_code(206)
+                }
+                None => tiny_http::Response::from_data(data),
+            };
+            let _ = request.respond(response);
+        }
+    });
+
+    (format!("http://127.0.0.1:{}", port), signed)
+}
+
+#[test]
+fn test_cli_s3_store() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+
+    // Hierarchical (unconsolidated) store, discovered by listing the bucket prefix
+    let store_path = temp_dir
+        .path()
+        .join("bucket")
+        .join("data")
+        .join("sample.zarr");
+    fs::create_dir_all(&store_path).unwrap();
+    create_sample_store(&store_path).expect("Failed to create sample store");
+    fs::create_dir_all(store_path.join("x")).unwrap();
+    fs::write(
+        store_path.join("x").join(".zarray"),
+        r#"{"zarr_format": 2, "shape": [2], "chunks": [2], "dtype": "<f8",
+            "compressor": null, "fill_value": null, "order": "C", "filters": null}"#,
+    )
+    .unwrap();
+    fs::write(
+        store_path.join("x").join(".zattrs"),
+        r#"{"_ARRAY_DIMENSIONS": ["x"]}"#,
+    )
+    .unwrap();
+    let values: Vec<u8> = [4.0f64, 8.0].iter().flat_map(|v| v.to_le_bytes()).collect();
+    fs::write(store_path.join("x").join("0"), values).unwrap();
+
+    let (endpoint, signed) = serve_s3(temp_dir.path());
+    let no_file = temp_dir.path().join("no-such-aws-file");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .arg("s3://bucket/data/sample.zarr")
+        .arg("--no-color")
+        .arg("-c")
+        .env("AWS_ENDPOINT_URL", &endpoint)
+        .env("AWS_ACCESS_KEY_ID", "minioadmin")
+        .env("AWS_SECRET_ACCESS_KEY", "minioadmin")
+        .env("AWS_REGION", "us-east-1")
+        .env("AWS_CONFIG_FILE", &no_file)
+        .env("AWS_SHARED_CREDENTIALS_FILE", &no_file)
+        .output()
+        .expect("Failed to execute zarr-dump");
+
+    assert!(
+        output.status.success(),
+        "Command failed with status: {:?}\nStderr: {}",
+        output.status,
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Falling back to hierarchical scanning"));
+    assert!(stdout.contains("float temperature(time, lat, lon)"));
+    assert!(stdout.contains("double x(x)"));
+    assert!(stdout.contains(":title = \"Sample Climate Dataset\""));
+    assert!(stdout.contains("4, 8"), "stdout: {}", stdout);
+
+    assert!(
+        signed.load(std::sync::atomic::Ordering::SeqCst) > 0,
+        "requests should be signed when credentials are set"
+    );
+}
+
+#[test]
+fn test_cli_kerchunk_references() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+
+    // A stand-in for a NetCDF/HDF5 file: the coordinate values sit after an opaque header
+    let mut netcdf = b"CDF\x01 opaque header".to_vec();
+    let offset = netcdf.len();
+    netcdf.extend([0.25f64, 0.5, 0.75].iter().flat_map(|v| v.to_le_bytes()));
+    fs::write(temp_dir.path().join("source.nc"), &netcdf).unwrap();
+
+    let refs = serde_json::json!({
+        "version": 1,
+        "templates": {"src": temp_dir.path().join("source.nc").to_str().unwrap()},
+        "refs": {
+            ".zgroup": r#"{"zarr_format": 2}"#,
+            ".zattrs": r#"{"title": "Virtual dataset"}"#,
+            "lat/.zarray": r#"{"zarr_format": 2, "shape": [3], "chunks": [3], "dtype": "<f8", "compressor": null, "fill_value": null, "order": "C", "filters": null}"#,
+            "lat/.zattrs": r#"{"_ARRAY_DIMENSIONS": ["lat"], "units": "degrees_north"}"#,
+            "lat/0": ["{{src}}", offset, 24],
+            "flag/.zarray": r#"{"zarr_format": 2, "shape": [2], "chunks": [2], "dtype": "<f4", "compressor": null, "fill_value": null, "order": "C", "filters": null}"#,
+            "flag/.zattrs": r#"{"_ARRAY_DIMENSIONS": ["flag"]}"#,
+            "flag/0": "base64:AACgQAAA4EA="
+        }
+    });
+    let refs_path = temp_dir.path().join("refs.json");
+    fs::write(&refs_path, refs.to_string()).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .arg(refs_path.to_str().unwrap())
+        .arg("--no-color")
+        .arg("-c")
+        .output()
+        .expect("Failed to execute zarr-dump");
+
+    assert!(
+        output.status.success(),
+        "Command failed with status: {:?}\nStderr: {}",
+        output.status,
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("double lat(lat)"));
+    assert!(stdout.contains(":title = \"Virtual dataset\""));
+    assert!(stdout.contains("0.25, 0.5, 0.75"), "stdout: {}", stdout);
+    assert!(stdout.contains("5, 7"), "stdout: {}", stdout);
+}
+
+/// Helper function to create a Zarr v3 store with nested groups (`model/historical`)
+fn create_v3_group_store(temp_dir: &Path, consolidated: bool) -> std::io::Result<()> {
+    let model = serde_json::json!({
+        "zarr_format": 3,
+        "node_type": "group",
+        "attributes": {"source_id": "TEST-MODEL"}
+    });
+    let historical = serde_json::json!({
+        "zarr_format": 3,
+        "node_type": "group",
+        "attributes": {"experiment_id": "historical"}
+    });
+    let tas = serde_json::json!({
+        "zarr_format": 3,
+        "node_type": "array",
+        "shape": [12, 4],
+        "data_type": "float32",
+        "chunk_grid": {"name": "regular", "configuration": {"chunk_shape": [6, 4]}},
+        "chunk_key_encoding": {"name": "default", "configuration": {"separator": "/"}},
+        "fill_value": "NaN",
+        "codecs": [{"name": "bytes", "configuration": {"endian": "little"}}],
+        "attributes": {"units": "K"},
+        "dimension_names": ["time", "site"]
+    });
+
+    let mut root = serde_json::json!({
+        "zarr_format": 3,
+        "node_type": "group",
+        "attributes": {"title": "Nested v3 groups"}
+    });
+
+    if consolidated {
+        root["consolidated_metadata"] = serde_json::json!({
+            "kind": "inline",
+            "must_understand": false,
+            "metadata": {
+                "model": model,
+                "model/historical": historical,
+                "model/historical/tas": tas
+            }
+        });
+    } else {
+        let historical_dir = temp_dir.join("model").join("historical");
+        fs::create_dir_all(historical_dir.join("tas"))?;
+        fs::write(temp_dir.join("model").join("zarr.json"), model.to_string())?;
+        fs::write(historical_dir.join("zarr.json"), historical.to_string())?;
+        fs::write(
+            historical_dir.join("tas").join("zarr.json"),
+            tas.to_string(),
+        )?;
+    }
+
+    fs::write(temp_dir.join("zarr.json"), root.to_string())
+}
+
+#[test]
+fn test_cli_v3_group_hierarchy() {
+    for consolidated in [false, true] {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        create_v3_group_store(temp_dir.path(), consolidated)
+            .expect("Failed to create v3 group store");
+
+        let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+            .arg(temp_dir.path().to_str().unwrap())
+            .arg("--no-color")
+            .output()
+            .expect("Failed to execute zarr-dump");
+
+        assert!(
+            output.status.success(),
+            "Command failed with status: {:?}\nStderr: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            stdout.contains("float tas(time, site)"),
+            "stdout: {}",
+            stdout
+        );
+        assert!(stdout.contains("group: model {"), "stdout: {}", stdout);
+        assert!(stdout.contains(":source_id = \"TEST-MODEL\""));
+        assert!(stdout.contains("    group: historical {"));
+        assert!(stdout.contains(":experiment_id = \"historical\""));
+        // Variables and dimensions are declared inside the group that holds them
+        assert!(
+            stdout.contains(
+                "    group: historical {\n        dimensions:\n            site = 4 ;\n            time = 12 ;\n        variables:\n            float tas(time, site) ;\n                tas:"
+            ),
+            "stdout: {}",
+            stdout
+        );
+        assert!(stdout.contains("        // group attributes:\n            :experiment_id"));
+        assert!(stdout.contains("    } // group historical\n} // group model"));
+        assert!(!stdout.starts_with("dimensions:") && !stdout.contains("\ndimensions:"));
+
+        // Groups and their children are also part of the JSON document
+        let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+            .arg(temp_dir.path().to_str().unwrap())
+            .arg("--format")
+            .arg("json")
+            .output()
+            .expect("Failed to execute zarr-dump");
+        let doc: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let groups = doc["groups"].as_array().unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0]["path"], "model");
+        assert_eq!(groups[0]["children"], serde_json::json!(["historical"]));
+        assert_eq!(groups[1]["attributes"]["experiment_id"], "historical");
+        assert_eq!(groups[1]["children"], serde_json::json!(["tas"]));
+    }
+}
+
+#[test]
+fn test_cli_v3_fill_values() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let root = temp_dir.path();
+
+    fs::write(
+        root.join("zarr.json"),
+        r#"{"zarr_format": 3, "node_type": "group", "attributes": {}}"#,
+    )
+    .unwrap();
+
+    // Coordinate whose fill value (-999.0) is given as a hex bit pattern
+    let x = serde_json::json!({
+        "zarr_format": 3,
+        "node_type": "array",
+        "shape": [4],
+        "data_type": "float64",
+        "chunk_grid": {"name": "regular", "configuration": {"chunk_shape": [4]}},
+        "chunk_key_encoding": {"name": "default", "configuration": {"separator": "/"}},
+        "fill_value": "0xc08f380000000000",
+        "codecs": [{"name": "bytes", "configuration": {"endian": "little"}}],
+        "dimension_names": ["x"]
+    });
+    fs::create_dir_all(root.join("x").join("c")).unwrap();
+    fs::write(root.join("x").join("zarr.json"), x.to_string()).unwrap();
+    let values: Vec<u8> = [1.0f64, -999.0, 3.0, f64::NAN]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    fs::write(root.join("x").join("c").join("0"), values).unwrap();
+
+    let mask = serde_json::json!({
+        "zarr_format": 3,
+        "node_type": "array",
+        "shape": [4],
+        "data_type": "uint8",
+        "chunk_grid": {"name": "regular", "configuration": {"chunk_shape": [4]}},
+        "chunk_key_encoding": {"name": "default", "configuration": {"separator": "/"}},
+        "fill_value": 255,
+        "codecs": [{"name": "bytes"}],
+        "dimension_names": ["x"]
+    });
+    fs::create_dir_all(root.join("mask")).unwrap();
+    fs::write(root.join("mask").join("zarr.json"), mask.to_string()).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .arg(root.to_str().unwrap())
+        .arg("--no-color")
+        .arg("-c")
+        .output()
+        .expect("Failed to execute zarr-dump");
+    assert!(
+        output.status.success(),
+        "Stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("x:_FillValue = -999 ;"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains("mask:_FillValue = 255 ;"),
+        "stdout: {}",
+        stdout
+    );
+    // The fill value is masked, while a NaN that is not the fill value is shown as-is
+    assert!(stdout.contains(" x = 1, _, 3, NaN ;"), "stdout: {}", stdout);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .arg(root.to_str().unwrap())
+        .arg("--format")
+        .arg("json")
+        .arg("-c")
+        .output()
+        .expect("Failed to execute zarr-dump");
+    let doc: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let variables = doc["variables"].as_array().unwrap();
+    assert_eq!(variables[0]["fill_value"], 255);
+    assert_eq!(variables[1]["fill_value"], -999.0);
+    assert_eq!(
+        doc["coordinate_data"]["x"],
+        serde_json::json!([1.0, null, 3.0, null])
+    );
+}
+
+#[test]
+fn test_cli_shard_inventory() {
+    use std::sync::Arc;
+    use zarrs::array::codec::array_to_bytes::sharding::ShardingCodecBuilder;
+    use zarrs::array::{ArrayBuilder, DataType, FillValue};
+
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let root = temp_dir.path();
+    fs::write(
+        root.join("zarr.json"),
+        r#"{"zarr_format": 3, "node_type": "group", "attributes": {}}"#,
+    )
+    .unwrap();
+
+    // Two shards of 2x4 elements, each made of two 2x2 inner chunks
+    let storage = Arc::new(zarrs::storage::store::FilesystemStore::new(root).unwrap());
+    let array = ArrayBuilder::new(
+        vec![4, 4],
+        DataType::Float64,
+        vec![2, 4].try_into().unwrap(),
+        FillValue::from(f64::NAN),
+    )
+    .array_to_bytes_codec(Box::new(
+        ShardingCodecBuilder::new(vec![2, 2].try_into().unwrap()).build(),
+    ))
+    .dimension_names(Some(["y", "x"]))
+    .build(storage, "/field")
+    .unwrap();
+    array.store_metadata().unwrap();
+    // Only the second inner chunk of the first shard holds data; the second shard is never written
+    array
+        .store_chunk_elements(
+            &[0, 0],
+            &[f64::NAN, f64::NAN, 1.0, 2.0, f64::NAN, f64::NAN, 3.0, 4.0],
+        )
+        .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .arg(root.to_str().unwrap())
+        .arg("--no-color")
+        .arg("--shards")
+        .output()
+        .expect("Failed to execute zarr-dump");
+    assert!(
+        output.status.success(),
+        "Stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(
+            "// sharding: inner chunks [2, 2], codecs [bytes], index codecs [bytes, crc32c] at end"
+        ),
+        "stdout: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains("// shards: 1 of 2 present, 1 of 4 inner chunks populated"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(stdout.contains("//   c/0/0: 1 of 2 inner chunks"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .arg(root.to_str().unwrap())
+        .arg("--format")
+        .arg("json")
+        .arg("--shards")
+        .output()
+        .expect("Failed to execute zarr-dump");
+    let doc: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let sharding = &doc["variables"][0]["sharding"];
+    assert_eq!(sharding["inner_chunk_shape"], serde_json::json!([2, 2]));
+    assert_eq!(sharding["index_location"], "end");
+    assert_eq!(sharding["index_codecs"][1]["name"], "crc32c");
+    assert_eq!(
+        doc["shard_inventory"]["field"],
+        serde_json::json!({
+            "total_shards": 2,
+            "inner_chunks_per_shard": 2,
+            "shards": [{"key": "c/0/0", "populated_inner_chunks": 1}]
+        })
+    );
+}
+
+#[test]
+fn test_cli_special_attributes() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    create_sample_store(temp_dir.path()).expect("Failed to create sample store");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .arg(temp_dir.path().to_str().unwrap())
+        .arg("--no-color")
+        .arg("-s")
+        .output()
+        .expect("Failed to execute zarr-dump");
+    assert!(
+        output.status.success(),
+        "Stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for expected in [
+        "temperature:_Storage = \"chunked\" ;",
+        "temperature:_ChunkSizes = [1, 180, 360] ;",
+        "temperature:_Endianness = \"little\" ;",
+        "temperature:_Compressor = \"zlib(level=1)\" ;",
+        "temperature:_FillValueSource = \"metadata\" ;",
+        "temperature:_Order = \"C\" ;",
+        "temperature:_DimensionSeparator = \".\" ;",
+        ":_Format = \"zarr v2\" ;",
+        ":_Consolidated = false ;",
+    ] {
+        assert!(
+            stdout.contains(expected),
+            "missing '{}' in: {}",
+            expected,
+            stdout
+        );
+    }
+
+    // Without -s the virtual attributes are not shown
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .arg(temp_dir.path().to_str().unwrap())
+        .arg("--no-color")
+        .output()
+        .expect("Failed to execute zarr-dump");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("_ChunkSizes"));
+    assert!(!stdout.contains("_Format"));
+
+    // v3: endianness comes from the bytes codec and chunk keys use `/`
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    create_v3_group_store(temp_dir.path(), true).expect("Failed to create v3 group store");
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .arg(temp_dir.path().to_str().unwrap())
+        .arg("--no-color")
+        .arg("--special")
+        .output()
+        .expect("Failed to execute zarr-dump");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for expected in [
+        "tas:_Endianness = \"little\" ;",
+        "tas:_DimensionSeparator = \"/\" ;",
+        ":_Format = \"zarr v3\" ;",
+        ":_Consolidated = true ;",
+    ] {
+        assert!(
+            stdout.contains(expected),
+            "missing '{}' in: {}",
+            expected,
+            stdout
+        );
+    }
+}
+
+fn create_cf_store(temp_dir: &Path) -> std::io::Result<()> {
+    fs::write(temp_dir.join(".zgroup"), r#"{"zarr_format": 2}"#)?;
+    fs::write(temp_dir.join(".zattrs"), r#"{"title": "CF store"}"#)?;
+
+    let arrays = [
+        ("time", "[4]", r#"{"_ARRAY_DIMENSIONS": ["time"]}"#),
+        (
+            "lat",
+            "[3]",
+            r#"{"_ARRAY_DIMENSIONS": ["lat"], "bounds": "lat_bnds"}"#,
+        ),
+        (
+            "lat_bnds",
+            "[3, 2]",
+            r#"{"_ARRAY_DIMENSIONS": ["lat", "nv"]}"#,
+        ),
+        (
+            "crs",
+            "[]",
+            r#"{"_ARRAY_DIMENSIONS": [], "grid_mapping_name": "latitude_longitude"}"#,
+        ),
+        (
+            "tas",
+            "[4, 3]",
+            r#"{"_ARRAY_DIMENSIONS": ["time", "lat"], "grid_mapping": "crs"}"#,
+        ),
+        ("pr", "[4, 3]", r#"{"_ARRAY_DIMENSIONS": ["time", "lat"]}"#),
+        ("station", "[5]", r#"{"_ARRAY_DIMENSIONS": ["station"]}"#),
+    ];
+    for (name, shape, attrs) in arrays {
+        fs::create_dir_all(temp_dir.join(name))?;
+        let chunks = if shape == "[]" { "[]" } else { shape };
+        fs::write(
+            temp_dir.join(name).join(".zarray"),
+            format!(
+                r#"{{"zarr_format": 2, "shape": {}, "chunks": {}, "dtype": "<f8", "compressor": null, "fill_value": "NaN", "order": "C", "filters": null}}"#,
+                shape, chunks
+            ),
+        )?;
+        fs::write(temp_dir.join(name).join(".zattrs"), attrs)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_cli_variable_filter() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    create_cf_store(temp_dir.path()).expect("Failed to create CF store");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .arg(temp_dir.path().to_str().unwrap())
+        .arg("--no-color")
+        .arg("-v")
+        .arg("tas")
+        .output()
+        .expect("Failed to execute zarr-dump");
+    assert!(
+        output.status.success(),
+        "Stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // The variable plus its coordinates, their bounds and its grid mapping
+    for expected in [
+        "double tas(time, lat) ;",
+        "double time(time) ;",
+        "double lat(lat) ;",
+        "double lat_bnds(lat, nv) ;",
+        "crs",
+        "nv = 2 ;",
+    ] {
+        assert!(
+            stdout.contains(expected),
+            "missing '{}' in: {}",
+            expected,
+            stdout
+        );
+    }
+    assert!(!stdout.contains("pr("), "stdout: {}", stdout);
+    assert!(!stdout.contains("station"), "stdout: {}", stdout);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .arg(temp_dir.path().to_str().unwrap())
+        .arg("-v")
+        .arg("tas,missing")
+        .output()
+        .expect("Failed to execute zarr-dump");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Variable 'missing' not found"));
+}
+
+#[test]
+fn test_cli_group_filter() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    create_v3_group_store(temp_dir.path(), true).expect("Failed to create v3 group store");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .arg(temp_dir.path().to_str().unwrap())
+        .arg("--no-color")
+        .arg("-g")
+        .arg("/model/historical")
+        .output()
+        .expect("Failed to execute zarr-dump");
+    assert!(
+        output.status.success(),
+        "Stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("group: historical {"), "stdout: {}", stdout);
+    assert!(stdout.contains(":experiment_id = \"historical\""));
+    assert!(stdout.contains("float tas(time, site)"));
+    // Attributes outside the selected subtree are not shown
+    assert!(!stdout.contains("source_id"), "stdout: {}", stdout);
+    assert!(!stdout.contains("Nested v3 groups"), "stdout: {}", stdout);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .arg(temp_dir.path().to_str().unwrap())
+        .arg("-g")
+        .arg("model/future")
+        .output()
+        .expect("Failed to execute zarr-dump");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(
+            "Group 'model/future' not found in store. Available groups: model, model/historical"
+        ),
+        "stderr: {}",
+        stderr
+    );
+}
+
+fn create_data_store(root: &Path) -> std::io::Result<()> {
+    fs::write(root.join(".zgroup"), r#"{"zarr_format": 2}"#)?;
+
+    let arrays: [(&str, &str, &str, Vec<f64>); 3] = [
+        ("time", "[4]", r#"["time"]"#, vec![0.0, 1.0, 2.0, 3.0]),
+        (
+            "lat",
+            "[5]",
+            r#"["lat"]"#,
+            vec![-60.0, -30.0, 0.0, 30.0, 60.0],
+        ),
+        (
+            "tas",
+            "[4, 5]",
+            r#"["time", "lat"]"#,
+            (0..20)
+                .map(|i| match i {
+                    7 => 1.23456789,
+                    8 => -1.0,
+                    _ => i as f64,
+                })
+                .collect(),
+        ),
+    ];
+    for (name, shape, dims, values) in arrays {
+        fs::create_dir_all(root.join(name))?;
+        fs::write(
+            root.join(name).join(".zarray"),
+            format!(
+                r#"{{"zarr_format": 2, "shape": {shape}, "chunks": {shape}, "dtype": "<f8", "compressor": null, "fill_value": -1.0, "order": "C", "filters": null}}"#
+            ),
+        )?;
+        fs::write(
+            root.join(name).join(".zattrs"),
+            format!(r#"{{"_ARRAY_DIMENSIONS": {dims}}}"#),
+        )?;
+        let chunk_key = if name == "tas" { "0.0" } else { "0" };
+        let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        fs::write(root.join(name).join(chunk_key), bytes)?;
+    }
+    Ok(())
+}
+
+fn run_data_command(root: &Path, extra_args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .arg("data")
+        .arg(root.to_str().unwrap())
+        .arg("--no-color")
+        .args(extra_args)
+        .output()
+        .expect("Failed to execute zarr-dump")
+}
+
+#[test]
+fn test_cli_data_subcommand() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    create_data_store(temp_dir.path()).expect("Failed to create data store");
+
+    // Whole variables, fill values masked
+    let output = run_data_command(temp_dir.path(), &["-v", "tas,lat"]);
+    assert!(
+        output.status.success(),
+        "Stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("data:"), "stdout: {}", stdout);
+    assert!(
+        stdout.contains(" lat = -60, -30, 0, 30, 60 ;"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains(
+            " tas =\n  0, 1, 2, 3, 4,\n  5, 6, 1.23456789, _, 9,\n  10, 11, 12, 13, 14,\n  15, 16, 17, 18, 19 ;"
+        ),
+        "stdout: {}",
+        stdout
+    );
+    assert!(!stdout.contains("time ="), "stdout: {}", stdout);
+
+    // Index and coordinate hyperslabs with precision control
+    let output = run_data_command(
+        temp_dir.path(),
+        &[
+            "-v",
+            "tas",
+            "--isel",
+            "time=1:4:2",
+            "--sel",
+            "lat=-30:30",
+            "-p",
+            "3",
+        ],
+    );
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("// tas(time=1:4:2, lat=1:4)"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains(" tas =\n  6, 1.23, _,\n  16, 17, 18 ;"),
+        "stdout: {}",
+        stdout
+    );
+
+    // Row and column limits
+    let output = run_data_command(
+        temp_dir.path(),
+        &["-v", "tas", "--max-rows", "2", "--max-cols", "3"],
+    );
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(" tas =\n  0, 1, 2, ...,\n  5, 6, 1.23456789, ...,\n  ... ;"),
+        "stdout: {}",
+        stdout
+    );
+
+    // A single index drops to one row, nearest coordinate selection
+    let output = run_data_command(
+        temp_dir.path(),
+        &["-v", "tas", "--isel", "time=-1", "--sel", "lat=25"],
+    );
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(" tas =\n  18 ;"), "stdout: {}", stdout);
+
+    // Errors
+    let output = run_data_command(temp_dir.path(), &["--isel", "level=0"]);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("Dimension 'level' is not used by any of the printed variables")
+    );
+    let output = run_data_command(temp_dir.path(), &["--isel", "time=9"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Index 9 out of bounds"));
+}
+
+#[test]
+fn test_cli_data_strided_selection_skips_chunks() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let root = temp_dir.path();
+    fs::write(root.join(".zgroup"), r#"{"zarr_format": 2}"#).unwrap();
+    fs::create_dir(root.join("x")).unwrap();
+    fs::write(
+        root.join("x/.zarray"),
+        r#"{"zarr_format": 2, "shape": [4, 3], "chunks": [1, 3], "dtype": "<f8", "compressor": null, "fill_value": -1.0, "order": "C", "filters": null}"#,
+    )
+    .unwrap();
+    fs::write(
+        root.join("x/.zattrs"),
+        r#"{"_ARRAY_DIMENSIONS": ["y", "z"]}"#,
+    )
+    .unwrap();
+    for row in 0..4u8 {
+        let bytes: Vec<u8> = (0..3)
+            .flat_map(|col| f64::from(row * 3 + col).to_le_bytes())
+            .collect();
+        fs::write(root.join(format!("x/{}.0", row)), bytes).unwrap();
+    }
+    // The chunk of row 1 is corrupt, so only selections that skip it can be read
+    fs::write(root.join("x/1.0"), b"corrupt").unwrap();
+
+    let output = run_data_command(root, &["-v", "x", "--isel", "y=::2"]);
+    assert!(
+        output.status.success(),
+        "Stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(" x =\n  0, 1, 2,\n  6, 7, 8 ;"),
+        "stdout: {}",
+        stdout
+    );
+
+    let output = run_data_command(root, &["-v", "x", "--isel", "y=0:2"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(" x = <error reading data:"),
+        "stdout: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_cli_data_exact_integers() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let root = temp_dir.path();
+    let write_array = |path: &str, dtype: &str, fill_value: &str, dim: &str, bytes: Vec<u8>| {
+        let dir = root.join(path);
+        fs::create_dir_all(&dir).unwrap();
+        let len = bytes.len() / if dtype == "<i2" { 2 } else { 8 };
+        fs::write(
+            dir.join(".zarray"),
+            format!(
+                r#"{{"zarr_format": 2, "shape": [{len}], "chunks": [{len}], "dtype": "{dtype}", "compressor": null, "fill_value": {fill_value}, "order": "C", "filters": null}}"#
+            ),
+        )
+        .unwrap();
+        fs::write(
+            dir.join(".zattrs"),
+            format!(r#"{{"_ARRAY_DIMENSIONS": ["{dim}"]}}"#),
+        )
+        .unwrap();
+        fs::write(dir.join("0"), bytes).unwrap();
+    };
+    fs::write(root.join(".zgroup"), r#"{"zarr_format": 2}"#).unwrap();
+    fs::create_dir(root.join("g")).unwrap();
+    fs::write(root.join("g/.zgroup"), r#"{"zarr_format": 2}"#).unwrap();
+    // Timestamps above 2^53 that an f64 cannot tell apart; the fill value matches only the second
+    write_array(
+        "time",
+        "<i8",
+        "1600000000000000001",
+        "time",
+        [
+            1_600_000_000_000_000_000i64,
+            1_600_000_000_000_000_001,
+            20_000_000_000,
+        ]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect(),
+    );
+    write_array(
+        "ids",
+        "<u8",
+        "0",
+        "n",
+        [u64::MAX, 5].iter().flat_map(|v| v.to_le_bytes()).collect(),
+    );
+    write_array(
+        "g/time",
+        "<i2",
+        "0",
+        "t",
+        [1i16, 2].iter().flat_map(|v| v.to_le_bytes()).collect(),
+    );
+
+    let output = run_data_command(root, &["-v", "time,ids,g/time", "-p", "3"]);
+    assert!(
+        output.status.success(),
+        "Stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(" time = 1600000000000000000, _, 20000000000 ;"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains(" ids = 18446744073709551615, 5 ;"),
+        "stdout: {}",
+        stdout
+    );
+    // `-v time` also selects g/time, which is printed once, under its path
+    assert_eq!(
+        stdout.matches(" g/time = 1, 2 ;").count(),
+        1,
+        "stdout: {}",
+        stdout
+    );
+
+    let output = run_data_command(root, &["-v", "g/time", "--isel", "t=1"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("// g/time(t=1)"), "stdout: {}", stdout);
+}
+
+#[test]
+fn test_cli_nested_group_dimensions() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let root = temp_dir.path();
+    fs::write(root.join(".zgroup"), r#"{"zarr_format": 2}"#).unwrap();
+    fs::create_dir_all(root.join("ens")).unwrap();
+    fs::write(root.join("ens").join(".zgroup"), r#"{"zarr_format": 2}"#).unwrap();
+    fs::write(root.join("ens").join(".zattrs"), r#"{"members": 2}"#).unwrap();
+
+    // `time` is shared with the root group, `x` only used inside `ens`
+    let arrays = [
+        ("time", "[3]", r#"["time"]"#),
+        ("ens/x", "[2]", r#"["x"]"#),
+        ("ens/pr", "[3, 2]", r#"["time", "x"]"#),
+    ];
+    for (path, shape, dims) in arrays {
+        fs::create_dir_all(root.join(path)).unwrap();
+        fs::write(
+            root.join(path).join(".zarray"),
+            format!(
+                r#"{{"zarr_format": 2, "shape": {shape}, "chunks": {shape}, "dtype": "<f8", "compressor": null, "fill_value": null, "order": "C", "filters": null}}"#
+            ),
+        )
+        .unwrap();
+        fs::write(
+            root.join(path).join(".zattrs"),
+            format!(r#"{{"_ARRAY_DIMENSIONS": {dims}}}"#),
+        )
+        .unwrap();
+    }
+    let bytes: Vec<u8> = [5.0f64, 6.0].iter().flat_map(|v| v.to_le_bytes()).collect();
+    fs::write(root.join("ens").join("x").join("0"), bytes).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .arg(root.to_str().unwrap())
+        .arg("--no-color")
+        .arg("-c")
+        .output()
+        .expect("Failed to execute zarr-dump");
+    assert!(
+        output.status.success(),
+        "Stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(
+            "zarr store {\ndimensions:\n    time = 3 ;\nvariables:\n    double time(time) ;"
+        ),
+        "stdout: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains(
+            "group: ens {\n    dimensions:\n        x = 2 ;\n    variables:\n        double pr(time, x) ;"
+        ),
+        "stdout: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains("        double x(x) ;"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(stdout.contains("    // group attributes:\n        :members = 2 ;"));
+    // Coordinate data is printed in the data section of the group holding the variable
+    assert!(
+        stdout.contains("    data:\n\n     x = 5, 6 ;\n} // group ens"),
+        "stdout: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_cli_cdl_output() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let store = temp_dir.path().join("sample.zarr");
+    fs::create_dir_all(&store).unwrap();
+    create_sample_store(&store).expect("Failed to create sample store");
+    fs::write(
+        store.join("pressure").join(".zattrs"),
+        r#"{
+            "_ARRAY_DIMENSIONS": ["time", "level", "lat", "lon"],
+            "long_name": "Say \"hPa\"",
+            "valid_range": [0, 1100],
+            "scale_factor": 1,
+            "levels": [1000, 850.5, 500],
+            "flags": ["a", "b"],
+            "provenance": {"tool": "test", "version": 2},
+            "nothing": null
+        }"#,
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .arg(store.to_str().unwrap())
+        .arg("--cdl")
+        .arg("-s")
+        .output()
+        .expect("Failed to execute zarr-dump");
+    assert!(
+        output.status.success(),
+        "Stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // Nothing but CDL on stdout
+    assert!(
+        stdout.starts_with("netcdf sample {\n"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(stdout.ends_with("}\n"));
+    for expected in [
+        "\trecord = UNLIMITED ; // (0 currently)",
+        "\tfloat temperature(time, lat, lon) ;",
+        "\t\ttemperature:_FillValue = -9999.0f ;",
+        "\t\ttemperature:_ChunkSizes = 1, 180, 360 ;",
+        "\t\tpressure:long_name = \"Say \\\"hPa\\\"\" ;",
+        "\t\tpressure:valid_range = 0.0f, 1100.0f ;",
+        "\t\tpressure:scale_factor = 1.0 ;",
+        "\t\tpressure:levels = 1000.0, 850.5, 500.0 ;",
+        "\t\tstring pressure:flags = \"a\", \"b\" ;",
+        "\t\tpressure:provenance = \"{\\\"tool\\\":\\\"test\\\",\\\"version\\\":2.0}\" ;",
+        "\t\t// pressure:nothing = null ;",
+        "\t\t:title = \"Sample Climate Dataset\" ;",
+        "\t\t:_Format = \"netCDF-4\" ;",
+    ] {
+        assert!(
+            stdout.contains(expected),
+            "missing '{}' in: {}",
+            expected,
+            stdout
+        );
+    }
+    // Zarr-only details are left out
+    assert!(!stdout.contains("_ARRAY_DIMENSIONS"), "stdout: {}", stdout);
+    assert!(!stdout.contains("_Codecs"), "stdout: {}", stdout);
+    assert!(!stdout.contains('['), "stdout: {}", stdout);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .arg(store.to_str().unwrap())
+        .arg("--cdl")
+        .arg("--format")
+        .arg("json")
+        .output()
+        .expect("Failed to execute zarr-dump");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_cli_export_netcdf() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let store = temp_dir.path().join("store.zarr");
+    fs::create_dir_all(&store).unwrap();
+    create_data_store(&store).expect("Failed to create data store");
+    let output_path = temp_dir.path().join("out.nc");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .args(["export", store.to_str().unwrap(), "--to", "netcdf"])
+        .arg(&output_path)
+        .args(["-v", "tas", "--isel", "time=1:3", "--isel", "lat=::2"])
+        .output()
+        .expect("Failed to execute zarr-dump");
+    assert!(
+        output.status.success(),
+        "Stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Wrote 3 variable(s)"), "stdout: {}", stdout);
+
+    let bytes = fs::read(&output_path).unwrap();
+    assert_eq!(&bytes[..4], b"CDF\x02");
+    let find = |needle: &[u8]| bytes.windows(needle.len()).any(|w| w == needle);
+    assert!(find(b"_FillValue"));
+    assert!(!find(b"_ARRAY_DIMENSIONS"));
+
+    // Data follows the header in variable order: lat(3), tas(2, 3), time(2)
+    let values: Vec<f64> = bytes[bytes.len() - 88..]
+        .chunks(8)
+        .map(|c| f64::from_be_bytes(c.try_into().unwrap()))
+        .collect();
+    assert_eq!(
+        values,
+        [
+            -60.0, 0.0, 60.0, 5.0, 1.23456789, 9.0, 10.0, 12.0, 14.0, 1.0, 2.0
+        ]
+    );
+    assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 2);
+
+    // Misspelled dimensions are rejected without writing anything
+    let bad_path = temp_dir.path().join("bad.nc");
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .args(["export", store.to_str().unwrap(), "--to", "netcdf"])
+        .arg(&bad_path)
+        .args(["--isel", "depth=0"])
+        .output()
+        .expect("Failed to execute zarr-dump");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Dimension 'depth'"), "stderr: {}", stderr);
+    assert!(!bad_path.exists());
+}
+
+#[test]
+fn test_cli_export_arrays() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let store = temp_dir.path().join("store.zarr");
+    fs::create_dir_all(&store).unwrap();
+    create_data_store(&store).expect("Failed to create data store");
+    let export = |format: &str, file: &str, args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+            .args(["export", store.to_str().unwrap(), "--to", format])
+            .arg(temp_dir.path().join(file))
+            .args(args)
+            .output()
+            .expect("Failed to execute zarr-dump")
+    };
+
+    // CSV rows carry the coordinate values; fill values are left empty
+    let output = export("csv", "tas.csv", &["-v", "tas", "--isel", "time=1:3"]);
+    assert!(
+        output.status.success(),
+        "Stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let csv = fs::read_to_string(temp_dir.path().join("tas.csv")).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 11, "csv: {}", csv);
+    assert_eq!(lines[0], "time,lat,tas");
+    assert_eq!(lines[1], "1,-60,5");
+    assert_eq!(lines[3], "1,0,1.23456789");
+    assert_eq!(lines[4], "1,30,");
+    assert_eq!(lines[10], "2,60,14");
+
+    // NPY keeps the native type and the selected shape
+    let output = export("npy", "tas.npy", &["-v", "tas", "--isel", "lat=::2"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("(dtype <f8, shape (4, 3))"),
+        "stdout: {}",
+        stdout
+    );
+    let npy = fs::read(temp_dir.path().join("tas.npy")).unwrap();
+    assert_eq!(&npy[..8], b"\x93NUMPY\x01\x00");
+    let header_len = u16::from_le_bytes([npy[8], npy[9]]) as usize;
+    assert_eq!((10 + header_len) % 64, 0);
+    let header = String::from_utf8_lossy(&npy[10..10 + header_len]);
+    assert!(header.contains("'descr': '<f8'"), "header: {}", header);
+    assert!(header.contains("'shape': (4, 3)"), "header: {}", header);
+    assert_eq!(npy.len(), 10 + header_len + 12 * 8);
+
+    // Raw files are the bare little-endian values
+    let output = export("raw", "lat.raw", &["-v", "lat", "--isel", "lat=-2:"]);
+    assert!(output.status.success());
+    let raw = fs::read(temp_dir.path().join("lat.raw")).unwrap();
+    let values: Vec<f64> = raw
+        .chunks(8)
+        .map(|c| f64::from_le_bytes(c.try_into().unwrap()))
+        .collect();
+    assert_eq!(values, [30.0, 60.0]);
+
+    // One variable only
+    let output = export("csv", "all.csv", &[]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("single variable"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_cli_export_wide_integers() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let store = temp_dir.path().join("store.zarr");
+    fs::create_dir_all(&store).unwrap();
+    fs::write(store.join(".zgroup"), r#"{"zarr_format": 2}"#).unwrap();
+    let ids = [(1i64 << 53) + 1, -(1i64 << 53) - 1, i64::MAX];
+    let counts = [u64::MAX, (1u64 << 63) + 1, 0];
+    for (name, dtype, bytes) in [
+        (
+            "ids",
+            "<i8",
+            ids.iter()
+                .flat_map(|v| v.to_le_bytes())
+                .collect::<Vec<u8>>(),
+        ),
+        (
+            "counts",
+            "<u8",
+            counts.iter().flat_map(|v| v.to_le_bytes()).collect(),
+        ),
+    ] {
+        let dir = store.join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join(".zarray"),
+            format!(
+                r#"{{"zarr_format": 2, "shape": [3], "chunks": [3], "dtype": "{}", "compressor": null, "fill_value": 0, "order": "C", "filters": null}}"#,
+                dtype
+            ),
+        )
+        .unwrap();
+        fs::write(dir.join(".zattrs"), r#"{"_ARRAY_DIMENSIONS": ["x"]}"#).unwrap();
+        fs::write(dir.join("0"), bytes).unwrap();
+    }
+    let export = |format: &str, file: &str, args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+            .args(["export", store.to_str().unwrap(), "--to", format])
+            .arg(temp_dir.path().join(file))
+            .args(args)
+            .output()
+            .expect("Failed to execute zarr-dump");
+        assert!(
+            output.status.success(),
+            "Stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        fs::read(temp_dir.path().join(file)).unwrap()
+    };
+
+    // Values beyond 2^53 are written exactly, not rounded through f64
+    let raw = export("raw", "ids.raw", &["-v", "ids"]);
+    let values: Vec<i64> = raw
+        .chunks(8)
+        .map(|c| i64::from_le_bytes(c.try_into().unwrap()))
+        .collect();
+    assert_eq!(values, ids);
+
+    let npy = export("npy", "counts.npy", &["-v", "counts"]);
+    let values: Vec<u64> = npy[npy.len() - 24..]
+        .chunks(8)
+        .map(|c| u64::from_le_bytes(c.try_into().unwrap()))
+        .collect();
+    assert_eq!(values, counts);
+
+    // Variable order: counts, ids
+    let nc = export("netcdf", "out.nc", &[]);
+    assert_eq!(&nc[..4], b"CDF\x05");
+    let data = &nc[nc.len() - 48..];
+    let values: Vec<u64> = data[..24]
+        .chunks(8)
+        .map(|c| u64::from_be_bytes(c.try_into().unwrap()))
+        .collect();
+    assert_eq!(values, counts);
+    let values: Vec<i64> = data[24..]
+        .chunks(8)
+        .map(|c| i64::from_be_bytes(c.try_into().unwrap()))
+        .collect();
+    assert_eq!(values, ids);
+}
+
+#[test]
+fn test_cli_stats() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    create_data_store(temp_dir.path()).expect("Failed to create data store");
+    // Packed values: the statistics are of the unpacked data
+    fs::write(
+        temp_dir.path().join("lat").join(".zattrs"),
+        r#"{"_ARRAY_DIMENSIONS": ["lat"], "scale_factor": 0.5, "add_offset": 100, "valid_max": 30}"#,
+    )
+    .unwrap();
+
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+            .arg("stats")
+            .arg(temp_dir.path())
+            .args(args)
+            .output()
+            .expect("Failed to execute zarr-dump")
+    };
+
+    let output = run(&["--format", "json", "--isel", "time=0:2"]);
+    assert!(
+        output.status.success(),
+        "Stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let tas = &report["tas"];
+    assert_eq!(tas["valid"], 9);
+    assert_eq!(tas["missing"], 1);
+    assert_eq!(tas["nan"], 0);
+    assert_eq!(tas["min"], 0.0);
+    assert_eq!(tas["max"], 9.0);
+    assert_eq!(report["time"]["valid"], 2);
+    // -60 .. 30 are valid, 60 is above valid_max
+    let lat = &report["lat"];
+    assert_eq!(lat["valid"], 4);
+    assert_eq!(lat["missing"], 1);
+    assert_eq!(lat["min"], 70.0);
+    assert_eq!(lat["max"], 115.0);
+    assert_eq!(lat["mean"], 92.5);
+
+    let output = run(&["-v", "time"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("variable  valid  missing  nan  min  max  mean      std"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains("time          4        0    0    0    3   1.5  1.11803"),
+        "stdout: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_cli_chunks_report() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let root = temp_dir.path();
+    fs::write(root.join(".zgroup"), r#"{"zarr_format": 2}"#).unwrap();
+    fs::create_dir_all(root.join("grid")).unwrap();
+    fs::write(
+        root.join("grid").join(".zarray"),
+        r#"{"zarr_format": 2, "shape": [4, 4], "chunks": [2, 2], "dtype": "<f8", "compressor": null, "fill_value": 0.0, "order": "C", "filters": null, "dimension_separator": "/"}"#,
+    )
+    .unwrap();
+    // Three of the four chunks, nested by the "/" separator; the last one is truncated
+    for (key, size) in [("0/0", 32), ("0/1", 32), ("1/1", 20)] {
+        let (row, column) = key.split_once('/').unwrap();
+        fs::create_dir_all(root.join("grid").join(row)).unwrap();
+        fs::write(root.join("grid").join(row).join(column), vec![0u8; size]).unwrap();
+    }
+
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+            .arg("chunks")
+            .arg(root)
+            .args(args)
+            .output()
+            .expect("Failed to execute zarr-dump")
+    };
+
+    let output = run(&["--format", "json"]);
+    assert!(
+        output.status.success(),
+        "Stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let grid = &report["grid"];
+    assert_eq!(grid["expected_chunks"], 4);
+    assert_eq!(grid["stored_chunks"], 3);
+    assert_eq!(grid["missing_chunks"], 1);
+    assert_eq!(grid["stored_bytes"], 84);
+    assert_eq!(grid["uncompressed_bytes"], 128);
+    assert_eq!(grid["smallest_chunk"]["key"], "1/1");
+    assert_eq!(grid["largest_chunk"]["bytes"], 32);
+
+    let output = run(&[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("grid         3/4    84 B         128 B   1.14  20 B (1/1)  32 B (0/0)"),
+        "stdout: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_cli_verify() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let root = temp_dir.path();
+    fs::write(root.join(".zgroup"), r#"{"zarr_format": 2}"#).unwrap();
+
+    // v2 array with "/" separated keys: one chunk missing, one truncated, one outside the grid
+    fs::create_dir_all(root.join("grid")).unwrap();
+    fs::write(
+        root.join("grid").join(".zarray"),
+        r#"{"zarr_format": 2, "shape": [4, 4], "chunks": [2, 2], "dtype": "<f8", "compressor": null, "fill_value": 0.0, "order": "C", "filters": null, "dimension_separator": "/"}"#,
+    )
+    .unwrap();
+    for (key, size) in [("0/0", 32), ("0/1", 32), ("1/1", 20), ("7/0", 32)] {
+        let (row, column) = key.split_once('/').unwrap();
+        fs::create_dir_all(root.join("grid").join(row)).unwrap();
+        fs::write(root.join("grid").join(row).join(column), vec![0u8; size]).unwrap();
+    }
+
+    // gzip compressed array with an undecodable chunk
+    fs::create_dir_all(root.join("packed")).unwrap();
+    fs::write(
+        root.join("packed").join(".zarray"),
+        r#"{"zarr_format": 2, "shape": [2], "chunks": [2], "dtype": "<f8", "compressor": {"id": "gzip", "level": 1}, "fill_value": 0.0, "order": "C", "filters": null}"#,
+    )
+    .unwrap();
+    fs::write(root.join("packed").join("0"), b"not gzip").unwrap();
+
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+            .arg("verify")
+            .arg(root)
+            .args(args)
+            .output()
+            .expect("Failed to execute zarr-dump")
+    };
+
+    let output = run(&["--format", "json"]);
+    assert!(!output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let grid = &report["grid"];
+    assert_eq!(grid["expected_chunks"], 4);
+    assert_eq!(grid["stored_chunks"], 3);
+    assert_eq!(grid["missing"], serde_json::json!(["1/0"]));
+    assert_eq!(grid["orphans"], serde_json::json!(["7/0"]));
+    assert_eq!(grid["wrong_size"][0]["key"], "1/1");
+    assert_eq!(report["packed"]["corrupt"][0]["key"], "0");
+
+    let output = run(&["-v", "grid"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("grid: 3/4 chunks stored, FAILED"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(stdout.contains("  orphaned (1): 7/0"), "stdout: {}", stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Store verification failed"),
+        "stderr: {}",
+        stderr
+    );
+
+    // Missing chunks alone only fail with --require-all-chunks (v3 "c/" keys)
+    let v3 = root.join("v3.zarr");
+    fs::create_dir_all(v3.join("x").join("c")).unwrap();
+    fs::write(
+        v3.join("zarr.json"),
+        r#"{"zarr_format": 3, "node_type": "group", "attributes": {}}"#,
+    )
+    .unwrap();
+    fs::write(
+        v3.join("x").join("zarr.json"),
+        r#"{"zarr_format": 3, "node_type": "array", "shape": [4], "data_type": "int32",
+            "chunk_grid": {"name": "regular", "configuration": {"chunk_shape": [2]}},
+            "chunk_key_encoding": {"name": "default", "configuration": {"separator": "/"}},
+            "fill_value": 0, "codecs": [{"name": "bytes", "configuration": {"endian": "little"}}],
+            "dimension_names": ["x"]}"#,
+    )
+    .unwrap();
+    fs::write(v3.join("x").join("c").join("0"), [0u8; 8]).unwrap();
+    let verify_v3 = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+            .arg("verify")
+            .arg(&v3)
+            .args(args)
+            .output()
+            .expect("Failed to execute zarr-dump")
+    };
+    let output = verify_v3(&[]);
+    assert!(
+        output.status.success(),
+        "Stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("x: 1/2 chunks stored, ok"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(stdout.contains("  missing (1): c/1"), "stdout: {}", stdout);
+    assert!(!verify_v3(&["--require-all-chunks"]).status.success());
+}
+
+#[test]
+fn test_cli_diff() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let (old, new) = (temp_dir.path().join("old"), temp_dir.path().join("new"));
+    for root in [&old, &new] {
+        fs::create_dir_all(root).unwrap();
+        create_data_store(root).unwrap();
+    }
+
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+            .arg("diff")
+            .arg(&old)
+            .arg(&new)
+            .args(args)
+            .output()
+            .expect("Failed to execute zarr-dump")
+    };
+
+    let output = run(&["--data"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Stores are identical"),
+        "stdout: {}",
+        stdout
+    );
+
+    // Change an attribute and one data value (tas[1, 4] from 9 to 9.5)
+    fs::write(
+        new.join("tas").join(".zattrs"),
+        r#"{"_ARRAY_DIMENSIONS": ["time", "lat"], "units": "K"}"#,
+    )
+    .unwrap();
+    let mut bytes = fs::read(new.join("tas").join("0.0")).unwrap();
+    bytes[72..80].copy_from_slice(&9.5f64.to_le_bytes());
+    fs::write(new.join("tas").join("0.0"), bytes).unwrap();
+
+    let output = run(&[]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("+ tas: attribute 'units' = \"K\"\n\n1 difference(s)"),
+        "stdout: {}",
+        stdout
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Stores differ"), "stderr: {}", stderr);
+
+    let output = run(&["--data", "--format", "json"]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report.as_array().unwrap().len(), 2);
+    assert_eq!(report[0]["item"], "attribute 'units'");
+    assert_eq!(report[1]["item"], "data");
+    assert_eq!(
+        report[1]["new"],
+        "1 value(s) differ, max abs difference 0.5, first at [1, 4]"
+    );
+
+    // Within tolerance only the attribute differs
+    let output = run(&["--data", "--atol", "0.5", "--format", "json"]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report.as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn test_cli_consolidate() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let root = temp_dir.path();
+    create_data_store(root).unwrap();
+
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+            .arg("consolidate")
+            .arg(root)
+            .args(args)
+            .output()
+            .expect("Failed to execute zarr-dump")
+    };
+
+    let output = run(&["--check"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("has no consolidated metadata"),
+        "stderr: {}",
+        stderr
+    );
+
+    let output = run(&[]);
+    assert!(output.status.success());
+    let zmetadata: serde_json::Value =
+        serde_json::from_slice(&fs::read(root.join(".zmetadata")).unwrap()).unwrap();
+    assert_eq!(zmetadata["zarr_consolidated_format"], 1);
+    assert_eq!(zmetadata["metadata"]["tas/.zarray"]["shape"][1], 5);
+    assert_eq!(
+        zmetadata["metadata"]["tas/.zattrs"]["_ARRAY_DIMENSIONS"][0],
+        "time"
+    );
+
+    let output = run(&["--check"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Consolidated metadata is up to date (7 entries)"),
+        "stdout: {}",
+        stdout
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .arg(root)
+        .output()
+        .expect("Failed to execute zarr-dump");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Loaded consolidated metadata from .zmetadata"),
+        "stdout: {}",
+        stdout
+    );
+
+    // Grow an array after consolidating
+    let zarray = fs::read_to_string(root.join("time").join(".zarray")).unwrap();
+    fs::write(
+        root.join("time").join(".zarray"),
+        zarray.replace("[4]", "[6]"),
+    )
+    .unwrap();
+    let output = run(&["--check"]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("time/.zarray: differs from the store\n"),
+        "stdout: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_cli_lint_consolidated() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let root = temp_dir.path();
+    create_data_store(root).unwrap();
+    let zarr_dump = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+            .args(args)
+            .arg(root)
+            .output()
+            .expect("Failed to execute zarr-dump")
+    };
+
+    let output = zarr_dump(&["lint"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("has no consolidated metadata to check"),
+        "stderr: {}",
+        stderr
+    );
+
+    assert!(zarr_dump(&["consolidate"]).status.success());
+    let output = zarr_dump(&["lint"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Consolidated metadata matches the store"),
+        "stdout: {}",
+        stdout
+    );
+
+    // Append to time, edit an attribute and add an array without consolidating again
+    let zarray = fs::read_to_string(root.join("time").join(".zarray")).unwrap();
+    fs::write(
+        root.join("time").join(".zarray"),
+        zarray.replace("[4]", "[6]"),
+    )
+    .unwrap();
+    fs::write(
+        root.join("tas").join(".zattrs"),
+        r#"{"_ARRAY_DIMENSIONS": ["time", "lat"], "units": "K"}"#,
+    )
+    .unwrap();
+    fs::create_dir_all(root.join("pr")).unwrap();
+    fs::write(root.join("pr").join(".zarray"), zarray).unwrap();
+
+    let output = zarr_dump(&["lint", "--format", "json"]);
+    assert!(!output.status.success());
+    let issues: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let codes: Vec<(&str, &str)> = issues
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|i| (i["path"].as_str().unwrap(), i["code"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        codes,
+        [
+            ("pr", "CONSOLIDATED-NODE-MISSING"),
+            ("tas", "CONSOLIDATED-ATTRIBUTE-DRIFT"),
+            // The chunks were rewritten as well
+            ("time", "CONSOLIDATED-METADATA-MISMATCH"),
+            ("time", "CONSOLIDATED-SHAPE-MISMATCH"),
+        ]
+    );
+
+    // The dump still shows the consolidated metadata, but warns and fails
+    let output = zarr_dump(&["--verify-consolidated", "--no-color"]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("time = 4 ;"), "stdout: {}", stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(
+            "Warning: time: [CONSOLIDATED-SHAPE-MISMATCH] shape is [4] in the consolidated metadata but [6] on disk"
+        ),
+        "stderr: {}",
+        stderr
+    );
+    assert!(
+        stderr.contains("Consolidated metadata does not match the store (4 issue(s))"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_cli_attrs() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let root = temp_dir.path();
+    create_data_store(root).unwrap();
+    let attrs = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+            .arg("attrs")
+            .arg(args[0])
+            .arg(root)
+            .args(&args[1..])
+            .output()
+            .expect("Failed to execute zarr-dump")
+    };
+    let read_json = |key: &str| -> serde_json::Value {
+        serde_json::from_slice(&fs::read(root.join(key)).unwrap()).unwrap()
+    };
+    assert!(
+        Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+            .args(["consolidate".as_ref(), root.as_os_str()])
+            .status()
+            .unwrap()
+            .success()
+    );
+
+    // A dry run shows the change without writing it
+    let output = attrs(&["set", "tas:units", "K", "--dry-run"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("+ tas: attribute 'units' = \"K\"\nDry run:"),
+        "stdout: {}",
+        stdout
+    );
+    assert!(read_json("tas/.zattrs").get("units").is_none());
+
+    let output = attrs(&["set", "tas:units", "K"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Refreshed consolidated metadata"),
+        "stdout: {}",
+        stdout
+    );
+    assert_eq!(read_json("tas/.zattrs")["units"], "K");
+    assert_eq!(
+        read_json(".zmetadata")["metadata"]["tas/.zattrs"]["units"],
+        "K"
+    );
+
+    // Typed values, global attributes (no .zattrs yet), append, rename and delete
+    assert!(
+        attrs(&["set", "tas:valid_range", "-50,50", "-t", "float"])
+            .status
+            .success()
+    );
+    assert!(attrs(&["set", ":title", "Run 1"]).status.success());
+    assert!(attrs(&["append", ":title", " (edited)"]).status.success());
+    assert!(attrs(&["set", "time:axis", "T"]).status.success());
+    assert!(attrs(&["rename", "time:axis", "AXIS"]).status.success());
+    assert!(attrs(&["set", "lat:flag", "1"]).status.success());
+    assert!(attrs(&["delete", "lat:flag"]).status.success());
+
+    assert_eq!(
+        read_json("tas/.zattrs")["valid_range"],
+        serde_json::json!([-50.0, 50.0])
+    );
+    assert_eq!(read_json(".zattrs")["title"], "Run 1 (edited)");
+    assert_eq!(read_json("time/.zattrs")["AXIS"], "T");
+    assert!(read_json("lat/.zattrs").get("flag").is_none());
+
+    let output = attrs(&["delete", "tas:missing"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Attribute 'missing' not found on 'tas'"),
+        "stderr: {}",
+        stderr
+    );
+
+    // Nodes are found on disk even when the consolidated metadata does not list them yet
+    fs::create_dir_all(root.join("pr")).unwrap();
+    for file in [".zarray", ".zattrs"] {
+        fs::copy(root.join("lat").join(file), root.join("pr").join(file)).unwrap();
+    }
+    let output = attrs(&["set", "pr:units", "mm"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(read_json("pr/.zattrs")["units"], "mm");
+    assert!(!attrs(&["set", "nothing:units", "mm"]).status.success());
+
+    // The consolidated metadata followed every edit
+    let output = Command::new(env!("CARGO_BIN_EXE_zarr-dump"))
+        .arg("lint")
+        .arg(root)
+        .output()
+        .expect("Failed to execute zarr-dump");
+    assert!(
+        output.status.success(),
+        "stdout: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}